@@ -1,54 +1,45 @@
 use std::time::Duration;
 
 use criterion::{criterion_group, criterion_main, Criterion};
-use order_processor::runs;
+use order_processor::{configs::Configs, runs::Pipeline};
 
-#[cfg(all(feature = "error_handling", feature = "inferring"))]
-fn run_inferring(c: &mut Criterion) {
-    c.bench_function("run_inferring", |b| {
+fn run_pipeline(c: &mut Criterion) {
+    let configs = Configs::load_from_file("configs.txt").expect("Invalid configs.txt");
+    let pipeline = Pipeline::default();
+    c.bench_function("run_pipeline", |b| {
         b.iter(|| {
-            runs::run_inferring();
+            pipeline.run(&configs);
         });
     });
 }
 
-#[cfg(all(feature = "error_handling", not(feature = "inferring")))]
-fn run_no_inferring(c: &mut Criterion) {
-    c.bench_function("run_no_inferring", |b| {
+fn run_parse_only(c: &mut Criterion) {
+    let mut configs = Configs::load_from_file("configs.txt").expect("Invalid configs.txt");
+    configs.archive_index_path = None;
+    let pipeline = Pipeline::parse_only();
+    c.bench_function("run_parse_only", |b| {
         b.iter(|| {
-            runs::run_no_inferring();
+            pipeline.parse_archive(&configs);
         });
     });
 }
 
-#[cfg(not(feature = "error_handling"))]
-fn run_optimized(c: &mut Criterion) {
-    c.bench_function("run_optimized", |b| {
+fn run_parse_indexed(c: &mut Criterion) {
+    let configs = Configs::load_from_file("configs.txt").expect("Invalid configs.txt");
+    let pipeline = Pipeline::parse_only();
+    // the first run fills the index, the measured ones only check the files
+    pipeline.parse_archive(&configs);
+    c.bench_function("run_parse_indexed", |b| {
         b.iter(|| {
-            runs::run_optimized();
+            pipeline.parse_archive(&configs);
         });
     });
 }
 
-#[cfg(all(feature = "error_handling", feature = "inferring"))]
 criterion_group! {
     name = benches;
     config = Criterion::default().measurement_time(Duration::new(6,500));
-    targets = run_inferring
-}
-
-#[cfg(all(feature = "error_handling", not(feature = "inferring")))]
-criterion_group! {
-    name = benches;
-    config = Criterion::default().measurement_time(Duration::new(6,500));
-    targets = run_no_inferring
-}
-
-#[cfg(not(feature = "error_handling"))]
-criterion_group! {
-    name = benches;
-    config = Criterion::default().measurement_time(Duration::new(7,0));
-    targets = run_optimized
+    targets = run_pipeline, run_parse_only, run_parse_indexed
 }
 
 criterion_main!(benches);
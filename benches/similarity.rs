@@ -0,0 +1,73 @@
+use std::time::Duration;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use order_processor::{
+    similarity::{DescriptionIndex, Similarity},
+    structs::sticker::Sticker,
+};
+
+// the size of a large archive, one sticker per file
+const ARCHIVE_FILES: usize = 100_000;
+
+const WORDS: &[&str] = &[
+    "AV", "CLEAN", "CREAM", "GEL", "TUBE", "SOAP", "LIQUID", "HAND", "BODY", "LOTION", "ALOE",
+    "VERA", "LEMON", "MINT", "FRESH", "BABY", "SHAMPOO", "BALM", "MILK", "ROSE", "OIL", "SPRAY",
+    "FOAM", "WASH", "CARE", "SOFT", "PURE", "DRY", "SKIN", "NIGHT", "DAY", "KIDS",
+];
+
+/// Stickers with made up descriptions of three to five words
+fn synthetic_stickers() -> Vec<Sticker> {
+    (0..ARCHIVE_FILES)
+        .flat_map(|i| {
+            let words: Vec<&str> = (0..3 + i % 3)
+                .map(|n| WORDS[(i / WORDS.len().pow(n as u32) + n * 7) % WORDS.len()])
+                .collect();
+            Sticker::parse_stickers(&format!("{}_{}_60X40_PVC", 100_000 + i, words.join(" ")))
+                .unwrap()
+        })
+        .collect()
+}
+
+const SIMILARITIES: [Similarity; 4] = [
+    Similarity::Levenshtein,
+    Similarity::TokenSet,
+    Similarity::JaroWinkler,
+    Similarity::Trigram,
+];
+
+fn build_index(c: &mut Criterion) {
+    let stickers = synthetic_stickers();
+    let mut group = c.benchmark_group("similarity_build_index");
+    group.sample_size(10);
+    for similarity in SIMILARITIES {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(similarity),
+            &similarity,
+            |b, &similarity| b.iter(|| DescriptionIndex::new(&stickers, similarity)),
+        );
+    }
+    group.finish();
+}
+
+fn search(c: &mut Criterion) {
+    let stickers = synthetic_stickers();
+    let mut group = c.benchmark_group("similarity_search");
+    group.sample_size(10);
+    for similarity in SIMILARITIES {
+        let index = DescriptionIndex::new(&stickers, similarity);
+        group.bench_with_input(
+            BenchmarkId::from_parameter(similarity),
+            &index,
+            |b, index| b.iter(|| index.similar_codes("GEL TUBE AV CLEAN CREM", 0.9)),
+        );
+    }
+    group.finish();
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().measurement_time(Duration::new(6, 500));
+    targets = build_index, search
+}
+
+criterion_main!(benches);
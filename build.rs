@@ -7,6 +7,7 @@ use std::{
 
 fn main() {
     let input_path = "./dimensions.txt";
+    println!("cargo:rerun-if-changed={input_path}");
 
     let out_dir = env::var("OUT_DIR").expect("OUT_DIR not set");
     let output_path = Path::new(&out_dir).join("generated_dimensions.rs");
@@ -16,14 +17,26 @@ fn main() {
 
     let mut output = String::from("pub const OFFICIAL_DIMENSIONS: &[Dimensions] = &[\n");
 
-    for line in reader.lines() {
+    for (index, line) in reader.lines().enumerate() {
         let line = line.expect("Could not read line");
-        if let Some((w, h)) = line.trim().split_once('x') {
-            output.push_str(&format!(
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let dimensions = line.split_once('x').and_then(|(w, h)| {
+            Some((w.trim().parse::<u32>().ok()?, h.trim().parse::<u32>().ok()?))
+        });
+        match dimensions {
+            Some((w, h)) if w > 0 && h > 0 => output.push_str(&format!(
                 "    Dimensions {{ width: {}, height: {} }},\n",
-                w.trim(),
-                h.trim()
-            ));
+                w, h
+            )),
+            _ => panic!(
+                "dimensions.txt line {}: expected 'WxH' with positive numbers, got '{}'",
+                index + 1,
+                line
+            ),
         }
     }
 
@@ -0,0 +1,228 @@
+use crate::{
+    configs::{ConfigOverrides, Configs},
+    fix_names, resolve,
+    runs::{self, Pipeline},
+    structs::{
+        code_normalizer::{self, CodeNormalizer},
+        dimensions::{self, DimensionsCatalog},
+        vocabulary::{self, Vocabulary},
+    },
+    watch,
+};
+use clap::{Args, Parser, Subcommand};
+use std::{path::PathBuf, process::ExitCode, time::Duration};
+
+#[derive(Parser, Debug)]
+#[command(
+    version,
+    about = "Matches .cdr sticker files from the archive against Excel orders"
+)]
+pub struct Cli {
+    /// Config file to read
+    #[arg(long, global = true, default_value = "configs.txt")]
+    pub config: PathBuf,
+
+    /// Also write a JSON report of the run to this file, for `process` and `report`
+    #[arg(long, global = true, value_name = "PATH")]
+    pub report_json: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub overrides: ConfigOverrides,
+
+    #[command(flatten)]
+    pub pipeline: PipelineArgs,
+
+    /// Defaults to `process` when omitted
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Parse the archive and the orders and write the production outputs
+    Process,
+    /// Only list the stickers parsed from the archive
+    Scan,
+    /// Only print the archive file names that could not be parsed
+    Validate,
+    /// Only write the material report
+    Report,
+    /// Load the config, report any problems and print the values in use
+    CheckConfig,
+    /// Write a plan that renames archive files to names that parse, or apply it
+    FixNames {
+        /// Rename the files of the plan instead of writing it
+        #[arg(long)]
+        apply: bool,
+        /// The plan file, a CSV of `from,to,fixes`
+        #[arg(long, default_value = fix_names::DEFAULT_PLAN)]
+        plan: PathBuf,
+    },
+    /// Walk through the missing orders and save how each one is resolved
+    Resolve,
+    /// Process again whenever the archive or the order file changes
+    Watch {
+        /// Milliseconds without changes to wait for before processing
+        #[arg(long, default_value_t = watch::DEFAULT_DEBOUNCE_MS)]
+        debounce_ms: u64,
+    },
+}
+
+/// Runtime switches for the pipeline steps, unset ones use the cargo feature defaults
+#[derive(Args, Debug, Default, Clone, Copy)]
+pub struct PipelineArgs {
+    /// Infer missing codes from similar descriptions
+    #[arg(long, global = true, value_name = "BOOL")]
+    pub inference: Option<bool>,
+    /// Print the parse errors similar to an order
+    #[arg(long, global = true, value_name = "BOOL")]
+    pub error_report: Option<bool>,
+    /// Print all parse errors grouped by type
+    #[arg(long, global = true, value_name = "BOOL")]
+    pub grouped_errors: Option<bool>,
+    /// Write the material report
+    #[arg(long, global = true, value_name = "BOOL")]
+    pub material_report: Option<bool>,
+    /// Parse the whole archive again instead of reusing the archive index
+    #[arg(long, global = true)]
+    pub rebuild_index: bool,
+}
+
+impl Command {
+    /// Whether the command reads the orders, the others work without an `order` entry
+    pub fn reads_orders(&self) -> bool {
+        matches!(
+            self,
+            Command::Process | Command::Report | Command::Resolve | Command::Watch { .. }
+        )
+    }
+}
+
+impl PipelineArgs {
+    pub fn apply(&self, mut pipeline: Pipeline) -> Pipeline {
+        if let Some(enabled) = self.inference {
+            pipeline = pipeline.inference(enabled);
+        }
+        if let Some(enabled) = self.error_report {
+            pipeline = pipeline.error_report(enabled);
+        }
+        if let Some(enabled) = self.grouped_errors {
+            pipeline = pipeline.grouped_errors(enabled);
+        }
+        if let Some(enabled) = self.material_report {
+            pipeline = pipeline.material_report(enabled);
+        }
+        pipeline.rebuild_index(self.rebuild_index)
+    }
+}
+
+impl Cli {
+    pub fn run(&self) -> ExitCode {
+        let configs = match Configs::load(&self.config, &self.overrides) {
+            Ok(configs) => configs,
+            Err(e) => {
+                eprintln!("Invalid configs: {e}");
+                return ExitCode::FAILURE;
+            }
+        };
+
+        let command = self.command.clone().unwrap_or(Command::Process);
+        if command.reads_orders() {
+            if let Err(e) = configs.check_orders() {
+                eprintln!("Invalid configs: {e}");
+                return ExitCode::FAILURE;
+            }
+        }
+
+        let catalog = match &configs.dimensions_path {
+            Some(path) => match DimensionsCatalog::load(path) {
+                Ok((catalog, duplicates)) => {
+                    for duplicate in duplicates {
+                        println!("!!!WARNING: DUPLICATE DIMENSIONS, {duplicate}!!!");
+                    }
+                    catalog
+                }
+                Err(e) => {
+                    eprintln!("Invalid dimensions: {e}");
+                    return ExitCode::FAILURE;
+                }
+            },
+            None => DimensionsCatalog::compiled(),
+        };
+        // nothing is installed before this point, so this cannot fail
+        let _ =
+            dimensions::install_catalog(catalog.with_tolerance(configs.dimensions_tolerance_mm));
+
+        if let Some(path) = &configs.vocabulary_path {
+            match Vocabulary::load(path) {
+                Ok(loaded) => {
+                    let _ = vocabulary::install_vocabulary(loaded);
+                }
+                Err(e) => {
+                    eprintln!("Invalid vocabulary: {e}");
+                    return ExitCode::FAILURE;
+                }
+            }
+        }
+
+        let _ =
+            code_normalizer::install_code_normalizer(CodeNormalizer::new(&configs.code_prefixes));
+
+        let pipeline = self.pipeline.apply(Pipeline::default());
+
+        match command {
+            Command::Process => {
+                pipeline.run_reporting(&configs, self.report_json.as_deref());
+            }
+            Command::Scan => runs::scan(&pipeline, &configs),
+            Command::Validate => runs::validate(&pipeline, &configs),
+            Command::Report => {
+                pipeline
+                    .error_report(false)
+                    .grouped_errors(false)
+                    .production_tables(false)
+                    .material_report(true)
+                    .run_reporting(&configs, self.report_json.as_deref());
+            }
+            Command::CheckConfig => {
+                println!("{configs}");
+                println!("{} official dimensions", dimensions::catalog().len());
+                let vocabulary = vocabulary::vocabulary();
+                println!(
+                    "{} materials in groups {}, {} colors",
+                    vocabulary.materials().count(),
+                    vocabulary.groups().join(", "),
+                    vocabulary.colors().count()
+                );
+                if configs.code_aliases_path.is_some() {
+                    println!("{} code aliases", runs::load_code_aliases(&configs).len());
+                }
+                if configs.overrides_path.is_some() {
+                    println!("{} overrides", runs::load_overrides(&configs).len());
+                }
+            }
+            Command::FixNames { apply, plan } => {
+                if let Err(e) = fix_names::fix_names(&configs, &plan, apply) {
+                    eprintln!("Failed to fix names: {e}");
+                    return ExitCode::FAILURE;
+                }
+            }
+            Command::Resolve => {
+                if let Err(e) = resolve::resolve(&pipeline, &configs) {
+                    eprintln!("Failed to resolve orders: {e}");
+                    return ExitCode::FAILURE;
+                }
+            }
+            Command::Watch { debounce_ms } => {
+                if let Err(e) =
+                    watch::watch(&pipeline, &configs, Duration::from_millis(debounce_ms))
+                {
+                    eprintln!("{e}");
+                    return ExitCode::FAILURE;
+                }
+            }
+        }
+
+        ExitCode::SUCCESS
+    }
+}
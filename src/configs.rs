@@ -1,86 +1,926 @@
+use clap::{Args, ValueEnum};
+use std::fmt;
 use std::fs;
 use std::io::{self, BufRead};
 use std::path::{Path, PathBuf};
+use strsim::normalized_levenshtein;
+
+use crate::excel::is_output_file;
+use crate::order_source::{SheetSelection, ORDER_EXTENSIONS};
+use crate::similarity::Similarity;
+use crate::structs::dimensions::DEFAULT_TOLERANCE_MM;
 
 pub struct Configs {
     pub archive_path: PathBuf,
-    pub order_path: PathBuf,
+    /// The `order` entries, their orders are merged into one run
+    pub orders: Vec<OrderInput>,
+    /// Sheets read from entries that do not name their own
     pub sheet_name: Option<String>,
     pub order_amount_column_name: Option<String>,
+    /// 1-based row of the order table header, detected from the code header when `None`
+    pub header_row: Option<usize>,
+    pub code_column: Option<ColumnRef>,
+    pub amount_column: Option<ColumnRef>,
+    pub description_column: Option<ColumnRef>,
+    /// Column of the ordered variants, read from the end of the description when `None`
+    pub variant_column: Option<ColumnRef>,
+    /// Prefixes stripped from the codes of orders and file names, e.g. `BG-`
+    pub code_prefixes: Vec<String>,
+    /// How many rows are searched for the code header
+    pub header_scan_rows: usize,
+    /// How many columns are searched for the header keywords, 6 for the code and 10 for the
+    /// other columns when `None`
+    pub header_scan_columns: Option<usize>,
     pub inferring_levenshtein_distance: f64,
     pub error_output_levenshtein_distance: f64,
+    /// How inference compares the descriptions of names without a code
+    pub inferring_similarity: Similarity,
+    pub inferring_token_set_threshold: f64,
+    pub inferring_jaro_winkler_threshold: f64,
+    pub inferring_trigram_threshold: f64,
+    pub ambiguous_inferences: AmbiguousInferences,
+    pub dimensions_path: Option<PathBuf>,
+    pub dimensions_tolerance_mm: u32,
+    pub vocabulary_path: Option<PathBuf>,
+    /// Old codes and the codes that replaced them, `OLD -> NEW` per line
+    pub code_aliases_path: Option<PathBuf>,
+    /// Stickers given by hand for archive files whose names do not parse
+    pub overrides_path: Option<PathBuf>,
+    pub non_ok_stickers: NonOkStickers,
+    /// Where the parsed archive is kept between runs, `None` parses the whole archive every run
+    pub archive_index_path: Option<PathBuf>,
+    /// Where each `order` entry came from, for `check_orders`
+    order_locations: Vec<Location>,
+}
+
+/// One `order` entry, a file or a glob pattern with the sheets to read from the matched files.
+///
+/// Written as `path`, or `path@Sheet1|Sheet2` and `path@*` to pick the sheets.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrderInput {
+    pub pattern: PathBuf,
+    /// `None` reads the sheets of `sheet_name`
+    pub sheets: Option<SheetSelection>,
+}
+
+impl OrderInput {
+    pub fn parse(entry: &str) -> Self {
+        match entry.rsplit_once('@') {
+            Some((pattern, sheets)) => Self {
+                pattern: pattern.trim().into(),
+                sheets: Some(SheetSelection::parse(sheets)),
+            },
+            None => Self {
+                pattern: entry.trim().into(),
+                sheets: None,
+            },
+        }
+    }
+
+    pub fn is_glob(&self) -> bool {
+        has_wildcard(&self.pattern.to_string_lossy())
+    }
+
+    /// The order files of the entry, sorted.
+    ///
+    /// A plain path is returned as is. A glob skips Excel lock files and the workbooks
+    /// written by earlier runs.
+    pub fn files(&self) -> Vec<PathBuf> {
+        if !self.is_glob() {
+            return vec![self.pattern.clone()];
+        }
+        let Ok(paths) = glob::glob(&self.pattern.to_string_lossy()) else {
+            return Vec::new();
+        };
+        let mut files: Vec<PathBuf> = paths
+            .filter_map(Result::ok)
+            .filter(|path| is_order_file(path) && !is_generated_file(path))
+            .collect();
+        files.sort();
+        files
+    }
+
+    /// The pattern split into its deepest directory without wildcards and the rest
+    pub fn split_at_base_dir(&self) -> (PathBuf, PathBuf) {
+        let components: Vec<_> = self.pattern.components().collect();
+        // the file name always belongs to the rest, even without wildcards
+        let literal = components[..components.len().saturating_sub(1)]
+            .iter()
+            .take_while(|component| !has_wildcard(&component.as_os_str().to_string_lossy()))
+            .count();
+        let base: PathBuf = components[..literal].iter().collect();
+        let rest: PathBuf = components[literal..].iter().collect();
+        if base.as_os_str().is_empty() {
+            (PathBuf::from("."), rest)
+        } else {
+            (base, rest)
+        }
+    }
+}
+
+impl fmt::Display for OrderInput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.pattern.display())?;
+        if let Some(sheets) = &self.sheets {
+            write!(f, "@{sheets}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A column of the order sheet, by letter or by header text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ColumnRef {
+    /// 0-based index of a column letter like `L`
+    Index(usize),
+    /// Text the header cell contains, case-insensitive
+    Header(String),
+}
+
+impl ColumnRef {
+    /// One to three uppercase letters are a column letter, anything else header text.
+    ///
+    /// Quotes force header text, e.g. `"QTY"`.
+    pub fn parse(value: &str) -> Self {
+        let value = value.trim();
+        if let Some(text) = value
+            .strip_prefix('"')
+            .and_then(|rest| rest.strip_suffix('"'))
+        {
+            return ColumnRef::Header(text.to_string());
+        }
+        match column_index(value) {
+            Some(index) => ColumnRef::Index(index),
+            None => ColumnRef::Header(value.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for ColumnRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColumnRef::Index(index) => write!(f, "{}", column_letter(*index)),
+            ColumnRef::Header(text) if column_index(text).is_some() => write!(f, "\"{text}\""),
+            ColumnRef::Header(text) => write!(f, "{text}"),
+        }
+    }
+}
+
+/// The 0-based index of a column letter, `A` is 0 and `AA` is 26
+fn column_index(letters: &str) -> Option<usize> {
+    if letters.is_empty() || letters.len() > 3 || !letters.bytes().all(|b| b.is_ascii_uppercase()) {
+        return None;
+    }
+    Some(
+        letters
+            .bytes()
+            .fold(0, |index, b| index * 26 + (b - b'A') as usize + 1)
+            - 1,
+    )
+}
+
+/// The letter of a 0-based column index, `0` is `A` and `26` is `AA`
+pub fn column_letter(index: usize) -> String {
+    let mut letters = Vec::new();
+    let mut rest = index + 1;
+    while rest > 0 {
+        letters.push(b'A' + ((rest - 1) % 26) as u8);
+        rest = (rest - 1) / 26;
+    }
+    letters.iter().rev().map(|&b| b as char).collect()
+}
+
+/// What to do in the sizes sheet with stickers whose name is not marked `OK`
+#[derive(ValueEnum, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum NonOkStickers {
+    /// List them like approved stickers
+    #[default]
+    Include,
+    /// Leave them out, orders with no approved sticker end up in the missing sheet
+    Exclude,
+    /// List them with their status cell marked
+    Highlight,
+}
+
+impl fmt::Display for NonOkStickers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&choice_name(self))
+    }
+}
+
+/// What to do with the stickers of a name without a code that inference matched to several codes
+#[derive(ValueEnum, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum AmbiguousInferences {
+    /// Leave them out, the name stays an error
+    #[default]
+    Exclude,
+    /// Use them for every matched code
+    Include,
+}
+
+impl fmt::Display for AmbiguousInferences {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&choice_name(self))
+    }
+}
+
+/// Values given on the command line, they take precedence over the config file
+#[derive(Args, Debug, Default, Clone)]
+pub struct ConfigOverrides {
+    /// Directory containing the .cdr files
+    #[arg(long, global = true)]
+    pub archive: Option<PathBuf>,
+    /// Order files or globs, `.xlsx`, `.xls`, `.ods` or `.csv`, `path@Sheet1|Sheet2` picks sheets
+    #[arg(long, global = true, value_delimiter = ',')]
+    pub order: Vec<String>,
+    /// Sheets of the order files to read, separated by `|`, or `*` for all
+    #[arg(long, global = true)]
+    pub sheet_name: Option<String>,
+    /// Extra header keyword for the order amount column
+    #[arg(long, global = true)]
+    pub order_amount_column_name: Option<String>,
+    /// 1-based row of the order table header
+    #[arg(long, global = true)]
+    pub header_row: Option<usize>,
+    /// Column of the codes, a letter like `B` or header text
+    #[arg(long, global = true)]
+    pub code_column: Option<String>,
+    /// Column of the order amounts, a letter like `L` or header text
+    #[arg(long, global = true)]
+    pub amount_column: Option<String>,
+    /// Column of the descriptions, a letter like `C` or header text
+    #[arg(long, global = true)]
+    pub description_column: Option<String>,
+    /// Column of the ordered variants, a letter like `D` or header text
+    #[arg(long, global = true)]
+    pub variant_column: Option<String>,
+    /// Prefixes stripped from the codes, separated by commas
+    #[arg(long, global = true, value_delimiter = ',')]
+    pub code_prefixes: Vec<String>,
+    /// How many rows are searched for the code header
+    #[arg(long, global = true)]
+    pub header_scan_rows: Option<usize>,
+    /// How many columns are searched for the header keywords
+    #[arg(long, global = true)]
+    pub header_scan_columns: Option<usize>,
+    /// Similarity needed to infer a missing code from a description
+    #[arg(long, global = true)]
+    pub inferring_levenshtein_distance: Option<f64>,
+    /// Similarity needed to show a parse error next to an order
+    #[arg(long, global = true)]
+    pub error_output_levenshtein_distance: Option<f64>,
+    /// How inference compares descriptions
+    #[arg(long, global = true, value_enum)]
+    pub inferring_similarity: Option<Similarity>,
+    /// Similarity needed to infer a code with the token_set similarity
+    #[arg(long, global = true)]
+    pub inferring_token_set_threshold: Option<f64>,
+    /// Similarity needed to infer a code with the jaro_winkler similarity
+    #[arg(long, global = true)]
+    pub inferring_jaro_winkler_threshold: Option<f64>,
+    /// Similarity needed to infer a code with the trigram similarity
+    #[arg(long, global = true)]
+    pub inferring_trigram_threshold: Option<f64>,
+    /// What to do with names inference matched to several codes
+    #[arg(long, global = true, value_enum)]
+    pub ambiguous_inferences: Option<AmbiguousInferences>,
+    /// Catalog of official dimensions, one `WxH` per line
+    #[arg(long, global = true)]
+    pub dimensions: Option<PathBuf>,
+    /// How many mm a size may be off to suggest the closest official size
+    #[arg(long, global = true)]
+    pub dimensions_tolerance_mm: Option<u32>,
+    /// Material and color vocabulary used to parse the file names
+    #[arg(long, global = true)]
+    pub vocabulary: Option<PathBuf>,
+    /// File of renamed codes, `OLD -> NEW` per line
+    #[arg(long, global = true)]
+    pub code_aliases: Option<PathBuf>,
+    /// File of stickers for archive files whose names do not parse
+    #[arg(long, global = true)]
+    pub overrides: Option<PathBuf>,
+    /// What to do with stickers that are not marked OK in the sizes sheet
+    #[arg(long, global = true, value_enum)]
+    pub non_ok_stickers: Option<NonOkStickers>,
+    /// File the parsed archive is kept in between runs, `off` to parse everything every run
+    #[arg(long, global = true)]
+    pub archive_index: Option<PathBuf>,
 }
 
 const DEFAULT_INFERRING_LEVENSHTEIN_DISTANCE: f64 = 0.93; // corresponds to 1-2 edits
 const DEFAULT_ERROR_OUTPUT_LEVENSHTEIN_DISTANCE: f64 = 0.7;
+// the word order is ignored, so a missing or extra word costs about as much as two typos
+const DEFAULT_INFERRING_TOKEN_SET_THRESHOLD: f64 = 0.9;
+// Jaro-Winkler scores unrelated descriptions with the same start high already
+const DEFAULT_INFERRING_JARO_WINKLER_THRESHOLD: f64 = 0.95;
+const DEFAULT_INFERRING_TRIGRAM_THRESHOLD: f64 = 0.7;
+const DEFAULT_ARCHIVE_INDEX: &str = "archive_index.json";
+const ARCHIVE_PLACEHOLDER: &str = "<archive>";
+/// Used when `overrides` is not set and the file exists, `resolve` writes to it
+pub const DEFAULT_OVERRIDES: &str = "overrides.txt";
+/// Used when `code_aliases` is not set and the file exists, `resolve` writes to it
+pub const DEFAULT_CODE_ALIASES: &str = "code_aliases.txt";
+const DEFAULT_HEADER_SCAN_ROWS: usize = 40;
+
+// below this similarity an unknown key is not worth suggesting a replacement for
+const KEY_SUGGESTION_SIMILARITY: f64 = 0.6;
+
+const KNOWN_KEYS: &[&str] = &[
+    "archive",
+    "order",
+    "sheet_name",
+    "order_amount_column_name",
+    "header_row",
+    "code_column",
+    "amount_column",
+    "description_column",
+    "variant_column",
+    "code_prefixes",
+    "header_scan_rows",
+    "header_scan_columns",
+    "inferring_levenshtein_distance",
+    "error_output_levenshtein_distance",
+    "inferring_similarity",
+    "inferring_token_set_threshold",
+    "inferring_jaro_winkler_threshold",
+    "inferring_trigram_threshold",
+    "ambiguous_inferences",
+    "dimensions",
+    "dimensions_tolerance_mm",
+    "vocabulary",
+    "code_aliases",
+    "overrides",
+    "non_ok_stickers",
+    "archive_index",
+];
+
+/// Where a config value came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Location {
+    Line(usize),
+    Flag(&'static str),
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Location::Line(line) => write!(f, "line {line}"),
+            Location::Flag(flag) => write!(f, "--{flag}"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io {
+        path: PathBuf,
+        source: io::Error,
+    },
+    MalformedLine {
+        line: usize,
+        content: String,
+    },
+    InvalidFloat {
+        line: usize,
+        key: String,
+        value: String,
+    },
+    InvalidChoice {
+        line: usize,
+        key: String,
+        value: String,
+        choices: Vec<String>,
+    },
+    InvalidInteger {
+        line: usize,
+        key: String,
+        value: String,
+    },
+    OutOfRange {
+        location: Location,
+        key: String,
+        value: f64,
+    },
+    NotPositive {
+        flag: &'static str,
+    },
+    MissingKey(&'static str),
+    InvalidArchive {
+        location: Location,
+        path: PathBuf,
+    },
+    InvalidOrder {
+        location: Location,
+        path: PathBuf,
+    },
+    NoOrderFiles {
+        location: Location,
+        pattern: PathBuf,
+    },
+    InvalidDimensions {
+        location: Location,
+        path: PathBuf,
+    },
+    InvalidVocabulary {
+        location: Location,
+        path: PathBuf,
+    },
+    InvalidCodeAliases {
+        location: Location,
+        path: PathBuf,
+    },
+    InvalidOverrides {
+        location: Location,
+        path: PathBuf,
+    },
+    ThresholdOrder {
+        inferring: f64,
+        error_output: f64,
+    },
+    Multiple(Vec<ConfigError>),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io { path, source } => {
+                write!(f, "Failed to read config file {:?}: {}", path, source)
+            }
+            ConfigError::MalformedLine { line, content } => {
+                write!(f, "line {line}: expected 'key=value', got '{content}'")
+            }
+            ConfigError::InvalidFloat { line, key, value } => {
+                write!(f, "line {line}: invalid float for {key}: '{value}'")
+            }
+            ConfigError::InvalidChoice {
+                line,
+                key,
+                value,
+                choices,
+            } => {
+                write!(
+                    f,
+                    "line {line}: invalid value for {key}: '{value}', expected one of {}",
+                    choices.join(", ")
+                )
+            }
+            ConfigError::InvalidInteger { line, key, value } => {
+                write!(f, "line {line}: invalid integer for {key}: '{value}'")
+            }
+            ConfigError::OutOfRange {
+                location,
+                key,
+                value,
+            } => {
+                write!(f, "{location}: {key} must be between 0 and 1, got {value}")
+            }
+            ConfigError::NotPositive { flag } => {
+                write!(f, "--{flag}: must be greater than 0")
+            }
+            ConfigError::MissingKey(key) => {
+                write!(f, "missing '{key}' key in config file")
+            }
+            ConfigError::InvalidArchive { location, path } => {
+                write!(
+                    f,
+                    "{location}: 'archive' path is not a valid directory: {:?}",
+                    path
+                )
+            }
+            ConfigError::InvalidOrder { location, path } => {
+                write!(
+                    f,
+                    "{location}: 'order' path is not a valid order file ('.xlsx', '.xls', '.ods', '.csv'): {:?}",
+                    path
+                )
+            }
+            ConfigError::NoOrderFiles { location, pattern } => {
+                write!(
+                    f,
+                    "{location}: 'order' pattern matches no order files: {:?}",
+                    pattern
+                )
+            }
+            ConfigError::InvalidDimensions { location, path } => {
+                write!(
+                    f,
+                    "{location}: 'dimensions' path is not a valid file: {:?}",
+                    path
+                )
+            }
+            ConfigError::InvalidVocabulary { location, path } => {
+                write!(
+                    f,
+                    "{location}: 'vocabulary' path is not a valid file: {:?}",
+                    path
+                )
+            }
+            ConfigError::InvalidCodeAliases { location, path } => {
+                write!(
+                    f,
+                    "{location}: 'code_aliases' path is not a valid file: {:?}",
+                    path
+                )
+            }
+            ConfigError::InvalidOverrides { location, path } => {
+                write!(
+                    f,
+                    "{location}: 'overrides' path is not a valid file: {:?}",
+                    path
+                )
+            }
+            ConfigError::ThresholdOrder {
+                inferring,
+                error_output,
+            } => {
+                write!(
+                    f,
+                    "error_output_levenshtein_distance ({error_output}) must be lower than inferring_levenshtein_distance ({inferring})"
+                )
+            }
+            ConfigError::Multiple(errors) => {
+                write!(f, "{} problems in config file:", errors.len())?;
+                for error in errors {
+                    write!(f, "\n\t{error}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConfigError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
 
 impl Configs {
-    pub fn load_from_file<P: AsRef<Path>>(config_path: P) -> Self {
-        let file = fs::File::open(&config_path)
-            .unwrap_or_else(|_| panic!("Failed to open config file: {:?}", config_path.as_ref()));
+    pub fn load_from_file<P: AsRef<Path>>(config_path: P) -> Result<Self, ConfigError> {
+        Self::load(config_path, &ConfigOverrides::default())
+    }
+
+    /// Checks the `order` entries, only needed by the commands that read orders.
+    ///
+    /// There has to be one, plain paths have to be order files and globs have to match one.
+    pub fn check_orders(&self) -> Result<(), ConfigError> {
+        let mut errors: Vec<ConfigError> = Vec::new();
+        if self.orders.is_empty() {
+            errors.push(ConfigError::MissingKey("order"));
+        }
+        for (location, input) in self.order_locations.iter().zip(&self.orders) {
+            if !input.is_glob() && !is_order_file(&input.pattern) {
+                errors.push(ConfigError::InvalidOrder {
+                    location: *location,
+                    path: input.pattern.clone(),
+                });
+            } else if input.is_glob() && input.files().is_empty() {
+                errors.push(ConfigError::NoOrderFiles {
+                    location: *location,
+                    pattern: input.pattern.clone(),
+                });
+            }
+        }
+
+        match errors.len() {
+            0 => Ok(()),
+            1 => Err(errors.remove(0)),
+            _ => Err(ConfigError::Multiple(errors)),
+        }
+    }
 
+    /// The inferring threshold of the configured similarity
+    pub fn inferring_threshold(&self) -> f64 {
+        match self.inferring_similarity {
+            Similarity::Levenshtein => self.inferring_levenshtein_distance,
+            Similarity::TokenSet => self.inferring_token_set_threshold,
+            Similarity::JaroWinkler => self.inferring_jaro_winkler_threshold,
+            Similarity::Trigram => self.inferring_trigram_threshold,
+        }
+    }
+
+    /// Reads the config file and applies the command line overrides on top of it
+    pub fn load<P: AsRef<Path>>(
+        config_path: P,
+        overrides: &ConfigOverrides,
+    ) -> Result<Self, ConfigError> {
+        let io_error = |source| ConfigError::Io {
+            path: config_path.as_ref().to_path_buf(),
+            source,
+        };
+
+        let file = fs::File::open(&config_path).map_err(io_error)?;
         let reader = io::BufReader::new(file);
 
-        let mut archive_path: Option<PathBuf> = None;
-        let mut order_path: Option<PathBuf> = None;
+        let mut errors: Vec<ConfigError> = Vec::new();
+
+        // the locations are kept so the path checks can point back at the entry
+        let mut archive_path: Option<(Location, PathBuf)> = None;
+        let mut orders: Vec<(Location, OrderInput)> = Vec::new();
         let mut inferring_levenshtein_distance: f64 = DEFAULT_INFERRING_LEVENSHTEIN_DISTANCE;
         let mut error_output_levenshtein_distance: f64 = DEFAULT_ERROR_OUTPUT_LEVENSHTEIN_DISTANCE;
+        let mut inferring_similarity = Similarity::default();
+        let mut inferring_token_set_threshold = DEFAULT_INFERRING_TOKEN_SET_THRESHOLD;
+        let mut inferring_jaro_winkler_threshold = DEFAULT_INFERRING_JARO_WINKLER_THRESHOLD;
+        let mut inferring_trigram_threshold = DEFAULT_INFERRING_TRIGRAM_THRESHOLD;
+        let mut ambiguous_inferences = AmbiguousInferences::default();
         let mut sheet_name: Option<String> = None;
         let mut order_amount_column_name: Option<String> = None;
+        let mut header_row: Option<usize> = None;
+        let mut code_column: Option<ColumnRef> = None;
+        let mut amount_column: Option<ColumnRef> = None;
+        let mut description_column: Option<ColumnRef> = None;
+        let mut variant_column: Option<ColumnRef> = None;
+        let mut code_prefixes: Vec<String> = Vec::new();
+        let mut header_scan_rows: usize = DEFAULT_HEADER_SCAN_ROWS;
+        let mut header_scan_columns: Option<usize> = None;
+        let mut dimensions_path: Option<(Location, PathBuf)> = None;
+        let mut dimensions_tolerance_mm: u32 = DEFAULT_TOLERANCE_MM;
+        let mut vocabulary_path: Option<(Location, PathBuf)> = None;
+        let mut code_aliases_path: Option<(Location, PathBuf)> = None;
+        let mut overrides_path: Option<(Location, PathBuf)> = None;
+        let mut non_ok_stickers = NonOkStickers::default();
+        let mut archive_index_path = Some(PathBuf::from(DEFAULT_ARCHIVE_INDEX));
+
+        for (index, line_result) in reader.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line_result.map_err(io_error)?;
+            let trimmed = line.trim();
 
-        for line_result in reader.lines() {
-            let line = line_result.expect("Failed to read line from config file");
-            if let Some((key, value)) = line.split_once('=') {
-                let key = key.trim();
-                let value = value.trim();
-                match key {
-                    "archive" => archive_path = Some(PathBuf::from(value)),
-                    "order" => order_path = Some(PathBuf::from(value)),
-                    "inferring_levenshtein_distance" => {
-                        inferring_levenshtein_distance = value.parse().unwrap_or_else(|_| {
-                            panic!(
-                                "Invalid float for inferring_levenshtein_distance: {}",
-                                value
-                            )
-                        });
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+
+            let Some((key, value)) = trimmed.split_once('=') else {
+                errors.push(ConfigError::MalformedLine {
+                    line: line_number,
+                    content: trimmed.to_string(),
+                });
+                continue;
+            };
+            let key = key.trim();
+            let value = value.trim();
+
+            match key {
+                "archive" => archive_path = Some((Location::Line(line_number), value.into())),
+                // every `order` line adds its entries
+                "order" => orders.extend(
+                    split_order_entries(value).map(|input| (Location::Line(line_number), input)),
+                ),
+                "inferring_levenshtein_distance" => {
+                    if let Some(parsed) = parse_threshold(line_number, key, value, &mut errors) {
+                        inferring_levenshtein_distance = parsed;
+                    }
+                }
+                "error_output_levenshtein_distance" => {
+                    if let Some(parsed) = parse_threshold(line_number, key, value, &mut errors) {
+                        error_output_levenshtein_distance = parsed;
+                    }
+                }
+                "inferring_similarity" => {
+                    if let Some(parsed) = parse_choice(line_number, key, value, &mut errors) {
+                        inferring_similarity = parsed;
+                    }
+                }
+                "inferring_token_set_threshold" => {
+                    if let Some(parsed) = parse_threshold(line_number, key, value, &mut errors) {
+                        inferring_token_set_threshold = parsed;
+                    }
+                }
+                "inferring_jaro_winkler_threshold" => {
+                    if let Some(parsed) = parse_threshold(line_number, key, value, &mut errors) {
+                        inferring_jaro_winkler_threshold = parsed;
+                    }
+                }
+                "inferring_trigram_threshold" => {
+                    if let Some(parsed) = parse_threshold(line_number, key, value, &mut errors) {
+                        inferring_trigram_threshold = parsed;
+                    }
+                }
+                "ambiguous_inferences" => {
+                    if let Some(parsed) = parse_choice(line_number, key, value, &mut errors) {
+                        ambiguous_inferences = parsed;
                     }
-                    "error_output_levenshtein_distance" => {
-                        error_output_levenshtein_distance = value.parse().unwrap_or_else(|_| {
-                            panic!(
-                                "Invalid float for error_output_levenshtein_distance: {}",
-                                value
-                            )
-                        });
+                }
+                "sheet_name" => {
+                    sheet_name = Some(value.to_string());
+                }
+                "order_amount_column_name" => {
+                    order_amount_column_name = Some(value.to_string());
+                }
+                "code_column" => code_column = column_ref(value),
+                "amount_column" => amount_column = column_ref(value),
+                "description_column" => description_column = column_ref(value),
+                "variant_column" => variant_column = column_ref(value),
+                "code_prefixes" => {
+                    code_prefixes = value
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|prefix| !prefix.is_empty())
+                        .map(str::to_string)
+                        .collect();
+                }
+                "header_row" => {
+                    if let Some(parsed) = parse_positive(line_number, key, value, &mut errors) {
+                        header_row = Some(parsed);
                     }
-                    "sheet_name" => {
-                        sheet_name = Some(value.to_string());
+                }
+                "header_scan_rows" => {
+                    if let Some(parsed) = parse_positive(line_number, key, value, &mut errors) {
+                        header_scan_rows = parsed;
                     }
-                    "order_amount_column_name" => {
-                        order_amount_column_name = Some(value.to_string());
+                }
+                "header_scan_columns" => {
+                    header_scan_columns = parse_positive(line_number, key, value, &mut errors);
+                }
+                "dimensions" => dimensions_path = Some((Location::Line(line_number), value.into())),
+                "vocabulary" => vocabulary_path = Some((Location::Line(line_number), value.into())),
+                "code_aliases" => {
+                    code_aliases_path = Some((Location::Line(line_number), value.into()))
+                }
+                "overrides" => overrides_path = Some((Location::Line(line_number), value.into())),
+                "archive_index" => archive_index_path = index_path(value),
+                "dimensions_tolerance_mm" => match value.parse() {
+                    Ok(parsed) => dimensions_tolerance_mm = parsed,
+                    Err(_) => errors.push(ConfigError::InvalidInteger {
+                        line: line_number,
+                        key: key.to_string(),
+                        value: value.to_string(),
+                    }),
+                },
+                "non_ok_stickers" => {
+                    if let Some(parsed) = parse_choice(line_number, key, value, &mut errors) {
+                        non_ok_stickers = parsed;
                     }
-                    _ => continue,
                 }
+                unknown => warn_unknown_key(line_number, unknown),
+            }
+        }
+
+        if let Some(path) = &overrides.archive {
+            archive_path = Some((Location::Flag("archive"), path.clone()));
+        }
+        if !overrides.order.is_empty() {
+            orders = overrides
+                .order
+                .iter()
+                .flat_map(|value| split_order_entries(value))
+                .map(|input| (Location::Flag("order"), input))
+                .collect();
+        }
+        if let Some(path) = &overrides.dimensions {
+            dimensions_path = Some((Location::Flag("dimensions"), path.clone()));
+        }
+        if let Some(path) = &overrides.vocabulary {
+            vocabulary_path = Some((Location::Flag("vocabulary"), path.clone()));
+        }
+        if let Some(path) = &overrides.code_aliases {
+            code_aliases_path = Some((Location::Flag("code_aliases"), path.clone()));
+        }
+        if let Some(path) = &overrides.overrides {
+            overrides_path = Some((Location::Flag("overrides"), path.clone()));
+        }
+        if let Some(path) = &overrides.archive_index {
+            archive_index_path = index_path(&path.to_string_lossy());
+        }
+        if let Some(choice) = overrides.non_ok_stickers {
+            non_ok_stickers = choice;
+        }
+        if let Some(tolerance) = overrides.dimensions_tolerance_mm {
+            dimensions_tolerance_mm = tolerance;
+        }
+        if let Some(name) = &overrides.sheet_name {
+            sheet_name = Some(name.clone());
+        }
+        if let Some(name) = &overrides.order_amount_column_name {
+            order_amount_column_name = Some(name.clone());
+        }
+        if let Some(row) = overrides.header_row {
+            header_row = check_positive_flag("header_row", row, &mut errors).or(header_row);
+        }
+        if let Some(column) = &overrides.code_column {
+            code_column = column_ref(column);
+        }
+        if let Some(column) = &overrides.amount_column {
+            amount_column = column_ref(column);
+        }
+        if let Some(column) = &overrides.description_column {
+            description_column = column_ref(column);
+        }
+        if let Some(column) = &overrides.variant_column {
+            variant_column = column_ref(column);
+        }
+        if !overrides.code_prefixes.is_empty() {
+            code_prefixes = overrides.code_prefixes.clone();
+        }
+        if let Some(rows) = overrides.header_scan_rows {
+            header_scan_rows = check_positive_flag("header_scan_rows", rows, &mut errors)
+                .unwrap_or(header_scan_rows);
+        }
+        if let Some(columns) = overrides.header_scan_columns {
+            header_scan_columns = check_positive_flag("header_scan_columns", columns, &mut errors)
+                .or(header_scan_columns);
+        }
+        if let Some(value) = overrides.inferring_levenshtein_distance {
+            if let Some(value) =
+                check_threshold_flag("inferring_levenshtein_distance", value, &mut errors)
+            {
+                inferring_levenshtein_distance = value;
+            }
+        }
+        if let Some(value) = overrides.error_output_levenshtein_distance {
+            if let Some(value) =
+                check_threshold_flag("error_output_levenshtein_distance", value, &mut errors)
+            {
+                error_output_levenshtein_distance = value;
+            }
+        }
+        if let Some(similarity) = overrides.inferring_similarity {
+            inferring_similarity = similarity;
+        }
+        if let Some(choice) = overrides.ambiguous_inferences {
+            ambiguous_inferences = choice;
+        }
+        if let Some(value) = overrides.inferring_token_set_threshold {
+            if let Some(value) =
+                check_threshold_flag("inferring_token_set_threshold", value, &mut errors)
+            {
+                inferring_token_set_threshold = value;
+            }
+        }
+        if let Some(value) = overrides.inferring_jaro_winkler_threshold {
+            if let Some(value) =
+                check_threshold_flag("inferring_jaro_winkler_threshold", value, &mut errors)
+            {
+                inferring_jaro_winkler_threshold = value;
+            }
+        }
+        if let Some(value) = overrides.inferring_trigram_threshold {
+            if let Some(value) =
+                check_threshold_flag("inferring_trigram_threshold", value, &mut errors)
+            {
+                inferring_trigram_threshold = value;
             }
         }
 
-        let archive_path = archive_path.expect("Missing 'archive' key in config file");
-        let order_path = order_path.expect("Missing 'order' key in config file");
+        match &archive_path {
+            None => errors.push(ConfigError::MissingKey("archive")),
+            Some((location, path)) if !path.is_dir() => errors.push(ConfigError::InvalidArchive {
+                location: *location,
+                path: path.clone(),
+            }),
+            _ => {}
+        }
 
-        if !archive_path.is_dir() {
-            panic!(
-                "'archive' path is not a valid directory: {:?}",
-                archive_path
-            );
+        if let Some((location, path)) = &dimensions_path {
+            if !path.is_file() {
+                errors.push(ConfigError::InvalidDimensions {
+                    location: *location,
+                    path: path.clone(),
+                });
+            }
         }
 
-        if !order_path.is_file()
-            || order_path.extension().and_then(|e| e.to_str()) != Some("xlsx")
-                && order_path.extension().and_then(|e| e.to_str()) != Some("xls")
-        {
-            panic!(
-                "'order' path is not a valid excel file ('.xlsx', '.xls'): {:?}",
-                order_path
-            );
+        if let Some((location, path)) = &vocabulary_path {
+            if !path.is_file() {
+                errors.push(ConfigError::InvalidVocabulary {
+                    location: *location,
+                    path: path.clone(),
+                });
+            }
+        }
+
+        if let Some((location, path)) = &code_aliases_path {
+            if !path.is_file() {
+                errors.push(ConfigError::InvalidCodeAliases {
+                    location: *location,
+                    path: path.clone(),
+                });
+            }
+        }
+        if let Some((location, path)) = &overrides_path {
+            if !path.is_file() {
+                errors.push(ConfigError::InvalidOverrides {
+                    location: *location,
+                    path: path.clone(),
+                });
+            }
+        }
+
+        if error_output_levenshtein_distance >= inferring_levenshtein_distance {
+            errors.push(ConfigError::ThresholdOrder {
+                inferring: inferring_levenshtein_distance,
+                error_output: error_output_levenshtein_distance,
+            });
+        }
+
+        match errors.len() {
+            0 => {}
+            1 => return Err(errors.remove(0)),
+            _ => return Err(ConfigError::Multiple(errors)),
         }
 
         if inferring_levenshtein_distance == DEFAULT_INFERRING_LEVENSHTEIN_DISTANCE {
@@ -101,13 +941,311 @@ impl Configs {
             println!("error_output_levenshtein_distance=0.5");
         }
 
-        Configs {
+        // checked above, the unwrap cannot fail
+        let archive_path = archive_path.unwrap().1;
+        let archive_index_path = archive_index_path.map(|path| in_archive(path, &archive_path));
+        Ok(Configs {
             archive_path,
-            order_path,
+            order_locations: orders.iter().map(|(location, _)| *location).collect(),
+            orders: orders.into_iter().map(|(_, input)| input).collect(),
             sheet_name,
             order_amount_column_name,
+            header_row,
+            code_column,
+            amount_column,
+            description_column,
+            variant_column,
+            code_prefixes,
+            header_scan_rows,
+            header_scan_columns,
             inferring_levenshtein_distance,
             error_output_levenshtein_distance,
+            inferring_similarity,
+            inferring_token_set_threshold,
+            inferring_jaro_winkler_threshold,
+            inferring_trigram_threshold,
+            ambiguous_inferences,
+            dimensions_path: dimensions_path.map(|(_, path)| path),
+            dimensions_tolerance_mm,
+            vocabulary_path: vocabulary_path.map(|(_, path)| path),
+            code_aliases_path: code_aliases_path
+                .map(|(_, path)| path)
+                .or_else(|| existing_file(DEFAULT_CODE_ALIASES)),
+            overrides_path: overrides_path
+                .map(|(_, path)| path)
+                .or_else(|| existing_file(DEFAULT_OVERRIDES)),
+            non_ok_stickers,
+            archive_index_path,
+        })
+    }
+}
+
+/// The values a choice config accepts, the same as its flag
+pub fn choices<T: ValueEnum>() -> Vec<String> {
+    T::value_variants()
+        .iter()
+        .filter_map(ValueEnum::to_possible_value)
+        .map(|value| value.get_name().to_owned())
+        .collect()
+}
+
+/// The name of a choice as it is written in the config and on the command line
+pub fn choice_name<T: ValueEnum>(choice: &T) -> String {
+    choice
+        .to_possible_value()
+        .map(|value| value.get_name().to_owned())
+        .unwrap_or_default()
+}
+
+/// Parses a choice config like its flag, ignoring the case
+fn parse_choice<T: ValueEnum>(
+    line: usize,
+    key: &str,
+    value: &str,
+    errors: &mut Vec<ConfigError>,
+) -> Option<T> {
+    match <T as ValueEnum>::from_str(value, true) {
+        Ok(parsed) => Some(parsed),
+        Err(_) => {
+            errors.push(ConfigError::InvalidChoice {
+                line,
+                key: key.to_string(),
+                value: value.to_string(),
+                choices: choices::<T>(),
+            });
+            None
+        }
+    }
+}
+
+fn parse_threshold(
+    line: usize,
+    key: &str,
+    value: &str,
+    errors: &mut Vec<ConfigError>,
+) -> Option<f64> {
+    match value.parse::<f64>() {
+        Ok(parsed) if (0.0..=1.0).contains(&parsed) => Some(parsed),
+        Ok(parsed) => {
+            errors.push(ConfigError::OutOfRange {
+                location: Location::Line(line),
+                key: key.to_string(),
+                value: parsed,
+            });
+            None
+        }
+        Err(_) => {
+            errors.push(ConfigError::InvalidFloat {
+                line,
+                key: key.to_string(),
+                value: value.to_string(),
+            });
+            None
+        }
+    }
+}
+
+/// A count or 1-based position, zero is invalid
+fn parse_positive(
+    line: usize,
+    key: &str,
+    value: &str,
+    errors: &mut Vec<ConfigError>,
+) -> Option<usize> {
+    match value.parse::<usize>() {
+        Ok(parsed) if parsed > 0 => Some(parsed),
+        _ => {
+            errors.push(ConfigError::InvalidInteger {
+                line,
+                key: key.to_string(),
+                value: value.to_string(),
+            });
+            None
+        }
+    }
+}
+
+fn check_positive_flag(
+    flag: &'static str,
+    value: usize,
+    errors: &mut Vec<ConfigError>,
+) -> Option<usize> {
+    if value > 0 {
+        Some(value)
+    } else {
+        errors.push(ConfigError::NotPositive { flag });
+        None
+    }
+}
+
+/// An empty value leaves the column to the keyword detection
+fn column_ref(value: &str) -> Option<ColumnRef> {
+    (!value.trim().is_empty()).then(|| ColumnRef::parse(value))
+}
+
+fn check_threshold_flag(
+    flag: &'static str,
+    value: f64,
+    errors: &mut Vec<ConfigError>,
+) -> Option<f64> {
+    if (0.0..=1.0).contains(&value) {
+        Some(value)
+    } else {
+        errors.push(ConfigError::OutOfRange {
+            location: Location::Flag(flag),
+            key: flag.to_string(),
+            value,
+        });
+        None
+    }
+}
+
+/// The entries of an `order` value, separated by commas
+fn split_order_entries(value: &str) -> impl Iterator<Item = OrderInput> + '_ {
+    value
+        .split(',')
+        .filter(|entry| !entry.trim().is_empty())
+        .map(OrderInput::parse)
+}
+
+/// `path` if it is an existing file, for the optional files with a default name
+fn existing_file(path: &str) -> Option<PathBuf> {
+    let path = PathBuf::from(path);
+    path.is_file().then_some(path)
+}
+
+fn has_wildcard(text: &str) -> bool {
+    text.contains(['*', '?', '['])
+}
+
+/// Excel lock files and the workbooks written by this tool, never orders
+pub fn is_generated_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with("~$") || is_output_file(name))
+}
+
+fn is_order_file(path: &Path) -> bool {
+    path.is_file()
+        && path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|ext| ORDER_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+/// Closest known key to a misspelled one, if it is similar enough to be a likely typo
+pub fn suggest_key(unknown: &str) -> Option<&'static str> {
+    KNOWN_KEYS
+        .iter()
+        .map(|known| (*known, normalized_levenshtein(unknown, known)))
+        .filter(|(_, similarity)| *similarity >= KEY_SUGGESTION_SIMILARITY)
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(known, _)| known)
+}
+
+impl fmt::Display for Configs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "archive={}", self.archive_path.display())?;
+        let orders: Vec<String> = self.orders.iter().map(|input| input.to_string()).collect();
+        writeln!(f, "order={}", orders.join(", "))?;
+        if let Some(sheet_name) = &self.sheet_name {
+            writeln!(f, "sheet_name={sheet_name}")?;
+        }
+        if let Some(column_name) = &self.order_amount_column_name {
+            writeln!(f, "order_amount_column_name={column_name}")?;
+        }
+        if let Some(row) = self.header_row {
+            writeln!(f, "header_row={row}")?;
+        }
+        if let Some(column) = &self.code_column {
+            writeln!(f, "code_column={column}")?;
+        }
+        if let Some(column) = &self.amount_column {
+            writeln!(f, "amount_column={column}")?;
+        }
+        if let Some(column) = &self.description_column {
+            writeln!(f, "description_column={column}")?;
+        }
+        if let Some(column) = &self.variant_column {
+            writeln!(f, "variant_column={column}")?;
+        }
+        if !self.code_prefixes.is_empty() {
+            writeln!(f, "code_prefixes={}", self.code_prefixes.join(", "))?;
         }
+        writeln!(f, "header_scan_rows={}", self.header_scan_rows)?;
+        if let Some(columns) = self.header_scan_columns {
+            writeln!(f, "header_scan_columns={columns}")?;
+        }
+        if let Some(dimensions_path) = &self.dimensions_path {
+            writeln!(f, "dimensions={}", dimensions_path.display())?;
+        }
+        writeln!(
+            f,
+            "dimensions_tolerance_mm={}",
+            self.dimensions_tolerance_mm
+        )?;
+        if let Some(vocabulary_path) = &self.vocabulary_path {
+            writeln!(f, "vocabulary={}", vocabulary_path.display())?;
+        }
+        if let Some(code_aliases_path) = &self.code_aliases_path {
+            writeln!(f, "code_aliases={}", code_aliases_path.display())?;
+        }
+        if let Some(overrides_path) = &self.overrides_path {
+            writeln!(f, "overrides={}", overrides_path.display())?;
+        }
+        writeln!(f, "non_ok_stickers={}", self.non_ok_stickers)?;
+        writeln!(f, "ambiguous_inferences={}", self.ambiguous_inferences)?;
+        match &self.archive_index_path {
+            Some(path) => writeln!(f, "archive_index={}", path.display())?,
+            None => writeln!(f, "archive_index=off")?,
+        }
+        writeln!(
+            f,
+            "inferring_levenshtein_distance={}",
+            self.inferring_levenshtein_distance
+        )?;
+        writeln!(f, "inferring_similarity={}", self.inferring_similarity)?;
+        writeln!(
+            f,
+            "inferring_token_set_threshold={}",
+            self.inferring_token_set_threshold
+        )?;
+        writeln!(
+            f,
+            "inferring_jaro_winkler_threshold={}",
+            self.inferring_jaro_winkler_threshold
+        )?;
+        writeln!(
+            f,
+            "inferring_trigram_threshold={}",
+            self.inferring_trigram_threshold
+        )?;
+        write!(
+            f,
+            "error_output_levenshtein_distance={}",
+            self.error_output_levenshtein_distance
+        )
+    }
+}
+
+fn warn_unknown_key(line: usize, key: &str) {
+    match suggest_key(key) {
+        Some(suggestion) => println!(
+            "!!!WARNING: UNKNOWN KEY '{key}' ON LINE {line} IN CONFIG, DID YOU MEAN '{suggestion}'?!!!"
+        ),
+        None => println!("!!!WARNING: UNKNOWN KEY '{key}' ON LINE {line} IN CONFIG!!!"),
+    }
+}
+
+/// The archive index path of a config value, `off` disables the index
+fn index_path(value: &str) -> Option<PathBuf> {
+    (!value.eq_ignore_ascii_case("off")).then(|| PathBuf::from(value))
+}
+
+/// `<archive>/...` in the archive directory, the archive is only written to when asked to
+fn in_archive(path: PathBuf, archive: &Path) -> PathBuf {
+    match path.strip_prefix(ARCHIVE_PLACEHOLDER) {
+        Ok(rest) => archive.join(rest),
+        Err(_) => path,
     }
 }
@@ -1,162 +1,559 @@
 use crate::{
-    configs::Configs,
-    structs::{color::Color, material::Material, order::Order, sticker::Sticker},
+    configs::{column_letter, ColumnRef, Configs, NonOkStickers},
+    inference::AmbiguousInference,
+    order_source::{self, SheetSelection},
+    structs::{
+        code_normalizer::code_normalizer,
+        color::Color,
+        material::Material,
+        order::{merge_orders, order_sources, Order, OrderOrigin},
+        provenance::Provenance,
+        rejected_row::{RejectReason, RejectedRow},
+        sticker::Sticker,
+        variant::Variant,
+        vocabulary::vocabulary,
+    },
 };
 
-use calamine::{open_workbook_auto, Data, DataType, Reader};
-use chrono::Local;
-use std::collections::HashMap;
+use calamine::{Data, DataType, Range};
+use chrono::{Local, NaiveDate};
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
+use std::fmt;
+use std::path::Path;
 use xlsxwriter::prelude::*;
+use xlsxwriter::worksheet::conditional_format::ConditionalFormat;
 use xlsxwriter::*;
 
 type Coord = (usize, usize);
 
-/// Parse orders from an Excel file
+// the workbook of a run is `orders_dd_mm_yy.xlsx`
+const OUTPUT_PREFIX: &str = "orders_";
+const OUTPUT_DATE_FORMAT: &str = "%d_%m_%y";
+
+/// The valid orders of all order files and sheets and the rows that were left out
+#[derive(Debug, Clone, Default)]
+pub struct OrderImport {
+    pub orders: Vec<Order>,
+    pub rejected: Vec<RejectedRow>,
+}
+
+/// Parse the valid orders of all order files and sheets, merged by code
 pub fn parse_orders(configs: &Configs) -> Result<Vec<Order>, Box<dyn Error>> {
-    let file_path = configs.order_path.to_str().ok_or("Invalid file path")?;
-
-    let mut workbook = open_workbook_auto(file_path)?;
-    let range = workbook.worksheet_range(
-        configs
-            .sheet_name
-            .clone()
-            .unwrap_or("Sheet1".to_owned())
-            .as_str(),
-    )?;
+    import_orders(configs).map(|import| import.orders)
+}
 
-    let cell1 = find_keyword_cell(
-        &range,
-        6,
-        40,
-        &["БГ СТИКЕР", "Френски код", "French code", "Fr Code"],
-    )?;
-    let mut order_amount_keywords: Vec<String> = vec![
-        "Поръчка".into(),
-        "Брой".into(),
-        "Order".into(),
-        "Total".into(),
-    ];
-    if let Some(keyword) = configs.order_amount_column_name.clone() {
-        order_amount_keywords.push(keyword);
+/// Parse the orders of all order files and sheets, merged by code.
+///
+/// Invalid rows are collected instead of failing the import. With all sheets selected, sheets
+/// without an order table are skipped.
+pub fn import_orders(configs: &Configs) -> Result<OrderImport, Box<dyn Error>> {
+    let default_sheets = configs
+        .sheet_name
+        .as_deref()
+        .map(SheetSelection::parse)
+        .unwrap_or_default();
+
+    let mut seen = HashSet::new();
+    let mut import = OrderImport::default();
+    for input in &configs.orders {
+        let sheets = input.sheets.as_ref().unwrap_or(&default_sheets);
+        // a file matched by two entries is only read once
+        for path in input.files() {
+            if seen.insert(path.clone()) {
+                parse_order_file(&path, sheets, configs, &mut import)?;
+            }
+        }
     }
-    let cell2 = find_row_keyword_in_same_row(&range, cell1.0, 10, &order_amount_keywords)?;
-    let cell3 = find_row_keyword_in_same_row(
-        &range,
-        cell1.0,
-        10,
-        &["Описание".into(), "Description".into(), "Product".into()],
-    )?;
 
-    let orders = extract_orders(&range, cell1.1, cell2.1, cell3.1, cell1.0)?;
-    Ok(orders)
+    import.orders = merge_orders(import.orders);
+    Ok(import)
 }
 
-fn find_keyword_cell(
-    range: &calamine::Range<Data>,
-    max_cols: usize,
-    max_rows: usize,
-    keywords: &[&str],
-) -> Result<Coord, Box<dyn Error>> {
-    for row in 0..max_rows {
-        for col in 0..max_cols {
-            if let Some(val) = range.get((row, col)) {
-                if let Some(s) = val.get_string() {
-                    if keywords
-                        .iter()
-                        .any(|k| s.to_lowercase().contains(&k.to_lowercase()))
-                    {
-                        return Ok((row, col));
-                    }
+fn parse_order_file(
+    path: &Path,
+    sheets: &SheetSelection,
+    configs: &Configs,
+    import: &mut OrderImport,
+) -> Result<(), Box<dyn Error>> {
+    let file = path.file_name().map_or_else(
+        || path.display().to_string(),
+        |name| name.to_string_lossy().into(),
+    );
+    let ranges = order_source::open(path)
+        .read_sheets(sheets)
+        .map_err(|e| format!("{file}: {e}"))?;
+
+    for (sheet, range) in ranges {
+        let origin = OrderOrigin {
+            file: file.clone(),
+            sheet,
+            amount: 0,
+        };
+        let layout = match find_layout(&range, configs) {
+            Ok(layout) => layout,
+            Err(e) if *sheets == SheetSelection::All => {
+                println!("Skipping {origin}: {e}");
+                continue;
+            }
+            Err(e) => return Err(format!("{origin}: {e}").into()),
+        };
+
+        let (orders, rejected) = extract_orders(&range, &layout, &origin.to_string());
+        import.orders.extend(orders.into_iter().map(|mut order| {
+            order.origins = vec![OrderOrigin {
+                amount: order.amount,
+                ..origin.clone()
+            }];
+            order
+        }));
+        import.rejected.extend(rejected);
+    }
+    Ok(())
+}
+
+/// Where the header and the columns of the order table are, relative to the range
+struct OrderLayout {
+    header_row: usize,
+    code: usize,
+    amount: usize,
+    description: usize,
+    /// Only when configured, the variant is read from the description otherwise
+    variant: Option<usize>,
+}
+
+/// Why the order table of a sheet could not be found
+#[derive(Debug)]
+pub enum OrderLayoutError {
+    HeaderRowNotFound {
+        keywords: Vec<String>,
+        rows: usize,
+        columns: String,
+        /// `A1: text` of the non-empty cells that were searched
+        cells: Vec<String>,
+    },
+    ColumnNotFound {
+        column: &'static str,
+        keywords: Vec<String>,
+        /// 1-based
+        row: usize,
+        /// `A: text` of the non-empty cells of the header row
+        headers: Vec<String>,
+    },
+    OutsideSheet {
+        key: &'static str,
+        value: String,
+        last: String,
+    },
+}
+
+impl fmt::Display for OrderLayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OrderLayoutError::HeaderRowNotFound {
+                keywords,
+                rows,
+                columns,
+                cells,
+            } => {
+                write!(
+                    f,
+                    "no code header ({}) in the first {rows} rows of columns {columns}, set header_row and code_column",
+                    keywords.join(", ")
+                )?;
+                write_seen(f, "cells", cells)
+            }
+            OrderLayoutError::ColumnNotFound {
+                column,
+                keywords,
+                row,
+                headers,
+            } => {
+                write!(
+                    f,
+                    "no {column} column ({}) in header row {row}, set {column}_column",
+                    keywords.join(", ")
+                )?;
+                write_seen(f, "headers", headers)
+            }
+            OrderLayoutError::OutsideSheet { key, value, last } => {
+                write!(
+                    f,
+                    "{key}={value} is outside the sheet, which ends at {last}"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for OrderLayoutError {}
+
+fn write_seen(f: &mut fmt::Formatter<'_>, what: &str, seen: &[String]) -> fmt::Result {
+    if seen.is_empty() {
+        write!(f, ", no {what} seen, the area is empty")
+    } else {
+        write!(f, ", {what} seen: {}", seen.join(", "))
+    }
+}
+
+const CODE_KEYWORDS: [&str; 4] = ["БГ СТИКЕР", "Френски код", "French code", "Fr Code"];
+const AMOUNT_KEYWORDS: [&str; 4] = ["Поръчка", "Брой", "Order", "Total"];
+const DESCRIPTION_KEYWORDS: [&str; 3] = ["Описание", "Description", "Product"];
+// a row starting with one of them under the orders is the totals row that ends the table
+const FOOTER_KEYWORDS: [&str; 4] = ["ОБЩО", "Всичко", "Total", "Sum"];
+const CODE_SCAN_COLUMNS: usize = 6;
+const HEADER_SCAN_COLUMNS: usize = 10;
+
+/// Finds the header row and the columns, configured ones first, the rest by their keywords
+fn find_layout(range: &Range<Data>, configs: &Configs) -> Result<OrderLayout, OrderLayoutError> {
+    // configured rows and letters are sheet positions, the range can start further in
+    let (first_row, first_col) = range
+        .start()
+        .map_or((0, 0), |(row, col)| (row as usize, col as usize));
+    let code_scan_columns = configs.header_scan_columns.unwrap_or(CODE_SCAN_COLUMNS);
+    let scan_columns = configs.header_scan_columns.unwrap_or(HEADER_SCAN_COLUMNS);
+
+    let header_row = match configs.header_row {
+        Some(row) => (row - 1)
+            .checked_sub(first_row)
+            .filter(|row| *row < range.height())
+            .ok_or_else(|| OrderLayoutError::OutsideSheet {
+                key: "header_row",
+                value: row.to_string(),
+                last: (first_row + range.height()).to_string(),
+            })?,
+        None => {
+            let (keywords, columns) = match &configs.code_column {
+                Some(ColumnRef::Index(col)) => {
+                    let col = relative_column(range, "code_column", *col, first_col)?;
+                    (keywords(&CODE_KEYWORDS), col..col + 1)
                 }
+                Some(ColumnRef::Header(text)) => (vec![text.clone()], 0..range.width()),
+                None => (keywords(&CODE_KEYWORDS), 0..code_scan_columns),
+            };
+            find_keyword_cell(range, columns.clone(), configs.header_scan_rows, &keywords)
+                .map(|(row, _)| row)
+                .ok_or_else(|| OrderLayoutError::HeaderRowNotFound {
+                    keywords: keywords.clone(),
+                    rows: configs.header_scan_rows,
+                    columns: format!(
+                        "{}-{}",
+                        column_letter(first_col + columns.start),
+                        column_letter(first_col + columns.end.max(columns.start + 1) - 1)
+                    ),
+                    cells: cells_seen(range, 0..configs.header_scan_rows, columns, true),
+                })?
+        }
+    };
+
+    let mut amount_keywords = keywords(&AMOUNT_KEYWORDS);
+    amount_keywords.extend(configs.order_amount_column_name.clone());
+
+    let column = |name: &'static str,
+                  key: &'static str,
+                  configured: &Option<ColumnRef>,
+                  keywords: Vec<String>| {
+        match configured {
+            Some(ColumnRef::Index(col)) => relative_column(range, key, *col, first_col),
+            // configured header text is looked for in the whole row
+            Some(ColumnRef::Header(text)) => find_row_keyword_in_same_row(
+                range,
+                header_row,
+                0..range.width(),
+                std::slice::from_ref(text),
+            )
+            .map(|(_, col)| col)
+            .ok_or_else(|| column_not_found(range, name, vec![text.clone()], header_row)),
+            None => find_row_keyword_in_same_row(range, header_row, 0..scan_columns, &keywords)
+                .map(|(_, col)| col)
+                .ok_or_else(|| column_not_found(range, name, keywords, header_row)),
+        }
+    };
+
+    Ok(OrderLayout {
+        header_row,
+        code: column(
+            "code",
+            "code_column",
+            &configs.code_column,
+            keywords(&CODE_KEYWORDS),
+        )?,
+        amount: column(
+            "amount",
+            "amount_column",
+            &configs.amount_column,
+            amount_keywords,
+        )?,
+        description: column(
+            "description",
+            "description_column",
+            &configs.description_column,
+            keywords(&DESCRIPTION_KEYWORDS),
+        )?,
+        variant: match &configs.variant_column {
+            Some(_) => Some(column(
+                "variant",
+                "variant_column",
+                &configs.variant_column,
+                Vec::new(),
+            )?),
+            None => None,
+        },
+    })
+}
+
+fn keywords(keywords: &[&str]) -> Vec<String> {
+    keywords.iter().map(|keyword| keyword.to_string()).collect()
+}
+
+/// A configured column letter as an index into the range
+fn relative_column(
+    range: &Range<Data>,
+    key: &'static str,
+    col: usize,
+    first_col: usize,
+) -> Result<usize, OrderLayoutError> {
+    col.checked_sub(first_col)
+        .filter(|col| *col < range.width())
+        .ok_or_else(|| OrderLayoutError::OutsideSheet {
+            key,
+            value: column_letter(col),
+            last: column_letter((first_col + range.width()).max(1) - 1),
+        })
+}
+
+fn column_not_found(
+    range: &Range<Data>,
+    column: &'static str,
+    keywords: Vec<String>,
+    header_row: usize,
+) -> OrderLayoutError {
+    let first_row = range.start().map_or(0, |(row, _)| row as usize);
+    // the row number is the same for all of them, only the letters are shown
+    let headers = cells_seen(range, header_row..header_row + 1, 0..range.width(), false);
+    OrderLayoutError::ColumnNotFound {
+        column,
+        keywords,
+        row: first_row + header_row + 1,
+        headers,
+    }
+}
+
+/// `A1: 'text'` of the non-empty cells in the rows and columns, relative to the range
+fn cells_seen(
+    range: &Range<Data>,
+    rows: std::ops::Range<usize>,
+    columns: std::ops::Range<usize>,
+    with_row: bool,
+) -> Vec<String> {
+    let (first_row, first_col) = range
+        .start()
+        .map_or((0, 0), |(row, col)| (row as usize, col as usize));
+    let mut cells = Vec::new();
+    for row in rows.start..rows.end.min(range.height()) {
+        for col in columns.start..columns.end.min(range.width()) {
+            let text = range
+                .get((row, col))
+                .map(|cell| cell.to_string())
+                .unwrap_or_default();
+            if !text.trim().is_empty() {
+                let row = if with_row {
+                    (first_row + row + 1).to_string()
+                } else {
+                    String::new()
+                };
+                cells.push(format!(
+                    "{}{row}: '{}'",
+                    column_letter(first_col + col),
+                    text.trim()
+                ));
             }
         }
     }
-    Err("Keyword cell not found".into())
+    cells
+}
+
+fn find_keyword_cell(
+    range: &Range<Data>,
+    columns: std::ops::Range<usize>,
+    max_rows: usize,
+    keywords: &[String],
+) -> Option<Coord> {
+    (0..max_rows)
+        .find_map(|row| find_row_keyword_in_same_row(range, row, columns.clone(), keywords))
 }
 
 fn find_row_keyword_in_same_row(
-    range: &calamine::Range<Data>,
+    range: &Range<Data>,
     row: usize,
-    max_cols: usize,
+    columns: std::ops::Range<usize>,
     keywords: &[String],
-) -> Result<Coord, Box<dyn Error>> {
-    for col in 0..max_cols {
+) -> Option<Coord> {
+    for col in columns {
         if let Some(val) = range.get((row, col)) {
             if let Some(s) = val.get_string() {
                 if keywords
                     .iter()
                     .any(|k| s.to_lowercase().contains(&k.to_lowercase()))
                 {
-                    return Ok((row, col));
+                    return Some((row, col));
                 }
             }
         }
     }
-    Err("Row keyword cell not found".into())
+    None
 }
 
+/// The valid orders below the header row and the rejected rows, `source` labels the latter.
+///
+/// The table ends at the first empty row after the orders or at a totals row, the notes and
+/// sums under it are not orders. Empty rows before the first order are skipped.
 fn extract_orders(
-    range: &calamine::Range<Data>,
-    col_code: usize,
-    col_amount: usize,
-    col_description: usize,
-    start_row: usize,
-) -> Result<Vec<Order>, Box<dyn Error>> {
-    let mut result = Vec::new();
+    range: &Range<Data>,
+    layout: &OrderLayout,
+    source: &str,
+) -> (Vec<Order>, Vec<RejectedRow>) {
+    let first_row = range.start().map_or(0, |(row, _)| row as usize);
+    let text = |row: usize, col: usize| {
+        range
+            .get((row, col))
+            .map(|cell| cell.to_string().trim().to_string())
+            .unwrap_or_default()
+    };
+
+    let mut orders = Vec::new();
+    let mut rejected = Vec::new();
+    // 1-based sheet row of every (code, variant), to reject a second line of the same order
+    let mut first_rows: HashMap<(String, Variant), usize> = HashMap::new();
     let mut started = false;
 
-    for row in (start_row + 1)..range.height() {
-        let code: String;
-        if let Some(code_str) = range.get((row, col_code)) {
-            code = code_str.to_string();
-            started = true;
-        } else {
+    for row in (layout.header_row + 1)..range.height() {
+        let sheet_row = first_row + row + 1;
+        let original_code = text(row, layout.code);
+        let code = code_normalizer().normalize(&original_code);
+        let description = text(row, layout.description);
+        let amount_text = text(row, layout.amount);
+        let variant = match layout.variant {
+            Some(col) => Variant::from_cell(&text(row, col)),
+            None => Variant::in_description(&description),
+        };
+        if original_code.is_empty() && description.is_empty() && amount_text.is_empty() {
             if started {
                 break;
-            } else {
-                continue;
             }
+            continue;
         }
+        if is_footer(&original_code) || (original_code.is_empty() && is_footer(&description)) {
+            break;
+        }
+        started = true;
 
-        let amount = get_u64_from_cell(range.get((row, col_amount)))
-            .ok_or_else(|| format!("Invalid value in amount column at row {row}"))?;
-
-        let description = range
-            .get((row, col_description))
-            .and_then(|cell| cell.get_string())
-            .unwrap_or("")
-            .trim()
-            .to_string();
-
-        result.push(Order {
-            code,
-            amount,
-            description,
+        let amount = if code.is_empty() {
+            Err(RejectReason::BlankCode)
+        } else {
+            parse_amount(range.get((row, layout.amount)))
+        }
+        .and_then(|amount| {
+            let key = (code.clone(), variant.clone());
+            match first_rows.get(&key) {
+                Some(&first_row) => Err(RejectReason::DuplicateCode { first_row }),
+                None => {
+                    first_rows.insert(key, sheet_row);
+                    Ok(amount)
+                }
+            }
         });
+
+        match amount {
+            Ok(amount) => orders.push(Order {
+                original_code: (original_code != code).then_some(original_code),
+                code,
+                amount,
+                description,
+                variant,
+                origins: Vec::new(),
+            }),
+            Err(reason) => rejected.push(RejectedRow {
+                source: source.to_string(),
+                row: sheet_row,
+                code: original_code,
+                description,
+                amount: amount_text,
+                reason,
+            }),
+        }
     }
 
-    Ok(result)
+    (orders, rejected)
 }
 
-fn get_u64_from_cell(cell: Option<&Data>) -> Option<u64> {
-    match cell {
-        Some(Data::Int(n)) => Some(*n as u64),
-        Some(Data::Float(f)) if *f >= 0.0 && f.fract() == 0.0 => Some(*f as u64),
-        Some(Data::String(s)) => s.trim().parse::<u64>().ok(),
-        _ => None,
+fn is_footer(text: &str) -> bool {
+    let text = text.to_lowercase();
+    FOOTER_KEYWORDS
+        .iter()
+        .any(|keyword| text.starts_with(&keyword.to_lowercase()))
+}
+
+/// A positive whole amount, numbers written as text count as numbers
+fn parse_amount(cell: Option<&Data>) -> Result<u64, RejectReason> {
+    let value = match cell {
+        None | Some(Data::Empty) => return Err(RejectReason::MissingAmount),
+        Some(Data::Int(n)) => *n as f64,
+        Some(Data::Float(f)) => *f,
+        Some(Data::String(s)) if s.trim().is_empty() => return Err(RejectReason::MissingAmount),
+        // a decimal comma is common in Bulgarian exports
+        Some(Data::String(s)) => s
+            .trim()
+            .replace(',', ".")
+            .parse::<f64>()
+            .map_err(|_| RejectReason::InvalidAmount(s.trim().to_string()))?,
+        Some(other) => return Err(RejectReason::InvalidAmount(other.to_string())),
+    };
+
+    if !value.is_finite() {
+        Err(RejectReason::InvalidAmount(value.to_string()))
+    } else if value < 0.0 {
+        Err(RejectReason::NegativeAmount(value))
+    } else if value.fract() != 0.0 {
+        Err(RejectReason::FractionalAmount(value))
+    } else if value == 0.0 {
+        Err(RejectReason::ZeroAmount)
+    } else {
+        Ok(value as u64)
     }
 }
 
+// scores of inferred stickers from which the confidence cell is green and yellow, red below
+const HIGH_CONFIDENCE: f64 = 0.97;
+const LOW_CONFIDENCE: f64 = 0.9;
+const CONFIDENCE_COL: u16 = 8;
+
 pub fn write_sizes_table(
     workbook: &mut Workbook,
     orders: &[Order],
     code_to_stickers: &HashMap<String, Vec<Sticker>>,
+    non_ok_stickers: NonOkStickers,
+    sources: &[String],
 ) -> Result<(), XlsxError> {
     let mut sheet = workbook.add_worksheet(Some("sizes"))?;
 
-    let headers = ["code", "description", "material", "dimensions", "amount"];
-    let mut col_widths = headers.iter().map(|h| h.len()).collect::<Vec<_>>();
+    let headers = [
+        "code",
+        "description",
+        "variant",
+        "material",
+        "dimensions",
+        "amount",
+        "status",
+        "provenance",
+        "confidence",
+    ];
+    let mut col_widths = headers
+        .iter()
+        .copied()
+        .chain(sources.iter().map(String::as_str))
+        .map(|h| h.len())
+        .collect::<Vec<_>>();
 
     let mut base_format = Format::new();
     base_format.set_border(FormatBorder::Thin);
@@ -164,16 +561,30 @@ pub fn write_sizes_table(
     let mut amount_format = base_format.clone();
     amount_format.set_bg_color(FormatColor::Custom(0xFF_BF_00));
 
+    let mut not_ok_format = base_format.clone();
+    not_ok_format.set_bg_color(FormatColor::Orange);
+
+    let mut alias_format = base_format.clone();
+    alias_format.set_bg_color(FormatColor::Yellow);
+
+    let mut override_format = base_format.clone();
+    override_format.set_bg_color(FormatColor::Custom(0xDD_EB_F7));
+
+    let mut confidence_format = base_format.clone();
+    confidence_format.set_num_format("0.00");
+
     for (col, header) in headers.iter().enumerate() {
         sheet.write_string(0, col as u16, header, Some(&base_format))?;
     }
+    write_source_headers(&mut sheet, headers.len(), sources, &base_format)?;
 
     let mut row = 1;
     let mut last_multi: Option<bool> = None;
     let mut use_grey = true;
 
     for order in orders {
-        if let Some(stickers) = code_to_stickers.get(&order.code) {
+        let stickers = order.stickers_in(code_to_stickers);
+        if !stickers.is_empty() {
             let is_multi = stickers.len() > 1;
 
             // Determine color alternation for multi-sticker orders (only used for column 0)
@@ -196,9 +607,20 @@ pub fn write_sizes_table(
                 let values = [
                     sticker.code.to_string(),
                     sticker.description.clone(),
+                    if sticker.variant.is_regular() {
+                        String::new()
+                    } else {
+                        sticker.variant.to_string()
+                    },
                     sticker.material.to_string(),
                     sticker.dimensions.to_string(),
                     order.amount.to_string(),
+                    sticker.status.to_string(),
+                    if sticker.provenance.is_parsed() {
+                        String::new()
+                    } else {
+                        sticker.provenance.to_string()
+                    },
                 ];
 
                 for (col, value) in values.iter().enumerate() {
@@ -210,38 +632,59 @@ pub fn write_sizes_table(
                             }
                             f
                         }
-                        1 => {
-                            let mut f = base_format.clone();
-                            if sticker.description.contains("PROMO")
-                                || sticker.description.contains("TESTER")
-                            {
-                                f.set_bg_color(FormatColor::Red);
-                            }
+                        1 | 2 => {
+                            let mut f = Format::from(sticker.variant.clone());
+                            f.set_border(FormatBorder::Thin);
                             f
                         }
-                        2 => {
+                        3 => {
                             let mut f = Format::from(sticker.material.clone());
                             f.set_border(FormatBorder::Thin);
                             f
                         }
-                        3 => {
+                        4 => {
                             let mut f = Format::from(sticker.text_color.clone());
                             f.set_border(FormatBorder::Thin);
                             f
                         }
-                        4 => amount_format.clone(),
+                        5 => amount_format.clone(),
+                        6 if non_ok_stickers == NonOkStickers::Highlight
+                            && !sticker.status.is_ok() =>
+                        {
+                            not_ok_format.clone()
+                        }
+                        7 => match &sticker.provenance {
+                            Provenance::Alias { .. } => alias_format.clone(),
+                            Provenance::Override { .. } => override_format.clone(),
+                            Provenance::Parsed | Provenance::Inferred(_) => base_format.clone(),
+                        },
                         _ => base_format.clone(),
                     };
 
                     sheet.write_string(row, col as u16, value, Some(&format))?;
                     col_widths[col] = col_widths[col].max(value.len());
                 }
+                // a number, so the rows can be sorted and filtered by it
+                match sticker.provenance {
+                    Provenance::Inferred(_) => sheet.write_number(
+                        row,
+                        CONFIDENCE_COL,
+                        sticker.provenance.confidence(),
+                        Some(&confidence_format),
+                    )?,
+                    _ => sheet.write_blank(row, CONFIDENCE_COL, Some(&base_format))?,
+                }
+                write_source_amounts(&mut sheet, row, headers.len(), order, sources, &base_format)?;
 
                 row += 1;
             }
         }
     }
 
+    if row > 1 {
+        write_confidence_colors(&mut sheet, row - 1, &base_format)?;
+    }
+
     for (col, width) in col_widths.iter().enumerate() {
         sheet.set_column(col as u16, col as u16, *width as f64 + 2.0, None)?;
     }
@@ -249,15 +692,57 @@ pub fn write_sizes_table(
     Ok(())
 }
 
+/// Colors the confidence column green, yellow and red with conditional formats, so the
+/// thresholds can be changed in Excel without running again
+fn write_confidence_colors(
+    sheet: &mut Worksheet,
+    last_row: u32,
+    base_format: &Format,
+) -> Result<(), XlsxError> {
+    let [high, low, below] = [0xC6_EF_CE, 0xFF_EB_9C, 0xFF_C7_CE].map(|color| {
+        let mut f = base_format.clone();
+        f.set_bg_color(FormatColor::Custom(color));
+        f
+    });
+    // the first rule wins where two match, blank cells count as 0 and are left out
+    let cell = format!("{}2", column_letter(CONFIDENCE_COL as usize));
+    let rules = [
+        ConditionalFormat::cell_greater_than_or_equal_to(HIGH_CONFIDENCE, &high),
+        ConditionalFormat::cell_greater_than_or_equal_to(LOW_CONFIDENCE, &low),
+        ConditionalFormat::formula(
+            &format!("=AND(ISNUMBER({cell}),{cell}<{LOW_CONFIDENCE})"),
+            &below,
+        ),
+    ];
+    for rule in &rules {
+        sheet.conditional_format_range(1, CONFIDENCE_COL, last_row, CONFIDENCE_COL, rule)?;
+    }
+    Ok(())
+}
+
 pub fn write_missing_table(
     workbook: &mut Workbook,
     missing_orders: &[Order],
     code_to_stickers: &HashMap<String, Vec<Sticker>>,
+    sources: &[String],
 ) -> Result<(), XlsxError> {
     let mut sheet = workbook.add_worksheet(Some("missing"))?;
 
-    let headers = ["code", "description", "amount"];
-    let mut col_widths = headers.iter().map(|h| h.len()).collect::<Vec<_>>();
+    // the code as written in the order, when normalizing changed it
+    let headers = [
+        "code",
+        "description",
+        "variant",
+        "amount",
+        "original code",
+        "note",
+    ];
+    let mut col_widths = headers
+        .iter()
+        .copied()
+        .chain(sources.iter().map(String::as_str))
+        .map(|h| h.len())
+        .collect::<Vec<_>>();
 
     let mut base_format = Format::new();
     base_format.set_border(FormatBorder::Thin);
@@ -269,24 +754,125 @@ pub fn write_missing_table(
     for (col, header) in headers.iter().enumerate() {
         sheet.write_string(0, col as u16, header, Some(&base_format))?;
     }
+    write_source_headers(&mut sheet, headers.len(), sources, &base_format)?;
 
-    let mut row = 1;
-    for order in missing_orders
-        .iter()
-        .filter(|o| !code_to_stickers.contains_key(&o.code))
-    {
+    for (row, order) in (1..).zip(missing_orders) {
         let values = [
             order.code.to_string(),
             order.description.clone(),
+            if order.variant.is_regular() {
+                String::new()
+            } else {
+                order.variant.to_string()
+            },
             order.amount.to_string(),
+            order.original_code.clone().unwrap_or_default(),
+            variant_mismatch(order, code_to_stickers).unwrap_or_default(),
         ];
 
         for (col, value) in values.iter().enumerate() {
             sheet.write_string(row, col as u16, value, Some(&red_format))?;
             col_widths[col] = col_widths[col].max(value.len());
         }
+        write_source_amounts(&mut sheet, row, headers.len(), order, sources, &red_format)?;
+    }
+
+    for (col, width) in col_widths.iter().enumerate() {
+        sheet.set_column(col as u16, col as u16, *width as f64 + 2.0, None)?;
+    }
+
+    Ok(())
+}
+
+/// `only REGULAR stickers` when the code has stickers, but none of the ordered variant
+fn variant_mismatch(
+    order: &Order,
+    code_to_stickers: &HashMap<String, Vec<Sticker>>,
+) -> Option<String> {
+    let stickers = code_to_stickers.get(&order.code)?;
+    let mut variants: Vec<String> = stickers
+        .iter()
+        .map(|sticker| sticker.variant.to_string())
+        .collect();
+    variants.sort();
+    variants.dedup();
+    Some(format!("only {} stickers", variants.join(", ")))
+}
+
+/// One column per order source after the fixed columns, only written for several sources
+fn write_source_headers(
+    sheet: &mut Worksheet,
+    first_col: usize,
+    sources: &[String],
+    format: &Format,
+) -> Result<(), XlsxError> {
+    for (col, source) in (first_col..).zip(sources) {
+        sheet.write_string(0, col as u16, source, Some(format))?;
+    }
+    Ok(())
+}
 
-        row += 1;
+/// The amount each source ordered, empty for sources that did not order the code
+fn write_source_amounts(
+    sheet: &mut Worksheet,
+    row: u32,
+    first_col: usize,
+    order: &Order,
+    sources: &[String],
+    format: &Format,
+) -> Result<(), XlsxError> {
+    for (col, source) in (first_col..).zip(sources) {
+        let value = order
+            .amount_from(source)
+            .map_or_else(String::new, |amount| amount.to_string());
+        sheet.write_string(row, col as u16, &value, Some(format))?;
+    }
+    Ok(())
+}
+
+/// Whether `file_name` is a workbook written by `write_tables`
+pub fn is_output_file(file_name: &str) -> bool {
+    file_name
+        .strip_prefix(OUTPUT_PREFIX)
+        .and_then(|rest| rest.strip_suffix(".xlsx"))
+        .is_some_and(|date| NaiveDate::parse_from_str(date, OUTPUT_DATE_FORMAT).is_ok())
+}
+
+/// The order rows left out of the run, with why
+pub fn write_rejected_table(
+    workbook: &mut Workbook,
+    rejected: &[RejectedRow],
+) -> Result<(), XlsxError> {
+    let mut sheet = workbook.add_worksheet(Some("rejected"))?;
+
+    let headers = ["source", "row", "code", "description", "amount", "reason"];
+    let mut col_widths = headers.iter().map(|h| h.len()).collect::<Vec<_>>();
+
+    let mut base_format = Format::new();
+    base_format.set_border(FormatBorder::Thin);
+
+    let mut red_format = base_format.clone();
+    red_format.set_font_color(FormatColor::Red);
+
+    for (col, header) in headers.iter().enumerate() {
+        sheet.write_string(0, col as u16, header, Some(&base_format))?;
+    }
+
+    for (row, rejected_row) in (1..).zip(rejected) {
+        let values = [
+            rejected_row.source.clone(),
+            rejected_row.row.to_string(),
+            rejected_row.code.clone(),
+            rejected_row.description.clone(),
+            rejected_row.amount.clone(),
+            rejected_row.reason.to_string(),
+        ];
+
+        for (col, value) in values.iter().enumerate() {
+            let format = if col == 5 { &red_format } else { &base_format };
+            sheet.write_string(row, col as u16, value, Some(format))?;
+            col_widths[col] = col_widths[col].max(value.len());
+        }
     }
 
     for (col, width) in col_widths.iter().enumerate() {
@@ -296,27 +882,108 @@ pub fn write_missing_table(
     Ok(())
 }
 
+/// One row per candidate code of every ambiguous inference
+pub fn write_ambiguous_table(
+    workbook: &mut Workbook,
+    ambiguous: &[AmbiguousInference],
+) -> Result<(), XlsxError> {
+    let mut sheet = workbook.add_worksheet(Some("ambiguous"))?;
+
+    let headers = ["name", "code", "score", "source", "path"];
+    let mut col_widths = headers.iter().map(|h| h.len()).collect::<Vec<_>>();
+
+    let mut base_format = Format::new();
+    base_format.set_border(FormatBorder::Thin);
+
+    let mut red_format = base_format.clone();
+    red_format.set_font_color(FormatColor::Red);
+
+    for (col, header) in headers.iter().enumerate() {
+        sheet.write_string(0, col as u16, header, Some(&base_format))?;
+    }
+
+    let rows = ambiguous.iter().flat_map(|inference| {
+        inference
+            .candidates
+            .iter()
+            .map(move |candidate| (inference, candidate))
+    });
+    for (row, (inference, candidate)) in (1..).zip(rows) {
+        let values = [
+            inference.error.get_description(),
+            candidate.value.clone(),
+            format!("{:.2}", candidate.score),
+            candidate.source.clone(),
+            inference
+                .error
+                .path()
+                .map(|path| path.display().to_string())
+                .unwrap_or_default(),
+        ];
+
+        for (col, value) in values.iter().enumerate() {
+            let format = if col == 0 { &red_format } else { &base_format };
+            sheet.write_string(row, col as u16, value, Some(format))?;
+            col_widths[col] = col_widths[col].max(value.len());
+        }
+    }
+
+    for (col, width) in col_widths.iter().enumerate() {
+        sheet.set_column(col as u16, col as u16, *width as f64 + 2.0, None)?;
+    }
+
+    Ok(())
+}
+
+/// Writes the `sizes`, `missing`, `rejected` and `ambiguous` sheets of the orders
 pub fn write_tables(
     configs: &Configs,
+    import: &OrderImport,
     code_to_stickers: &HashMap<String, Vec<Sticker>>,
+    ambiguous: &[AmbiguousInference],
 ) -> Result<(), Box<dyn std::error::Error>> {
-    // Parse orders
-    let orders = parse_orders(configs)?;
+    let OrderImport { orders, rejected } = import;
+    if !rejected.is_empty() {
+        println!(
+            "{} order rows rejected, see the 'rejected' sheet:",
+            rejected.len()
+        );
+        for row in rejected {
+            println!("\t{row}");
+        }
+    }
+
+    // a single source needs no breakdown of the amounts
+    let sources = order_sources(orders);
+    let sources = if sources.len() > 1 {
+        sources
+    } else {
+        Vec::new()
+    };
 
     // Split available/missing
     let (available_orders, missing_orders): (Vec<_>, Vec<_>) = orders
-        .into_iter()
-        .partition(|order| code_to_stickers.contains_key(&order.code));
+        .iter()
+        .cloned()
+        .partition(|order| !order.stickers_in(code_to_stickers).is_empty());
 
-    let date_str = Local::now().format("%d_%m_%y").to_string();
-    let new_filename = format!("orders_{}.xlsx", date_str);
+    let date_str = Local::now().format(OUTPUT_DATE_FORMAT).to_string();
+    let new_filename = format!("{OUTPUT_PREFIX}{}.xlsx", date_str);
     let new_path = std::path::PathBuf::from(&new_filename);
 
     // Create new file
     let mut workbook = Workbook::new(new_path.to_str().unwrap())?;
 
-    write_sizes_table(&mut workbook, &available_orders, code_to_stickers)?;
-    write_missing_table(&mut workbook, &missing_orders, code_to_stickers)?;
+    write_sizes_table(
+        &mut workbook,
+        &available_orders,
+        code_to_stickers,
+        configs.non_ok_stickers,
+        &sources,
+    )?;
+    write_missing_table(&mut workbook, &missing_orders, code_to_stickers, &sources)?;
+    write_rejected_table(&mut workbook, rejected)?;
+    write_ambiguous_table(&mut workbook, ambiguous)?;
 
     workbook.close()?; // only close once
 
@@ -326,26 +993,35 @@ pub fn write_tables(
 impl From<Material> for Format {
     fn from(material: Material) -> Self {
         let mut format = Format::new();
-        let color = match material {
-            Material::PVC => format::FormatColor::Yellow,
-            Material::PVCR => format::FormatColor::Custom(0xFF_BF_00),
-            Material::PVCRSLV => format::FormatColor::Magenta,
-            _ => format::FormatColor::White,
-        };
+        let color = vocabulary()
+            .material_entry(&material)
+            .and_then(|entry| entry.excel_color)
+            .map_or(format::FormatColor::White, format::FormatColor::Custom);
         format.set_bg_color(color);
         format
     }
 }
 
+impl From<Variant> for Format {
+    fn from(variant: Variant) -> Self {
+        let mut format = Format::new();
+        if let Some(color) = vocabulary()
+            .variant_entry(&variant)
+            .and_then(|entry| entry.excel_color)
+        {
+            format.set_bg_color(format::FormatColor::Custom(color));
+        }
+        format
+    }
+}
+
 impl From<Color> for Format {
-    fn from(material: Color) -> Self {
+    fn from(color: Color) -> Self {
         let mut format = Format::new();
-        let color = match material {
-            Color::Black => format::FormatColor::Gray,
-            Color::Green => format::FormatColor::Green,
-            Color::Blue => format::FormatColor::Custom(0x46_75_E6),
-            Color::Red => format::FormatColor::Red,
-        };
+        let color = vocabulary()
+            .color_entry(&color)
+            .and_then(|entry| entry.excel_color)
+            .map_or(format::FormatColor::Gray, format::FormatColor::Custom);
         format.set_bg_color(color);
         format
     }
@@ -0,0 +1,347 @@
+use crate::{
+    configs::Configs,
+    parser::{self, DIMENSIONS_RE},
+    runs,
+    similarity::DescriptionIndex,
+    structs::{
+        archive_file::ArchiveFile, dimensions::Dimensions, parse_stcker_error::ParseStickerError,
+        sticker::Sticker, vocabulary::vocabulary,
+    },
+};
+use colored::*;
+use either::Either;
+use rayon::prelude::*;
+use regex::Regex;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use std::{fmt, fs, io};
+
+pub const DEFAULT_PLAN: &str = "rename_plan.csv";
+
+// sizes as people type them, with a lowercase or Cyrillic x and spaces around it
+static LOOSE_DIMENSIONS_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)(\d+) *[xх] *(\d+)").unwrap());
+
+const PLAN_HEADERS: [&str; 3] = ["from", "to", "fixes"];
+
+/// A naming mistake that a rename fixes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NameFix {
+    /// A size written with a Cyrillic or lowercase x or with spaces
+    Dimensions {
+        from: String,
+        to: String,
+    },
+    UnderscoreBeforeDimensions,
+    /// A material written with other separators than `_`, e.g. `PVC-R`
+    Material {
+        from: String,
+        to: String,
+    },
+    /// The code inference found for a name without one
+    InferredCode(String),
+}
+
+impl fmt::Display for NameFix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NameFix::Dimensions { from, to } => write!(f, "size '{from}' as '{to}'"),
+            NameFix::UnderscoreBeforeDimensions => write!(f, "underscore before the size"),
+            NameFix::Material { from, to } => write!(f, "material '{from}' as '{to}'"),
+            NameFix::InferredCode(code) => write!(f, "inferred code {code}"),
+        }
+    }
+}
+
+/// One file of a rename plan
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rename {
+    pub from: PathBuf,
+    pub to: PathBuf,
+    /// Why the file is renamed, as written in the plan
+    pub fixes: String,
+}
+
+/// The stem with its naming mistakes fixed, and the fixes that were made.
+///
+/// Only sizes that are in the official catalog are rewritten.
+pub fn canonical_stem(stem: &str, inferred_code: Option<&str>) -> (String, Vec<NameFix>) {
+    let mut fixes = Vec::new();
+
+    let mut name = LOOSE_DIMENSIONS_RE
+        .replace_all(stem, |caps: &regex::Captures| {
+            let written = &caps[0];
+            let canonical = format!("{}X{}", &caps[1], &caps[2]);
+            if written != canonical && canonical.parse::<Dimensions>().is_ok() {
+                fixes.push(NameFix::Dimensions {
+                    from: written.to_owned(),
+                    to: canonical.clone(),
+                });
+                canonical
+            } else {
+                written.to_owned()
+            }
+        })
+        .into_owned();
+
+    let first_size = DIMENSIONS_RE
+        .find_iter(&name)
+        .find(|m| m.as_str().parse::<Dimensions>().is_ok())
+        .map(|m| m.range());
+    if let Some(size) = first_size {
+        let before = &name[..size.start];
+        if !before.is_empty() && !before.ends_with('_') {
+            name = format!(
+                "{}_{}",
+                before.trim_end_matches([' ', '-']),
+                &name[size.start..]
+            );
+            fixes.push(NameFix::UnderscoreBeforeDimensions);
+        }
+    }
+
+    if let Some(size) = DIMENSIONS_RE.find(&name) {
+        let after = size.end();
+        if let Some((_, range)) = vocabulary().find_material(&name[after..]) {
+            let range = after + range.start..after + range.end;
+            let written = &name[range.clone()];
+            let canonical = written
+                .split(|c: char| !c.is_alphanumeric())
+                .filter(|part| !part.is_empty())
+                .collect::<Vec<_>>()
+                .join("_")
+                .to_uppercase();
+            if written != canonical {
+                fixes.push(NameFix::Material {
+                    from: written.to_owned(),
+                    to: canonical.clone(),
+                });
+                name.replace_range(range, &canonical);
+            }
+        }
+    }
+
+    if let Some(code) = inferred_code {
+        name = format!("{code}_{name}");
+        fixes.push(NameFix::InferredCode(code.to_owned()));
+    }
+
+    (name, fixes)
+}
+
+/// The renames that make the names canonical, for files that do not parse and whose new name
+/// does.
+///
+/// `errors` are the parse errors of the files, used to infer missing codes from `index`. Names
+/// that parse are never renamed, a rename only for looks would churn the archive.
+/// A rename onto an existing file or onto the target of another rename is left out.
+pub fn plan(
+    files: &[ArchiveFile],
+    index: &DescriptionIndex,
+    errors: &[ParseStickerError],
+    inferring_threshold: f64,
+) -> Vec<Rename> {
+    let mut targets: HashSet<PathBuf> = HashSet::new();
+    let mut renames = Vec::new();
+    let errors_by_path: HashMap<&Path, &ParseStickerError> = errors
+        .iter()
+        .filter_map(|error| Some((error.path()?, error)))
+        .collect();
+
+    for file in files {
+        let Some(error) = errors_by_path.get(file.path.as_path()) else {
+            continue;
+        };
+        let Some(stem) = file.path.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+        let inferred_code = inferred_code(error, index, inferring_threshold);
+
+        let (new_stem, fixes) = canonical_stem(stem, inferred_code.as_deref());
+        if fixes.is_empty() || Sticker::parse_stickers(&new_stem.to_uppercase()).is_err() {
+            continue;
+        }
+
+        let mut to = file.path.with_file_name(new_stem);
+        if let Some(extension) = file.path.extension() {
+            to.set_extension(extension);
+        }
+        if to.exists() || !targets.insert(to.clone()) {
+            continue;
+        }
+        let fixes: Vec<String> = fixes.iter().map(|fix| fix.to_string()).collect();
+        renames.push(Rename {
+            from: file.path.clone(),
+            to,
+            fixes: fixes.join("; "),
+        });
+    }
+    renames
+}
+
+/// The code inference finds for a name without one, when all similar stickers agree on it
+fn inferred_code(
+    error: &ParseStickerError,
+    index: &DescriptionIndex,
+    threshold: f64,
+) -> Option<String> {
+    let inferred = parser::infer_stickers(error, index, threshold).ok()?;
+    let codes: BTreeSet<&str> = inferred
+        .iter()
+        .map(|sticker| sticker.code.as_str())
+        .collect();
+    match codes.into_iter().collect::<Vec<_>>()[..] {
+        [code] => Some(code.to_owned()),
+        _ => None,
+    }
+}
+
+pub fn write_plan(path: &Path, renames: &[Rename]) -> Result<(), Box<dyn Error>> {
+    let mut writer = csv::Writer::from_path(path)?;
+    writer.write_record(PLAN_HEADERS)?;
+    for rename in renames {
+        write_rename(&mut writer, rename)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn write_rename<W: io::Write>(
+    writer: &mut csv::Writer<W>,
+    rename: &Rename,
+) -> Result<(), csv::Error> {
+    writer.write_record([
+        rename.from.to_string_lossy().as_ref(),
+        rename.to.to_string_lossy().as_ref(),
+        rename.fixes.as_str(),
+    ])
+}
+
+/// Reads a plan, rows may have been removed or edited by hand
+pub fn read_plan(path: &Path) -> Result<Vec<Rename>, Box<dyn Error>> {
+    let mut reader = csv::ReaderBuilder::new().flexible(true).from_path(path)?;
+    let mut renames = Vec::new();
+    for (index, record) in reader.records().enumerate() {
+        let record = record?;
+        match (record.get(0), record.get(1)) {
+            (Some(from), Some(to)) if !from.trim().is_empty() && !to.trim().is_empty() => renames
+                .push(Rename {
+                    from: from.trim().into(),
+                    to: to.trim().into(),
+                    fixes: record.get(2).unwrap_or_default().to_owned(),
+                }),
+            // the header is the first line
+            _ => return Err(format!("line {}: expected 'from,to,fixes'", index + 2).into()),
+        }
+    }
+    Ok(renames)
+}
+
+/// The undo log of a plan, `rename_plan_undo.csv` for `rename_plan.csv`
+pub fn undo_log_path(plan: &Path) -> PathBuf {
+    let stem = plan.file_stem().unwrap_or_default().to_string_lossy();
+    plan.with_file_name(format!("{stem}_undo.csv"))
+}
+
+/// Renames the files of the plan, a rename that fails is reported and skipped.
+///
+/// Every rename is appended reversed to the undo log before it is done, the log is itself a
+/// plan: applying it renames the files back. A rename that fails keeps its row, undoing it
+/// reports the file as missing. An existing undo log is never overwritten, it may be the only
+/// way back from an earlier apply.
+pub fn apply_plan(
+    renames: &[Rename],
+    undo_log: &Path,
+) -> Result<Vec<(Rename, String)>, Box<dyn Error>> {
+    if undo_log.exists() {
+        return Err(format!(
+            "the undo log {undo_log:?} already exists, apply or move it before applying again"
+        )
+        .into());
+    }
+    let mut undo = csv::Writer::from_path(undo_log)?;
+    undo.write_record(PLAN_HEADERS)?;
+    undo.flush()?;
+    let mut failed = Vec::new();
+
+    for rename in renames {
+        if !rename.from.is_file() {
+            failed.push((rename.clone(), format!("{:?} does not exist", rename.from)));
+            continue;
+        }
+        if rename.to.exists() {
+            failed.push((rename.clone(), format!("{:?} already exists", rename.to)));
+            continue;
+        }
+
+        // written before the rename, so an interrupted run can still be undone
+        let reversed = Rename {
+            from: rename.to.clone(),
+            to: rename.from.clone(),
+            fixes: format!("undo {}", rename.fixes),
+        };
+        write_rename(&mut undo, &reversed)?;
+        undo.flush()?;
+        if let Err(e) = fs::rename(&rename.from, &rename.to) {
+            failed.push((rename.clone(), e.to_string()));
+        }
+    }
+    Ok(failed)
+}
+
+/// Writes the rename plan of the archive, or applies the plan file with `apply`
+pub fn fix_names(configs: &Configs, plan_path: &Path, apply: bool) -> Result<(), Box<dyn Error>> {
+    if apply {
+        let renames = read_plan(plan_path)?;
+        let undo_log = undo_log_path(plan_path);
+        let failed = apply_plan(&renames, &undo_log)?;
+        for (rename, reason) in &failed {
+            eprintln!(
+                "{} {:?}: {}",
+                "Not renamed".red().bold(),
+                rename.from,
+                reason
+            );
+        }
+        println!(
+            "{} of {} files renamed, undo with: fix-names --apply --plan {:?}",
+            renames.len() - failed.len(),
+            renames.len(),
+            undo_log
+        );
+        return Ok(());
+    }
+
+    // overridden files keep their names, renaming them is what the override avoids
+    let files = runs::load_overrides(configs)
+        .apply(parser::collect_cdr_files(&configs.archive_path))
+        .remaining;
+    let (stickers, errors): (Vec<Vec<Sticker>>, Vec<ParseStickerError>) =
+        parser::parse_files(&files)
+            .into_par_iter()
+            .partition_map(|result| match result {
+                Ok(stickers) => Either::Left(stickers),
+                Err(error) => Either::Right(error),
+            });
+    let stickers: Vec<Sticker> = stickers.into_iter().flatten().collect();
+
+    let index = DescriptionIndex::new(&stickers, configs.inferring_similarity);
+    let renames = plan(&files, &index, &errors, configs.inferring_threshold());
+    write_plan(plan_path, &renames)?;
+
+    let fixed: HashSet<&Path> = renames.iter().map(|rename| rename.from.as_path()).collect();
+    let unfixable = errors
+        .iter()
+        .filter(|error| error.path().is_none_or(|path| !fixed.contains(path)))
+        .count();
+    println!(
+        "{} renames written to {:?}, {} file names that do not parse have no fix. \
+         Review the plan, then run fix-names --apply",
+        renames.len(),
+        plan_path,
+        unfixable
+    );
+    Ok(())
+}
@@ -0,0 +1,252 @@
+use crate::{
+    parser,
+    structs::{
+        archive_file::ArchiveFile, code_normalizer::code_normalizer, dimensions,
+        parse_stcker_error::ParseStickerError, sticker::Sticker, vocabulary::vocabulary,
+    },
+};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use std::{fmt, fs, io};
+
+// bump when the index layout changes, older indexes are then rebuilt
+const INDEX_VERSION: u32 = 2;
+
+type ParseResult = Result<Vec<Sticker>, ParseStickerError>;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexEntry {
+    size: u64,
+    modified: Option<SystemTime>,
+    result: ParseResult,
+}
+
+impl IndexEntry {
+    fn is_fresh(&self, file: &ArchiveFile) -> bool {
+        // without a modification time a file cannot be trusted to be unchanged
+        self.modified.is_some() && self.modified == file.modified && self.size == file.size
+    }
+}
+
+/// Parse results of the archive files, keyed by path, kept between runs.
+///
+/// An entry is reused while the file keeps its path, size and modification time. The whole
+/// index is dropped when the parser, the vocabulary, the code prefixes or the dimensions
+/// catalog change.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveIndex {
+    version: u32,
+    fingerprint: u64,
+    entries: HashMap<PathBuf, IndexEntry>,
+}
+
+/// What an index update did with the archive files
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IndexStats {
+    /// Unchanged files whose stored result was used
+    pub reused: usize,
+    /// New or changed files that were parsed
+    pub parsed: usize,
+    /// Entries of files that were deleted or renamed
+    pub removed: usize,
+}
+
+impl fmt::Display for IndexStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} reused, {} parsed, {} removed",
+            self.reused, self.parsed, self.removed
+        )
+    }
+}
+
+#[derive(Debug)]
+pub enum IndexError {
+    Io {
+        path: PathBuf,
+        source: io::Error,
+    },
+    Corrupt {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+}
+
+impl fmt::Display for IndexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IndexError::Io { path, source } => {
+                write!(f, "Failed to access archive index {:?}: {}", path, source)
+            }
+            IndexError::Corrupt { path, source } => {
+                write!(f, "Archive index {:?} is corrupt: {}", path, source)
+            }
+        }
+    }
+}
+
+impl std::error::Error for IndexError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            IndexError::Io { source, .. } => Some(source),
+            IndexError::Corrupt { source, .. } => Some(source),
+        }
+    }
+}
+
+impl Default for ArchiveIndex {
+    fn default() -> Self {
+        Self {
+            version: INDEX_VERSION,
+            fingerprint: parser_fingerprint(),
+            entries: HashMap::new(),
+        }
+    }
+}
+
+impl ArchiveIndex {
+    /// Loads the index at `path`.
+    ///
+    /// A missing index, or one written by another version or with another vocabulary, code
+    /// prefixes or catalog, gives an empty index.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, IndexError> {
+        let path = path.as_ref();
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(source) => {
+                return Err(IndexError::Io {
+                    path: path.to_path_buf(),
+                    source,
+                })
+            }
+        };
+
+        let index: ArchiveIndex =
+            serde_json::from_str(&text).map_err(|source| IndexError::Corrupt {
+                path: path.to_path_buf(),
+                source,
+            })?;
+
+        if index.version == INDEX_VERSION && index.fingerprint == parser_fingerprint() {
+            Ok(index)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), IndexError> {
+        let path = path.as_ref();
+        let io_error = |source| IndexError::Io {
+            path: path.to_path_buf(),
+            source,
+        };
+        let text = serde_json::to_string(self).map_err(|source| IndexError::Corrupt {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+        // write next to the index and rename, so an interrupted run cannot leave half an index
+        let tmp_path = path.with_extension("tmp");
+        fs::write(&tmp_path, text).map_err(io_error)?;
+        fs::rename(&tmp_path, path).map_err(io_error)
+    }
+
+    /// Parses the new and changed files and forgets the ones no longer in the archive.
+    ///
+    /// Returns the parse result of every file, in the order of `files`.
+    pub fn update(&mut self, files: &[ArchiveFile]) -> (Vec<ParseResult>, IndexStats) {
+        let mut stats = IndexStats::default();
+
+        let results: Vec<(ParseResult, bool)> = files
+            .par_iter()
+            .map(|file| match self.entries.get(&file.path) {
+                Some(entry) if entry.is_fresh(file) => (entry.result.clone(), true),
+                _ => (Sticker::parse_file(file), false),
+            })
+            .collect();
+
+        let mut entries = HashMap::with_capacity(files.len());
+        for (file, (result, reused)) in files.iter().zip(&results) {
+            if *reused {
+                stats.reused += 1;
+            } else {
+                stats.parsed += 1;
+            }
+            entries.insert(
+                file.path.clone(),
+                IndexEntry {
+                    size: file.size,
+                    modified: file.modified,
+                    result: result.clone(),
+                },
+            );
+        }
+        stats.removed = self
+            .entries
+            .keys()
+            .filter(|path| !entries.contains_key(*path))
+            .count();
+        self.entries = entries;
+
+        (
+            results.into_iter().map(|(result, _)| result).collect(),
+            stats,
+        )
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Parses `files` reusing the index at `index_path`, which is then updated.
+///
+/// With `rebuild` the stored results are ignored and every file is parsed again. Problems with
+/// the index only produce a warning, the files are then parsed without it.
+pub fn parse_files_indexed(
+    files: &[ArchiveFile],
+    index_path: &Path,
+    rebuild: bool,
+) -> (Vec<ParseResult>, IndexStats) {
+    let mut index = if rebuild {
+        ArchiveIndex::default()
+    } else {
+        ArchiveIndex::load(index_path).unwrap_or_else(|e| {
+            println!("!!!WARNING: REBUILDING ARCHIVE INDEX, {e}!!!");
+            ArchiveIndex::default()
+        })
+    };
+
+    let (results, stats) = index.update(files);
+
+    if let Err(e) = index.save(index_path) {
+        println!("!!!WARNING: ARCHIVE INDEX NOT SAVED, {e}!!!");
+    }
+
+    (results, stats)
+}
+
+/// Changes whenever a stored parse result could be different when parsing again
+fn parser_fingerprint() -> u64 {
+    let catalog = dimensions::catalog();
+    let mut hasher = DefaultHasher::new();
+    env!("CARGO_PKG_VERSION").hash(&mut hasher);
+    parser::PARSER_VERSION.hash(&mut hasher);
+    vocabulary().fingerprint().hash(&mut hasher);
+    code_normalizer().hash(&mut hasher);
+    catalog.tolerance_mm().hash(&mut hasher);
+    for dims in catalog.iter() {
+        dims.hash(&mut hasher);
+    }
+    hasher.finish()
+}
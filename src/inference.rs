@@ -0,0 +1,233 @@
+use crate::{
+    parser::{extract_code_token, split_at_dimensions, DIMENSIONS_RE},
+    similarity::DescriptionIndex,
+    structs::{
+        code_normalizer::code_normalizer,
+        parse_stcker_error::{ParseStickerError, SubParser},
+        provenance::{InferredField, Provenance},
+        sticker::Sticker,
+        vocabulary::vocabulary,
+    },
+};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::ops::Range;
+
+/// A name without a code whose description is similar to the descriptions of several codes
+#[derive(Debug, Clone)]
+pub struct AmbiguousInference {
+    pub error: ParseStickerError,
+    /// Every code with its most similar file, most similar first
+    pub candidates: Vec<InferredField>,
+}
+
+/// The inference of `error` when its stickers got different codes
+pub fn ambiguity(error: &ParseStickerError, inferred: &[Sticker]) -> Option<AmbiguousInference> {
+    let mut candidates: Vec<InferredField> = Vec::new();
+    for sticker in inferred {
+        let Provenance::Inferred(fields) = &sticker.provenance else {
+            continue;
+        };
+        for field in fields.iter().filter(|field| field.field == SubParser::Code) {
+            if candidates
+                .iter()
+                .all(|candidate| candidate.value != field.value)
+            {
+                candidates.push(field.clone());
+            }
+        }
+    }
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.value.cmp(&b.value)));
+
+    (candidates.len() > 1).then(|| AmbiguousInference {
+        error: error.clone(),
+        candidates,
+    })
+}
+
+/// The parsed stickers a missing size or material is taken from.
+///
+/// The other files of the same code are asked first. A code without other files falls back
+/// to the files whose description is at least `threshold` similar.
+pub struct FieldSources<'a> {
+    by_code: HashMap<&'a str, Vec<&'a Sticker>>,
+    index: &'a DescriptionIndex,
+    threshold: f64,
+}
+
+impl<'a> FieldSources<'a> {
+    pub fn new(stickers: &'a [Sticker], index: &'a DescriptionIndex, threshold: f64) -> Self {
+        let mut by_code: HashMap<&str, Vec<&Sticker>> = HashMap::new();
+        for sticker in stickers {
+            by_code.entry(&sticker.code).or_default().push(sticker);
+        }
+        Self {
+            by_code,
+            index,
+            threshold,
+        }
+    }
+
+    /// The field all source files agree on, the first of those files and how similar its
+    /// description is, `None` when there are no source files or they disagree
+    fn value(
+        &self,
+        field: SubParser,
+        code: &str,
+        description: &str,
+    ) -> Option<(String, String, f64)> {
+        let same_code = self.by_code.get(code).cloned().unwrap_or_default();
+        let sources: Vec<(&Sticker, f64)> = if same_code.is_empty() {
+            self.index
+                .similar(description, self.threshold)
+                .into_iter()
+                .filter_map(|similar| Some((self.by_code.get(similar.code)?, similar.score)))
+                .flat_map(|(stickers, score)| stickers.iter().map(move |s| (*s, score)))
+                .collect()
+        } else {
+            same_code
+                .into_iter()
+                .map(|sticker| (sticker, 1.0))
+                .collect()
+        };
+
+        // one value per file, a file with several sizes gives all of them
+        let mut files: BTreeMap<&str, (Vec<&Sticker>, f64)> = BTreeMap::new();
+        for (sticker, score) in sources {
+            files
+                .entry(&sticker.full_name)
+                .or_insert_with(|| (Vec::new(), score))
+                .0
+                .push(sticker);
+        }
+        let mut values: BTreeMap<String, (&str, f64)> = BTreeMap::new();
+        for (file, (stickers, score)) in files {
+            let value = match field {
+                SubParser::Dimensions => stickers
+                    .iter()
+                    .map(|s| format!("{}X{}", s.dimensions.width, s.dimensions.height))
+                    .collect::<Vec<_>>()
+                    .join("_"),
+                SubParser::Material => {
+                    let materials: BTreeSet<&str> =
+                        stickers.iter().map(|s| s.material.name()).collect();
+                    match materials.into_iter().collect::<Vec<_>>()[..] {
+                        [material] => material.to_owned(),
+                        // stickers of one file in different materials say nothing about another
+                        _ => continue,
+                    }
+                }
+                _ => return None,
+            };
+            values.entry(value).or_insert((file, score));
+        }
+
+        match values.len() {
+            1 => values
+                .into_iter()
+                .next()
+                .map(|(value, (file, score))| (value, file.to_owned(), score)),
+            _ => None,
+        }
+    }
+}
+
+/// Fills in the size or material missing from a file name with the one of other archive files.
+///
+/// The field is put into the name, which is parsed again, so a name missing both the size and
+/// the material gets both. Returns the stickers of the name, marked with the fields that were
+/// filled in, and those fields, or the original error when a field cannot be inferred.
+pub fn infer_fields(
+    error: &ParseStickerError,
+    sources: &FieldSources,
+) -> Result<(Vec<Sticker>, Vec<InferredField>), ParseStickerError> {
+    let original = &error.context().name;
+    let mut name = original.clone();
+    let mut current = error.clone();
+    let mut inferred: Vec<InferredField> = Vec::new();
+
+    loop {
+        let field = match current {
+            ParseStickerError::MissingDimensions(..) => SubParser::Dimensions,
+            ParseStickerError::MissingMaterial(_) | ParseStickerError::UnknownMaterial(_) => {
+                SubParser::Material
+            }
+            _ => return Err(error.clone()),
+        };
+        // a field that was filled in and still fails cannot be inferred
+        if inferred.iter().any(|done| done.field == field) {
+            return Err(error.clone());
+        }
+
+        let code_token = extract_code_token(&name).map_err(|_| error.clone())?;
+        let code = code_normalizer().normalize(code_token);
+        let Some((range, description)) = name
+            .find(code_token)
+            .and_then(|start| insertion(&name, start + code_token.len(), field))
+        else {
+            return Err(error.clone());
+        };
+        let Some((value, source, score)) =
+            sources.value(field, &code, description.trim_matches(['_', ' ']))
+        else {
+            return Err(error.clone());
+        };
+
+        let text = match field {
+            // a rejected size is replaced, a missing one goes in front of the material
+            SubParser::Dimensions if !range.is_empty() => value.clone(),
+            SubParser::Dimensions if range.start < name.len() => {
+                let separator = if name[..range.start].ends_with(['_', ' ']) {
+                    ""
+                } else {
+                    "_"
+                };
+                format!("{separator}{value}_")
+            }
+            _ => format!("_{value}"),
+        };
+        name.replace_range(range, &text);
+        inferred.push(InferredField {
+            name: original.clone(),
+            field,
+            value,
+            source,
+            score,
+        });
+
+        match Sticker::parse_stickers(&name) {
+            Ok(mut stickers) => {
+                for sticker in &mut stickers {
+                    sticker.full_name = original.clone();
+                    sticker.provenance = Provenance::Inferred(inferred.clone());
+                }
+                return Ok((stickers, inferred));
+            }
+            Err(e) => current = e,
+        }
+    }
+}
+
+/// Where in the name the field goes and the description in front of it
+fn insertion(name: &str, code_end: usize, field: SubParser) -> Option<(Range<usize>, &str)> {
+    let rest = &name[code_end..];
+    match field {
+        SubParser::Dimensions => {
+            let range = match DIMENSIONS_RE.find(rest) {
+                Some(rejected) => rejected.range(),
+                None => match vocabulary().find_material(rest) {
+                    Some((_, material)) => material.start..material.start,
+                    None => rest.len()..rest.len(),
+                },
+            };
+            Some((
+                code_end + range.start..code_end + range.end,
+                &rest[..range.start],
+            ))
+        }
+        SubParser::Material => {
+            let (before, _) = split_at_dimensions(name).ok()?;
+            Some((name.len()..name.len(), before.get(code_end..)?))
+        }
+        _ => None,
+    }
+}
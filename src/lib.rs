@@ -1,16 +1,32 @@
+pub mod cli;
 pub mod configs;
 pub mod excel;
-#[cfg(feature = "material_report")]
+pub mod fix_names;
+pub mod index;
+pub mod inference;
+pub mod order_source;
 pub mod order_summary;
 pub mod parser;
-#[cfg(feature = "error_handling")]
 pub mod report;
+pub mod resolve;
+pub mod run_report;
 pub mod runs;
+pub mod similarity;
+pub mod watch;
 pub mod structs {
+    pub mod archive_file;
+    pub mod code_aliases;
+    pub mod code_normalizer;
     pub mod color;
     pub mod dimensions;
     pub mod material;
     pub mod order;
     pub mod parse_stcker_error;
+    pub mod provenance;
+    pub mod rejected_row;
+    pub mod status;
     pub mod sticker;
+    pub mod sticker_overrides;
+    pub mod variant;
+    pub mod vocabulary;
 }
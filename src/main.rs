@@ -1,10 +1,7 @@
-use order_processor::runs;
+use clap::Parser;
+use order_processor::cli::Cli;
+use std::process::ExitCode;
 
-fn main() {
-    #[cfg(all(feature = "error_handling", feature = "inferring"))]
-    runs::run_inferring();
-    #[cfg(all(feature = "error_handling", not(feature = "inferring")))]
-    runs::run_no_inferring();
-    #[cfg(not(feature = "error_handling"))]
-    runs::run_optimized();
+fn main() -> ExitCode {
+    Cli::parse().run()
 }
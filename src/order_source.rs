@@ -0,0 +1,222 @@
+use calamine::{open_workbook_auto, Data, Range, Reader};
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_8};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const DEFAULT_SHEET_NAME: &str = "Sheet1";
+
+// delimiters a CSV export is tried with, in order of preference on ties
+const CSV_DELIMITERS: [u8; 4] = [b';', b',', b'\t', b'|'];
+// how many lines are looked at to detect the delimiter
+const DELIMITER_SAMPLE_LINES: usize = 20;
+
+/// The cells of one sheet, with the sheet name for sources that have sheets
+pub type Sheet = (Option<String>, Range<Data>);
+
+/// A file the orders are read from.
+///
+/// Every source hands back the cells of its order sheets, the header keyword detection in
+/// `excel::parse_orders` is the same for all of them.
+pub trait OrderSource {
+    /// The selected sheets, sources without sheets ignore the selection and give one range
+    fn read_sheets(&self, sheets: &SheetSelection) -> Result<Vec<Sheet>, Box<dyn Error>>;
+}
+
+/// Which sheets of a workbook hold orders
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SheetSelection {
+    Named(Vec<String>),
+    /// Every sheet, the ones without an order table are skipped
+    All,
+}
+
+impl Default for SheetSelection {
+    fn default() -> Self {
+        SheetSelection::Named(vec![DEFAULT_SHEET_NAME.to_owned()])
+    }
+}
+
+impl SheetSelection {
+    /// `*` or `all` for every sheet, otherwise sheet names separated by `|`
+    pub fn parse(value: &str) -> Self {
+        let value = value.trim();
+        if value == "*" || value.eq_ignore_ascii_case("all") {
+            return SheetSelection::All;
+        }
+        let names: Vec<String> = value
+            .split('|')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(str::to_owned)
+            .collect();
+        if names.is_empty() {
+            SheetSelection::default()
+        } else {
+            SheetSelection::Named(names)
+        }
+    }
+}
+
+impl fmt::Display for SheetSelection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SheetSelection::Named(names) => write!(f, "{}", names.join("|")),
+            SheetSelection::All => write!(f, "*"),
+        }
+    }
+}
+
+/// `.xlsx`, `.xls` and `.ods` workbooks
+pub struct SpreadsheetSource {
+    pub path: PathBuf,
+}
+
+/// CSV exports in any encoding, with `;`, `,`, tab or `|` as delimiter
+pub struct CsvSource {
+    pub path: PathBuf,
+}
+
+/// File extensions an order file can have
+pub const ORDER_EXTENSIONS: &[&str] = &["xlsx", "xls", "ods", "csv"];
+
+/// The source for an order file, picked by its extension
+pub fn open(path: &Path) -> Box<dyn OrderSource> {
+    let is_csv = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+    if is_csv {
+        Box::new(CsvSource {
+            path: path.to_path_buf(),
+        })
+    } else {
+        Box::new(SpreadsheetSource {
+            path: path.to_path_buf(),
+        })
+    }
+}
+
+impl OrderSource for SpreadsheetSource {
+    fn read_sheets(&self, sheets: &SheetSelection) -> Result<Vec<Sheet>, Box<dyn Error>> {
+        let mut workbook = open_workbook_auto(&self.path)?;
+        let names = match sheets {
+            SheetSelection::Named(names) => names.clone(),
+            SheetSelection::All => workbook.sheet_names(),
+        };
+        names
+            .into_iter()
+            .map(|name| {
+                let range = workbook.worksheet_range(&name)?;
+                Ok((Some(name), range))
+            })
+            .collect()
+    }
+}
+
+impl OrderSource for CsvSource {
+    fn read_sheets(&self, _sheets: &SheetSelection) -> Result<Vec<Sheet>, Box<dyn Error>> {
+        Ok(vec![(None, self.read_range()?)])
+    }
+}
+
+impl CsvSource {
+    pub fn read_range(&self) -> Result<Range<Data>, Box<dyn Error>> {
+        let bytes = fs::read(&self.path)?;
+        let text = decode(&bytes);
+        let delimiter = detect_delimiter(&text);
+
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(delimiter)
+            .has_headers(false)
+            .flexible(true)
+            .from_reader(text.as_bytes());
+
+        let mut rows: Vec<Vec<String>> = Vec::new();
+        for record in reader.records() {
+            rows.push(record?.iter().map(str::to_owned).collect());
+        }
+
+        let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+        if rows.is_empty() || width == 0 {
+            return Ok(Range::empty());
+        }
+
+        let mut range = Range::new((0, 0), (rows.len() as u32 - 1, width as u32 - 1));
+        for (row, values) in rows.into_iter().enumerate() {
+            for (col, value) in values.into_iter().enumerate() {
+                if !value.trim().is_empty() {
+                    range.set_value((row as u32, col as u32), Data::String(value));
+                }
+            }
+        }
+        Ok(range)
+    }
+}
+
+/// Decodes the file with its BOM encoding, as UTF-8 when valid, or with the guessed encoding
+pub fn decode(bytes: &[u8]) -> String {
+    if let Some((encoding, bom_length)) = Encoding::for_bom(bytes) {
+        return encoding
+            .decode_without_bom_handling(&bytes[bom_length..])
+            .0
+            .into_owned();
+    }
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        return text.to_owned();
+    }
+
+    // mostly Windows-1251 exports from Bulgarian systems
+    let mut detector = EncodingDetector::new();
+    detector.feed(bytes, true);
+    let encoding = detector.guess(None, true);
+    let encoding = if encoding == UTF_8 {
+        encoding_rs::WINDOWS_1251
+    } else {
+        encoding
+    };
+    encoding.decode_without_bom_handling(bytes).0.into_owned()
+}
+
+/// The delimiter that splits the sample lines into the most columns, consistently.
+///
+/// Each candidate is scored by its lowest count over the non-empty sample lines, so a comma
+/// inside a description does not win over a semicolon on every line.
+pub fn detect_delimiter(text: &str) -> u8 {
+    let lines: Vec<&str> = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .take(DELIMITER_SAMPLE_LINES)
+        .collect();
+
+    CSV_DELIMITERS
+        .iter()
+        .map(|&delimiter| {
+            let counts: Vec<usize> = lines
+                .iter()
+                .map(|line| count_outside_quotes(line, delimiter))
+                .collect();
+            let lowest = counts.iter().copied().min().unwrap_or(0);
+            let total: usize = counts.iter().sum();
+            (delimiter, (lowest, total))
+        })
+        // max_by_key keeps the last maximum, reversing keeps the preferred delimiter on ties
+        .rev()
+        .max_by_key(|(_, score)| *score)
+        .filter(|(_, (_, total))| *total > 0)
+        .map_or(b',', |(delimiter, _)| delimiter)
+}
+
+fn count_outside_quotes(line: &str, delimiter: u8) -> usize {
+    let mut quoted = false;
+    let mut count = 0;
+    for byte in line.bytes() {
+        if byte == b'"' {
+            quoted = !quoted;
+        } else if byte == delimiter && !quoted {
+            count += 1;
+        }
+    }
+    count
+}
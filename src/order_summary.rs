@@ -1,39 +1,53 @@
-use crate::{
-    configs::Configs,
-    excel::parse_orders,
-    structs::{dimensions::Dimensions, material::Material, sticker::Sticker},
+use crate::structs::{
+    dimensions::Dimensions, order::Order, sticker::Sticker, variant::Variant,
+    vocabulary::vocabulary,
 };
 use colored::*;
 use std::{collections::HashMap, fs::File, io::Write};
 
-fn group_material(material: &Material) -> &'static str {
-    match material {
-        Material::Paper | Material::PaperGR | Material::LEAFLET => "PAPER",
-        Material::PVC | Material::PVCR | Material::PVCRSLV => "PVC",
-    }
+/// How many stickers of each material group, variant and size the orders need
+#[derive(Debug, Clone, Default)]
+pub struct MaterialCounts {
+    pub counts: HashMap<(String, Variant, Dimensions), u64>,
+    /// Orders whose code has no stickers of the ordered variant
+    pub missing_stickers: u32,
 }
 
-pub fn generate_material_report_for_orders(
-    configs: &Configs,
+pub fn count_materials(
+    orders: &[Order],
     code_to_stickers_map: &HashMap<String, Vec<Sticker>>,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let orders = parse_orders(configs)?;
-    let mut counts: HashMap<(String, Dimensions), u64> = HashMap::new();
+) -> MaterialCounts {
+    let mut counts: HashMap<(String, Variant, Dimensions), u64> = HashMap::new();
 
     let mut missing_stickers: u32 = 0;
 
     for order in orders {
-        if let Some(stickers) = code_to_stickers_map.get(&order.code) {
-            for sticker in stickers {
-                let group = group_material(&sticker.material).to_string();
-                let key = (group, sticker.dimensions.clone());
-                *counts.entry(key).or_insert(0) += order.amount;
-            }
-        } else {
+        let stickers = order.stickers_in(code_to_stickers_map);
+        if stickers.is_empty() {
             missing_stickers += 1;
         }
+        for sticker in stickers {
+            let group = sticker.material.group();
+            let key = (group, sticker.variant.clone(), sticker.dimensions.clone());
+            *counts.entry(key).or_insert(0) += order.amount;
+        }
     }
 
+    MaterialCounts {
+        counts,
+        missing_stickers,
+    }
+}
+
+pub fn generate_material_report_for_orders(
+    orders: &[Order],
+    code_to_stickers_map: &HashMap<String, Vec<Sticker>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let MaterialCounts {
+        counts,
+        missing_stickers,
+    } = count_materials(orders, code_to_stickers_map);
+
     eprintln!(
         "\n{}: {}\n",
         "Missing sticker files".underline().bold().blue(),
@@ -44,28 +58,43 @@ pub fn generate_material_report_for_orders(
     output.push_str("Needed Stickers Report\n");
     output.push_str("======================\n");
 
-    let mut pvc_entries: Vec<(Dimensions, u64)> = Vec::new();
-    let mut paper_entries: Vec<(Dimensions, u64)> = Vec::new();
-
-    for ((mat, dims), count) in counts {
-        match mat.as_str() {
-            "PAPER" => paper_entries.push((dims, count)),
-            "PVC" => pvc_entries.push((dims, count)),
-            _ => panic!("Unknown material group: {}", mat),
-        }
+    let mut entries_by_group: HashMap<String, Vec<(Variant, Dimensions, u64)>> = HashMap::new();
+    for ((group, variant, dims), count) in counts {
+        entries_by_group
+            .entry(group)
+            .or_default()
+            .push((variant, dims, count));
     }
 
-    pvc_entries.sort_by_key(|(dims, _)| dims.to_string());
-    paper_entries.sort_by_key(|(dims, _)| dims.to_string());
+    // groups in vocabulary order, groups of materials missing from the vocabulary last
+    let mut groups: Vec<String> = vocabulary()
+        .groups()
+        .into_iter()
+        .map(str::to_owned)
+        .collect();
+    let mut unknown_groups: Vec<String> = entries_by_group
+        .keys()
+        .filter(|group| !groups.contains(group))
+        .cloned()
+        .collect();
+    unknown_groups.sort();
+    groups.extend(unknown_groups);
 
-    output.push_str("\nPVC\n");
-    for (dims, count) in pvc_entries {
-        output.push_str(&format!("{dims} - {count}\n"));
-    }
+    for group in groups {
+        let mut entries = entries_by_group.remove(&group).unwrap_or_default();
+        // the variants of a size are counted separately, listed after the regular one
+        entries.sort_by_key(|(variant, dims, _)| {
+            (dims.to_string(), !variant.is_regular(), variant.to_string())
+        });
 
-    output.push_str("\nPAPER\n");
-    for (dims, count) in paper_entries {
-        output.push_str(&format!("{dims} - {count}\n"));
+        output.push_str(&format!("\n{group}\n"));
+        for (variant, dims, count) in entries {
+            if variant.is_regular() {
+                output.push_str(&format!("{dims} - {count}\n"));
+            } else {
+                output.push_str(&format!("{dims} {variant} - {count}\n"));
+            }
+        }
     }
 
     let mut file = File::create("poruchka_stickeri.txt")?;
@@ -1,34 +1,91 @@
+use crate::similarity::{DescriptionIndex, Similarity};
 use crate::structs::{
-    color::Color, dimensions::Dimensions, material::Material,
-    parse_stcker_error::ParseStickerError, sticker::Sticker,
+    archive_file::ArchiveFile,
+    code_normalizer::code_normalizer,
+    color::Color,
+    dimensions::{self, Dimensions, DimensionsSuggestion},
+    material::Material,
+    parse_stcker_error::{ErrorContext, ParseStickerError, SubParser},
+    provenance::{InferredField, Provenance},
+    status::{StatusFlag, StickerStatus},
+    sticker::Sticker,
+    variant::Variant,
+    vocabulary::vocabulary,
 };
 use rayon::prelude::*;
 use regex::Regex;
+use std::ops::Range;
 use std::sync::LazyLock;
 use std::{fs, path::Path};
 
+/// Bump whenever parsing gives other stickers or errors for the same name, or `Sticker` or
+/// `ParseStickerError` change, the archive index is then rebuilt
+pub const PARSER_VERSION: u32 = 2;
+
 // use Lazy to build the regexes only once and keep the helper functions clean
 pub static CODE_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^([\p{L}\p{N}]{3,})").unwrap());
 pub static DIMENSIONS_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\d+[ХX]\d+").unwrap());
-pub static MATERIAL_RE: LazyLock<Regex> = LazyLock::new(|| {
-    Regex::new(
-        r"(?i)(PAPER([_ ().&-]+GR)?|LEAFLET|PP|PVC([_ ().&-]+R([_ ().&-]+SLV)?)?|SLV)([_ ().&-]+|$)",
-    )
-    .unwrap()
+// a status suffix is a whole token, so 1ST in `1ST 68X40` is not a double sticker, and ST only
+// counts after DV or DB, a lone ST is usually an abbreviation like `ST IVAN`
+pub static STATUS_RES: LazyLock<Vec<(StatusFlag, Regex)>> = LazyLock::new(|| {
+    [
+        (StatusFlag::Approved, "OK"),
+        (StatusFlag::PrintFile, "PF"),
+        (
+            StatusFlag::DoubleSticker,
+            r"(?:DV|DB)(?:[_ .]+ST)?|DVOEN[_ ]+STIKER|DVA[_ ]+STIKERA|ДВОЕН[_ ]+СТИКЕР",
+        ),
+        (StatusFlag::TempSize, r"TEMP[_ ]+SIZE"),
+    ]
+    .into_iter()
+    .map(|(flag, pattern)| {
+        let re = Regex::new(&format!(r"(?i)(?:^|[_ .&()-])(?:{pattern})(?:[_ .&()-]|$)")).unwrap();
+        (flag, re)
+    })
+    .collect()
 });
-pub static COLOR_RE: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r"(?i)BLK|BLACK|RED|GREEN|BLUE").unwrap());
 
+/// Byte range of `part` in `name`, `part` is expected to be a slice of `name`
+fn span_of(name: &str, part: &str) -> Range<usize> {
+    let name_start = name.as_ptr() as usize;
+    let part_start = part.as_ptr() as usize;
+
+    if part_start >= name_start && part_start + part.len() <= name_start + name.len() {
+        let start = part_start - name_start;
+        start..start + part.len()
+    } else {
+        name.find(part)
+            .map(|start| start..start + part.len())
+            .unwrap_or(0..name.len())
+    }
+}
+
+/// The normalized code at the start of the name
 pub fn extract_code(name: &str) -> Result<String, ParseStickerError> {
+    extract_code_token(name).map(|token| code_normalizer().normalize(token))
+}
+
+/// The code at the start of the name as written, after a configured prefix
+pub fn extract_code_token(name: &str) -> Result<&str, ParseStickerError> {
+    let unprefixed = code_normalizer().strip_prefix(name);
+    let prefix_len = name.len() - unprefixed.len();
     CODE_RE
-        .captures(name)
+        .captures(unprefixed)
         .and_then(|caps| caps.get(1))
-        .map(|m| m.as_str().to_owned())
-        .ok_or_else(|| ParseStickerError::MissingCode(name.to_string()))
+        .map(|m| m.as_str())
+        .ok_or_else(|| {
+            // the code is expected to be the first token of the name
+            let token_end = unprefixed.find(['_', ' ']).unwrap_or(unprefixed.len());
+            let token = &unprefixed[..token_end];
+            ParseStickerError::MissingCode(
+                ErrorContext::new(name, prefix_len..prefix_len + token_end)
+                    .rejecting((!token.is_empty()).then(|| token.to_owned())),
+            )
+        })
 }
 
 pub fn split_at_dimensions(name: &str) -> Result<(&str, &str), ParseStickerError> {
-    let output = DIMENSIONS_RE
+    DIMENSIONS_RE
         .find_iter(name)
         .find_map(|m| {
             m.as_str()
@@ -36,8 +93,29 @@ pub fn split_at_dimensions(name: &str) -> Result<(&str, &str), ParseStickerError
                 .ok()
                 .map(|_| name.split_at(m.start()))
         })
-        .ok_or_else(|| ParseStickerError::MissingDimensions(name.to_string()));
-    output
+        .ok_or_else(|| {
+            let candidates: Vec<_> = DIMENSIONS_RE.find_iter(name).collect();
+            let span = candidates
+                .first()
+                .map(|m| m.range())
+                .unwrap_or(0..name.len());
+            let rejected = candidates.iter().map(|m| m.as_str().to_owned());
+
+            ParseStickerError::MissingDimensions(
+                ErrorContext::new(name, span).rejecting(rejected),
+                suggest_dimensions(name),
+            )
+        })
+}
+
+/// Closest official sizes for every `WxH` in the name that is not in the catalog
+pub fn suggest_dimensions(name: &str) -> Vec<DimensionsSuggestion> {
+    let catalog = dimensions::catalog();
+    DIMENSIONS_RE
+        .find_iter(name)
+        .filter_map(|m| Dimensions::parse_unchecked(m.as_str()).ok())
+        .flat_map(|rejected| catalog.suggestions(&rejected))
+        .collect()
 }
 
 pub fn extract_dimensions(end_string: &str) -> Vec<Dimensions> {
@@ -56,49 +134,62 @@ pub fn extract_description(
         .split_once(code.to_string().as_str())
         .map(|(_, tail)| tail.trim_matches(['_', ' ']).to_string())
         .filter(|desc| !desc.is_empty())
-        .map(|mut desc| {
-            if name_parts.1.contains("PROMO") {
-                desc.push_str(" PROMO");
-            }
-            if name_parts.1.contains("TESTER") {
-                desc.push_str(" TESTER");
-            }
-            desc
-        })
         .ok_or_else(|| {
-            ParseStickerError::MissingDescription(format!("{}{}", name_parts.0, name_parts.1))
+            let name = format!("{}{}", name_parts.0, name_parts.1);
+            let description_start = name_parts
+                .0
+                .find(code)
+                .map(|start| start + code.len())
+                .unwrap_or(0);
+            ParseStickerError::MissingDescription(ErrorContext::new(
+                &name,
+                description_start..name_parts.0.len(),
+            ))
         })
 }
 
 pub fn extract_material(material_part: &str, name: &str) -> Result<Material, ParseStickerError> {
-    let material_part = material_part
-        .replace("OK", "")
-        .replace("DV", "")
-        .replace("PF", "")
-        .replace("ST", "");
+    let span = span_of(name, material_part);
     let material_part = material_part.trim_matches(['_', ' ', '.']);
 
-    let longest_match = MATERIAL_RE
-        .find_iter(material_part)
-        .max_by_key(|m| m.as_str().len());
-
-    if let Some(m) = longest_match {
-        m.as_str().parse()
-    } else if name.contains("LEAFLET") {
-        Ok(Material::LEAFLET)
+    let vocabulary = vocabulary();
+    if let Some((entry, _)) = vocabulary.find_material(material_part) {
+        Ok(entry.material.clone())
+    } else if let Some(entry) = vocabulary.find_material_anywhere(name) {
+        Ok(entry.material.clone())
     } else {
-        Err(ParseStickerError::MissingMaterial(name.to_owned()))
+        Err(ParseStickerError::MissingMaterial(ErrorContext::new(
+            name, span,
+        )))
     }
 }
 
 pub fn extract_color(end_part: &str) -> Option<Color> {
     let end_part = end_part.trim_matches(['_', ' ', '.']);
 
-    if let Some(m) = COLOR_RE.find(end_part) {
-        m.as_str().parse().ok()
-    } else {
-        None
-    }
+    vocabulary()
+        .find_color(end_part)
+        .map(|(entry, _)| entry.color.clone())
+}
+
+/// The status suffixes in the part of the name after the dimensions
+pub fn extract_status(end_part: &str) -> StickerStatus {
+    STATUS_RES
+        .iter()
+        .filter(|(_, re)| re.is_match(end_part))
+        .map(|(flag, _)| *flag)
+        .collect()
+}
+
+/// The variant named after the dimensions, or anywhere in the name for `anywhere` variants
+pub fn extract_variant(end_part: &str, name: &str) -> Variant {
+    let vocabulary = vocabulary();
+    vocabulary
+        .find_variant(end_part)
+        .map(|(entry, _)| entry)
+        .or_else(|| vocabulary.find_variant_anywhere(name))
+        .map(|entry| entry.variant.clone())
+        .unwrap_or_default()
 }
 
 pub fn parse_names(names: &[String]) -> Vec<Result<Vec<Sticker>, ParseStickerError>> {
@@ -108,34 +199,58 @@ pub fn parse_names(names: &[String]) -> Vec<Result<Vec<Sticker>, ParseStickerErr
         .collect()
 }
 
-#[cfg(any(feature = "error_handling", feature = "inferring"))]
-use strsim::normalized_levenshtein;
-#[cfg(any(feature = "error_handling", feature = "inferring"))]
+pub fn parse_files(files: &[ArchiveFile]) -> Vec<Result<Vec<Sticker>, ParseStickerError>> {
+    files.par_iter().map(Sticker::parse_file).collect()
+}
+
+/// `infer_stickers` with a Levenshtein index of `parsed_stickers`
 pub fn try_infering_code_by_description_similiarity_measure(
     error: &ParseStickerError,
     parsed_stickers: &[Sticker],
     levenshtein_distance_bound: f64,
 ) -> Result<Vec<Sticker>, ParseStickerError> {
-    if let ParseStickerError::MissingCode(name) = &error {
-        let error_description = split_at_dimensions(name)?
+    let index = DescriptionIndex::new(parsed_stickers, Similarity::Levenshtein);
+    infer_stickers(error, &index, levenshtein_distance_bound)
+}
+
+/// The stickers of a name without a code, with the codes of the similar descriptions.
+///
+/// The stickers are marked inferred, with the file of each code and how similar it is.
+///
+/// The index is built once for all errors of a run, each error is only searched in it.
+pub fn infer_stickers(
+    error: &ParseStickerError,
+    index: &DescriptionIndex,
+    threshold: f64,
+) -> Result<Vec<Sticker>, ParseStickerError> {
+    if let ParseStickerError::MissingCode(context) = &error {
+        let name = &context.name;
+        let error_description = split_at_dimensions(name)
+            .map_err(|e| match error.path() {
+                Some(path) => e.with_path(path),
+                None => e,
+            })?
             .0
             .trim_matches(['_', ' '].as_ref());
 
-        let similar_stickers: Vec<Sticker> = parsed_stickers
-            .par_iter()
-            .enumerate()
-            .map(|(i, sticker)| {
-                (
-                    i,
-                    normalized_levenshtein(error_description, &sticker.description).abs(),
-                )
-            })
-            .filter(|(_, levenshtein)| *levenshtein >= levenshtein_distance_bound)
-            .flat_map(|(i, _)| {
-                Sticker::parse_stickers(
-                    (parsed_stickers[i].code.clone().to_string() + "_" + name).as_str(),
-                )
-                .unwrap_or_default()
+        let similar_stickers: Vec<Sticker> = index
+            .similar(error_description, threshold)
+            .into_iter()
+            .flat_map(|similar| {
+                let provenance = Provenance::Inferred(vec![InferredField {
+                    name: name.clone(),
+                    field: SubParser::Code,
+                    value: similar.code.to_owned(),
+                    source: similar.source.to_owned(),
+                    score: similar.score,
+                }]);
+                Sticker::parse_stickers(&format!("{}_{name}", similar.code))
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(move |sticker| Sticker {
+                        provenance: provenance.clone(),
+                        ..sticker
+                    })
             })
             .collect();
 
@@ -150,12 +265,19 @@ pub fn try_infering_code_by_description_similiarity_measure(
 }
 
 pub fn collect_cdr_prefixes(dir: &Path) -> Vec<String> {
-    fn visit_dir(path: &Path, prefixes: &mut Vec<String>) {
+    collect_cdr_files(dir)
+        .into_iter()
+        .map(|file| file.name)
+        .collect()
+}
+
+pub fn collect_cdr_files(dir: &Path) -> Vec<ArchiveFile> {
+    fn visit_dir(path: &Path, files: &mut Vec<ArchiveFile>) {
         if let Ok(entries) = fs::read_dir(path) {
             for entry in entries.flatten() {
                 let entry_path = entry.path();
                 if entry_path.is_dir() {
-                    visit_dir(&entry_path, prefixes);
+                    visit_dir(&entry_path, files);
                 } else if entry_path
                     .extension()
                     .is_some_and(|ext| ext.eq_ignore_ascii_case("cdr"))
@@ -163,7 +285,13 @@ pub fn collect_cdr_prefixes(dir: &Path) -> Vec<String> {
                     if let Some(file_stem) = entry_path.file_stem().and_then(|s| s.to_str()) {
                         let upper_stem = file_stem.to_uppercase();
                         if !upper_stem.contains("BACKUP") {
-                            prefixes.push(upper_stem.replace(" _", "_"));
+                            let metadata = entry.metadata().ok();
+                            files.push(ArchiveFile {
+                                name: ArchiveFile::name_from_stem(file_stem),
+                                size: metadata.as_ref().map_or(0, |m| m.len()),
+                                modified: metadata.and_then(|m| m.modified().ok()),
+                                path: entry_path,
+                            });
                         }
                     }
                 }
@@ -171,7 +299,7 @@ pub fn collect_cdr_prefixes(dir: &Path) -> Vec<String> {
         }
     }
 
-    let mut prefixes = Vec::new();
-    visit_dir(dir, &mut prefixes);
-    prefixes
+    let mut files = Vec::new();
+    visit_dir(dir, &mut files);
+    files
 }
@@ -1,18 +1,20 @@
-use crate::{configs::Configs, excel, structs::parse_stcker_error::ParseStickerError};
+use crate::{
+    configs::{AmbiguousInferences, Configs},
+    inference::AmbiguousInference,
+    structs::{
+        code_aliases::AliasProblem, order::Order, parse_stcker_error::ParseStickerError,
+        provenance::InferredField, sticker_overrides::StickerOverride,
+    },
+};
 use colored::*;
-use std::{collections::HashMap, fmt::Write};
+use std::{
+    collections::HashMap,
+    fmt::{self, Write},
+    path::Path,
+};
 use strsim::normalized_levenshtein;
 
-#[cfg(feature = "error_handling")]
-pub fn print_relevant_errors(errors: &[ParseStickerError], configs: &Configs) {
-    let orders = match excel::parse_orders(configs) {
-        Ok(orders) => orders,
-        Err(e) => {
-            eprintln!("{}: {:?}", "Failed to parse orders".red().bold(), e);
-            return;
-        }
-    };
-
+pub fn print_relevant_errors(errors: &[ParseStickerError], orders: &[Order], configs: &Configs) {
     eprintln!(
         "\n{}: [{} {} {}]\n",
         "Filtered Errors Based on Description Similarity"
@@ -27,43 +29,43 @@ pub fn print_relevant_errors(errors: &[ParseStickerError], configs: &Configs) {
         "similarity".dimmed()
     );
 
-    for order in &orders {
+    for order in orders {
         let mut similarity_matches = String::new();
         let mut code_matches = String::new();
 
         for error in errors {
             let error_str = error.get_description();
-            let similarity = normalized_levenshtein(
-                error_str.as_str(),
-                format!("{}_{}", order.code, &order.description).as_str(),
-            );
+            let similarity = order_similarity(order, error);
 
             if similarity >= configs.error_output_levenshtein_distance
-                && writeln!(
+                && (writeln!(
                     &mut similarity_matches,
                     "\t\t{} \"{}\" {} \"{}\" {} {:.2}{}",
                     "↳ Similar to file name:".cyan(),
-                    error_str.yellow(),
+                    highlighted_name(error),
                     "with error".dimmed(),
-                    format!("{:?}", error).italic(),
+                    error.details().italic(),
                     "(similarity:".dimmed(),
                     similarity,
                     ")".dimmed(),
                 )
                 .is_err()
+                    || write_error_context(&mut similarity_matches, error).is_err())
             {
                 eprintln!("Write to buffer failed");
             }
 
             if error_str.contains(&order.code.to_string())
-                && writeln!(
+                && (writeln!(
                     &mut code_matches,
-                    "\t\t{} {}: {}",
+                    "\t\t{} {}: \"{}\" {}",
                     "↳ Error contains code".magenta(),
                     order.code.to_string().yellow(),
-                    format!("{:?}", error).italic()
+                    highlighted_name(error),
+                    error.details().italic()
                 )
                 .is_err()
+                    || write_error_context(&mut code_matches, error).is_err())
             {
                 eprintln!("Write to buffer failed");
             }
@@ -98,7 +100,136 @@ pub fn print_relevant_errors(errors: &[ParseStickerError], configs: &Configs) {
     }
 }
 
-#[cfg(feature = "error_handling")]
+/// How similar the failing file name is to `CODE_DESCRIPTION` of the order
+pub fn order_similarity(order: &Order, error: &ParseStickerError) -> f64 {
+    normalized_levenshtein(
+        &error.get_description(),
+        &format!("{}_{}", order.code, order.description),
+    )
+}
+
+/// Aliases that are not used because they form a cycle or their old code has no stickers
+pub fn print_alias_problems(problems: &[AliasProblem]) {
+    if problems.is_empty() {
+        return;
+    }
+    eprintln!("\n{}:", "Code Alias Problems".underline().bold().blue());
+    for problem in problems {
+        eprintln!("\t{} {}", "↳".yellow(), problem);
+    }
+}
+
+/// The lines of the overrides file that are not used, the files they are for are parsed
+pub fn print_override_problems(path: &Path, malformed: &[(usize, String)]) {
+    if malformed.is_empty() {
+        return;
+    }
+    eprintln!(
+        "\n{} {}:",
+        "Override Problems".underline().bold().blue(),
+        format!("{path:?}").dimmed()
+    );
+    for (line, reason) in malformed {
+        eprintln!("\t{} line {line}: {}", "↳".yellow(), reason.red());
+    }
+}
+
+/// Overrides for archive files that do not exist anymore, they can be removed from the file
+pub fn print_stale_overrides(stale: &[StickerOverride]) {
+    if stale.is_empty() {
+        return;
+    }
+    eprintln!("\n{}:", "Stale Overrides".underline().bold().blue());
+    for entry in stale {
+        eprintln!(
+            "\t{} line {}: no archive file {}",
+            "↳".yellow(),
+            entry.line,
+            entry.target.to_string().red()
+        );
+    }
+}
+
+/// Sizes and materials missing from file names that were taken from other archive files
+pub fn print_inferred_fields(fields: &[InferredField]) {
+    if fields.is_empty() {
+        return;
+    }
+    eprintln!("\n{}:", "Inferred Fields".underline().bold().blue());
+    for inferred in fields {
+        eprintln!(
+            "\t{} {}: {} {} from {} ({:.2})",
+            "↳".yellow(),
+            inferred.name,
+            inferred.field,
+            inferred.value.green(),
+            inferred.source.italic(),
+            inferred.score
+        );
+    }
+}
+
+/// Names without a code whose description matched several codes, with every candidate code
+pub fn print_ambiguous_inferences(ambiguous: &[AmbiguousInference], use_them: AmbiguousInferences) {
+    if ambiguous.is_empty() {
+        return;
+    }
+    let title = match use_them {
+        AmbiguousInferences::Exclude => "Ambiguous Inferences (not used)",
+        AmbiguousInferences::Include => "Ambiguous Inferences (used for every code)",
+    };
+    eprintln!("\n{}:", title.underline().bold().blue());
+    for inference in ambiguous {
+        eprintln!(
+            "\t{} \"{}\"",
+            "↳".yellow(),
+            inference.error.get_description().red()
+        );
+        for candidate in &inference.candidates {
+            eprintln!(
+                "\t\t{} {:.2} {}",
+                candidate.value.yellow(),
+                candidate.score,
+                candidate.source.italic()
+            );
+        }
+    }
+}
+
+/// The file name with the part the failing sub-parser looked at in red
+pub fn highlighted_name(error: &ParseStickerError) -> String {
+    let (before, failed, after) = error.context().split();
+    format!(
+        "{}{}{}",
+        before.yellow(),
+        failed.red().bold().underline(),
+        after.yellow()
+    )
+}
+
+fn write_error_context(buffer: &mut String, error: &ParseStickerError) -> fmt::Result {
+    if let Some(path) = error.path() {
+        writeln!(
+            buffer,
+            "\t\t\t{} {}",
+            "↳ File:".dimmed(),
+            path.display().to_string().dimmed()
+        )?;
+    }
+    for suggestion in error.dimensions_suggestions() {
+        writeln!(
+            buffer,
+            "\t\t\t{} {} {} {}{}",
+            "↳ Closest official size:".green(),
+            suggestion.to_string().yellow(),
+            "(off by".dimmed(),
+            suggestion.distance,
+            " mm)".dimmed(),
+        )?;
+    }
+    Ok(())
+}
+
 pub fn print_errors_grouped_by_type(errors: &[ParseStickerError]) {
     let mut grouped: HashMap<String, Vec<&ParseStickerError>> = HashMap::new();
 
@@ -116,7 +247,13 @@ pub fn print_errors_grouped_by_type(errors: &[ParseStickerError]) {
         eprintln!("{} {}", "•".red(), error_type.bold().green());
 
         for error in group {
-            eprintln!("\t{}: {}", "↳".dimmed(), error.get_description().yellow());
+            eprintln!("\t{}: {}", "↳".dimmed(), highlighted_name(error));
+            if !error.context().rejected.is_empty() {
+                eprintln!("\t\t{}", error.details().italic());
+            }
+            if let Some(path) = error.path() {
+                eprintln!("\t\t{}", path.display().to_string().dimmed());
+            }
         }
 
         eprintln!("{}", "-".repeat(60).dimmed());
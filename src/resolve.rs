@@ -0,0 +1,392 @@
+use crate::{
+    configs::{Configs, DEFAULT_CODE_ALIASES, DEFAULT_OVERRIDES},
+    excel::parse_orders,
+    parser::{extract_color, extract_material, DIMENSIONS_RE},
+    report::{highlighted_name, order_similarity},
+    runs::{self, Pipeline},
+    structs::{
+        code_aliases::CodeAliases,
+        code_normalizer::code_normalizer,
+        dimensions::Dimensions,
+        material::Material,
+        order::Order,
+        parse_stcker_error::ParseStickerError,
+        sticker::Sticker,
+        sticker_overrides::{OverrideTarget, StickerOverride, StickerOverrides},
+        vocabulary::vocabulary,
+    },
+};
+use chrono::Local;
+use colored::*;
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+
+// how many candidate files are offered for one order
+const MAX_CANDIDATES: usize = 9;
+
+/// A failing archive file that may hold the stickers of a missing order
+#[derive(Debug, Clone)]
+pub struct Candidate<'a> {
+    pub error: &'a ParseStickerError,
+    pub similarity: f64,
+}
+
+/// What the operator decided for a missing order
+#[derive(Debug, Clone, PartialEq)]
+pub enum Decision {
+    /// The stickers of an archive file whose name does not parse
+    Override(StickerOverride),
+    /// The order uses the stickers of another code
+    Alias { old: String, new: String },
+}
+
+/// Where the decisions are appended, the files a run reads the overrides and aliases from
+#[derive(Debug, Clone)]
+pub struct DecisionFiles {
+    pub overrides: PathBuf,
+    pub code_aliases: PathBuf,
+}
+
+impl DecisionFiles {
+    /// The configured files, or the default ones that are used without configuring them
+    pub fn new(configs: &Configs) -> Self {
+        Self {
+            overrides: configs
+                .overrides_path
+                .clone()
+                .unwrap_or_else(|| DEFAULT_OVERRIDES.into()),
+            code_aliases: configs
+                .code_aliases_path
+                .clone()
+                .unwrap_or_else(|| DEFAULT_CODE_ALIASES.into()),
+        }
+    }
+
+    /// Appends the decision to its file as one line.
+    ///
+    /// The overrides file is read back, a line a run could not use is reported right away.
+    pub fn save(&self, decision: &Decision) -> io::Result<()> {
+        match decision {
+            Decision::Override(entry) => {
+                append_line(&self.overrides, &entry.to_string())?;
+                StickerOverrides::load(&self.overrides)
+                    .and_then(|overrides| overrides.check())
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))
+            }
+            Decision::Alias { old, new } => {
+                append_line(&self.code_aliases, &format!("{old} -> {new}"))
+            }
+        }
+    }
+}
+
+fn append_line(path: &Path, line: &str) -> io::Result<()> {
+    // a file edited by hand may not end with a new line
+    let needs_newline = fs::read(path).is_ok_and(|bytes| bytes.last().is_some_and(|b| *b != b'\n'));
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    if needs_newline {
+        writeln!(file)?;
+    }
+    writeln!(file, "{line}")
+}
+
+/// The failing files similar to the order or containing its code, most similar first
+pub fn candidates<'a>(
+    order: &Order,
+    errors: &'a [ParseStickerError],
+    threshold: f64,
+) -> Vec<Candidate<'a>> {
+    let mut candidates: Vec<Candidate> = errors
+        .iter()
+        .map(|error| Candidate {
+            error,
+            similarity: order_similarity(order, error),
+        })
+        .filter(|candidate| {
+            candidate.similarity >= threshold
+                || candidate.error.get_description().contains(&order.code)
+        })
+        .collect();
+    candidates.sort_by(|a, b| b.similarity.total_cmp(&a.similarity));
+    candidates.truncate(MAX_CANDIDATES);
+    candidates
+}
+
+/// Walks the operator through the missing orders on a terminal, or any reader and writer
+pub struct Resolver<R, W> {
+    input: R,
+    output: W,
+    /// Override paths are written relative to the archive
+    archive: PathBuf,
+    /// Lowest similarity of a file name to be offered for an order
+    threshold: f64,
+}
+
+impl<R: BufRead, W: Write> Resolver<R, W> {
+    pub fn new(input: R, output: W, archive: &Path, threshold: f64) -> Self {
+        Self {
+            input,
+            output,
+            archive: archive.to_path_buf(),
+            threshold,
+        }
+    }
+
+    /// Asks for each missing order what to do and saves every decision as soon as it is made.
+    ///
+    /// Stops at `q` or the end of the input, the decisions made so far are kept.
+    pub fn resolve(
+        &mut self,
+        missing: &[Order],
+        errors: &[ParseStickerError],
+        code_to_stickers: &HashMap<String, Vec<Sticker>>,
+        aliases: &CodeAliases,
+        files: &DecisionFiles,
+    ) -> io::Result<Vec<Decision>> {
+        let mut decisions = Vec::new();
+        // an archive file gets one override and an old code one alias
+        let mut used_files: HashSet<String> = HashSet::new();
+        let mut used_codes: HashSet<String> = HashSet::new();
+
+        for (index, order) in missing.iter().enumerate() {
+            let candidates: Vec<Candidate> = candidates(order, errors, self.threshold)
+                .into_iter()
+                .filter(|candidate| !used_files.contains(&candidate.error.get_description()))
+                .collect();
+            self.show_order(order, index, missing.len(), &candidates)?;
+
+            let decision = loop {
+                let Some(answer) = self.ask("> ")? else {
+                    return Ok(decisions);
+                };
+                match answer.as_str() {
+                    "" | "s" => break None,
+                    "q" => return Ok(decisions),
+                    _ => {}
+                }
+
+                if let Some(code) = answer.strip_prefix("c ") {
+                    let old = code_normalizer().normalize(code);
+                    if old == order.code {
+                        writeln!(self.output, "{old} is the ordered code")?;
+                    } else if !code_to_stickers.contains_key(&old) {
+                        writeln!(self.output, "{old} has no stickers in the archive")?;
+                    } else if let Some(new) = aliases.get(&old) {
+                        writeln!(self.output, "{old} is already an alias of {new}")?;
+                    } else if used_codes.contains(&old) {
+                        writeln!(self.output, "{old} was already used for another order")?;
+                    } else {
+                        used_codes.insert(old.clone());
+                        break Some(Decision::Alias {
+                            old,
+                            new: order.code.clone(),
+                        });
+                    }
+                    continue;
+                }
+
+                let chosen = answer
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|number| candidates.get(number.wrapping_sub(1)));
+                match chosen {
+                    Some(candidate) => match self.build_override(order, candidate.error)? {
+                        Some(entry) => {
+                            used_files.insert(candidate.error.get_description());
+                            break Some(Decision::Override(entry));
+                        }
+                        None => writeln!(self.output, "Not saved")?,
+                    },
+                    None => writeln!(
+                        self.output,
+                        "Type a number from the list, 'c CODE', 's' or 'q'"
+                    )?,
+                }
+            };
+
+            if let Some(decision) = decision {
+                files.save(&decision)?;
+                writeln!(self.output, "{} {}", "Saved".green(), describe(&decision))?;
+                decisions.push(decision);
+            }
+        }
+        Ok(decisions)
+    }
+
+    fn show_order(
+        &mut self,
+        order: &Order,
+        index: usize,
+        count: usize,
+        candidates: &[Candidate],
+    ) -> io::Result<()> {
+        writeln!(self.output, "{}", "_".repeat(100).dimmed())?;
+        writeln!(
+            self.output,
+            "{} [{}/{}]: \"{}_{}\" ({})",
+            "Missing order".bright_blue().bold(),
+            index + 1,
+            count,
+            order.code.yellow(),
+            order.description.green(),
+            order.amount
+        )?;
+        if candidates.is_empty() {
+            writeln!(self.output, "\tNo similar file names")?;
+        }
+        for (number, candidate) in candidates.iter().enumerate() {
+            writeln!(
+                self.output,
+                "\t{}) {:.2} \"{}\" {}",
+                number + 1,
+                candidate.similarity,
+                highlighted_name(candidate.error),
+                candidate.error.details().italic()
+            )?;
+            if let Some(path) = candidate.error.path() {
+                writeln!(self.output, "\t   {}", path.display().to_string().dimmed())?;
+            }
+        }
+        writeln!(
+            self.output,
+            "[number] use the file, [c CODE] use the stickers of CODE, [s] skip, [q] quit"
+        )
+    }
+
+    /// An override of the file with the order's code and description.
+    ///
+    /// The sizes, material and color are taken from the file name, the ones it does not name
+    /// are asked for. `None` when an answer is left empty or the input ends.
+    fn build_override(
+        &mut self,
+        order: &Order,
+        error: &ParseStickerError,
+    ) -> io::Result<Option<StickerOverride>> {
+        let name = &error.context().name;
+
+        let mut dimensions: Vec<Dimensions> = DIMENSIONS_RE
+            .find_iter(name)
+            .filter_map(|m| Dimensions::parse_unchecked(m.as_str()).ok())
+            .collect();
+        while dimensions.is_empty() {
+            let Some(answer) = self
+                .ask("Sizes (WxH, separated by commas): ")?
+                .filter(|answer| !answer.is_empty())
+            else {
+                return Ok(None);
+            };
+            match answer
+                .split(',')
+                .map(Dimensions::parse_unchecked)
+                .collect::<Result<Vec<_>, _>>()
+            {
+                Ok(parsed) => dimensions = parsed,
+                Err(e) => writeln!(self.output, "{e}")?,
+            }
+        }
+
+        // after the sizes like the parser, anywhere in names without a size
+        let named_material = DIMENSIONS_RE
+            .split(name)
+            .skip(1)
+            .find_map(|part| extract_material(part, name).ok())
+            .or_else(|| {
+                vocabulary()
+                    .find_material(name)
+                    .map(|(entry, _)| entry.material.clone())
+            });
+        let material = match named_material {
+            Some(material) => material,
+            None => loop {
+                let Some(answer) = self.ask("Material: ")?.filter(|answer| !answer.is_empty())
+                else {
+                    return Ok(None);
+                };
+                match answer.parse::<Material>() {
+                    Ok(material) => break material,
+                    Err(_) => writeln!(self.output, "Unknown material '{answer}'")?,
+                }
+            },
+        };
+
+        let target = match error.path() {
+            Some(path) => OverrideTarget::Path(
+                path.strip_prefix(&self.archive)
+                    .unwrap_or(path)
+                    .to_string_lossy()
+                    .replace('\\', "/")
+                    .into(),
+            ),
+            None => OverrideTarget::Stem(name.clone()),
+        };
+
+        Ok(Some(StickerOverride {
+            target,
+            line: 0,
+            code: order.code.clone(),
+            description: order.description.to_uppercase(),
+            dimensions,
+            material,
+            color: extract_color(name).unwrap_or_default(),
+        }))
+    }
+
+    /// The trimmed answer, `None` at the end of the input
+    fn ask(&mut self, prompt: &str) -> io::Result<Option<String>> {
+        write!(self.output, "{prompt}")?;
+        self.output.flush()?;
+        let mut answer = String::new();
+        if self.input.read_line(&mut answer)? == 0 {
+            return Ok(None);
+        }
+        Ok(Some(answer.trim().to_owned()))
+    }
+}
+
+fn describe(decision: &Decision) -> String {
+    match decision {
+        Decision::Override(entry) => format!("override {entry}"),
+        Decision::Alias { old, new } => format!("alias {old} -> {new}"),
+    }
+}
+
+/// Parses the archive and the orders and asks on the terminal how to resolve each missing order
+pub fn resolve(pipeline: &Pipeline, configs: &Configs) -> Result<(), Box<dyn std::error::Error>> {
+    let (stickers, errors) = pipeline.parse_archive(configs);
+    let mut code_to_stickers = runs::group_by_code(&stickers);
+    let aliases = runs::load_code_aliases(configs);
+    aliases.apply(&mut code_to_stickers);
+
+    let mut seen = HashSet::new();
+    let missing: Vec<Order> = parse_orders(configs)?
+        .into_iter()
+        .filter(|order| !code_to_stickers.contains_key(&order.code))
+        // orders of several variants of a code are resolved once
+        .filter(|order| seen.insert(order.code.clone()))
+        .collect();
+    if missing.is_empty() {
+        println!("Every order has stickers, nothing to resolve");
+        return Ok(());
+    }
+
+    let files = DecisionFiles::new(configs);
+    let decisions = Resolver::new(
+        io::stdin().lock(),
+        io::stdout(),
+        &configs.archive_path,
+        configs.error_output_levenshtein_distance,
+    )
+    .resolve(&missing, &errors, &code_to_stickers, &aliases, &files)?;
+
+    println!(
+        "\n{} {} decisions on {}, overrides in {:?} and aliases in {:?} are used from the next run",
+        "Saved".green().bold(),
+        decisions.len(),
+        Local::now().format("%d.%m.%Y %H:%M"),
+        files.overrides,
+        files.code_aliases
+    );
+    Ok(())
+}
@@ -0,0 +1,294 @@
+use crate::{
+    configs::{AmbiguousInferences, Configs},
+    inference::AmbiguousInference,
+    order_summary::{self, MaterialCounts},
+    structs::{
+        order::Order,
+        parse_stcker_error::ParseStickerError,
+        provenance::{InferredField, Provenance},
+        sticker::Sticker,
+    },
+};
+use chrono::Local;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Raised whenever a field is renamed or removed, adding fields keeps the version
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Everything a run printed, for scripts, written with `--report-json`
+#[derive(Debug, Clone, Serialize)]
+pub struct RunReport {
+    pub schema_version: u32,
+    /// Local time, RFC 3339
+    pub generated_at: String,
+    /// The config values in use, as `check-config` prints them
+    pub config: BTreeMap<String, String>,
+    pub stickers: StickerCounts,
+    /// The files that could not be parsed, by error type
+    pub errors: BTreeMap<String, Vec<ErrorEntry>>,
+    pub inferred: Vec<InferredSticker>,
+    pub ambiguous: Vec<AmbiguousEntry>,
+    pub orders: OrderReport,
+    /// What the orders need of each material group, size and variant
+    pub materials: Vec<MaterialTotal>,
+    pub timings: Timings,
+}
+
+/// Stickers by where they came from, aliased ones are copies listed under a new code
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct StickerCounts {
+    pub total: usize,
+    pub parsed: usize,
+    pub inferred: usize,
+    pub overridden: usize,
+    pub aliased: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ErrorEntry {
+    pub name: String,
+    pub path: Option<PathBuf>,
+    /// The error type with the rejected tokens
+    pub details: String,
+    pub rejected: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct InferredSticker {
+    pub code: String,
+    pub name: String,
+    pub dimensions: String,
+    pub material: String,
+    /// The lowest score of the fields
+    pub confidence: f64,
+    pub fields: Vec<InferredField>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AmbiguousEntry {
+    pub name: String,
+    pub path: Option<PathBuf>,
+    /// Whether its stickers were used, `ambiguous_inferences=include`
+    pub included: bool,
+    pub candidates: Vec<InferredField>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct OrderReport {
+    pub matched: Vec<OrderEntry>,
+    pub missing: Vec<OrderEntry>,
+    /// Why the orders could not be read, both lists are empty then
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct OrderEntry {
+    pub code: String,
+    pub description: String,
+    pub variant: String,
+    pub amount: u64,
+    /// The stickers of the code that match the variant of the order
+    pub stickers: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MaterialTotal {
+    pub group: String,
+    pub dimensions: String,
+    pub variant: String,
+    pub amount: u64,
+}
+
+/// Milliseconds of the steps of a run, parsing includes the overrides and inference
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Timings {
+    pub parse_ms: u64,
+    pub outputs_ms: u64,
+    pub total_ms: u64,
+}
+
+impl Timings {
+    pub fn new(parse: Duration, total: Duration) -> Self {
+        let ms = |duration: Duration| duration.as_millis() as u64;
+        Self {
+            parse_ms: ms(parse),
+            outputs_ms: ms(total.saturating_sub(parse)),
+            total_ms: ms(total),
+        }
+    }
+}
+
+/// The stickers and errors of the archive the report is made of
+pub struct RunOutcome<'a> {
+    pub stickers: &'a [Sticker],
+    pub errors: &'a [ParseStickerError],
+    pub ambiguous: &'a [AmbiguousInference],
+    /// After the aliases were applied
+    pub code_to_stickers: &'a HashMap<String, Vec<Sticker>>,
+    /// The orders, or why they could not be read
+    pub orders: Result<&'a [Order], String>,
+}
+
+impl RunReport {
+    pub fn new(configs: &Configs, outcome: &RunOutcome, timings: Timings) -> Self {
+        let orders = match &outcome.orders {
+            Ok(orders) => order_report(orders, outcome.code_to_stickers),
+            Err(e) => OrderReport {
+                error: Some(e.clone()),
+                ..OrderReport::default()
+            },
+        };
+        let materials = match outcome.orders {
+            Ok(orders) => material_totals(order_summary::count_materials(
+                orders,
+                outcome.code_to_stickers,
+            )),
+            Err(_) => Vec::new(),
+        };
+        let included = configs.ambiguous_inferences == AmbiguousInferences::Include;
+
+        Self {
+            schema_version: SCHEMA_VERSION,
+            generated_at: Local::now().to_rfc3339(),
+            config: config_entries(configs),
+            stickers: sticker_counts(outcome.stickers, outcome.code_to_stickers),
+            errors: grouped_errors(outcome.errors),
+            inferred: inferred_stickers(outcome.stickers),
+            ambiguous: outcome
+                .ambiguous
+                .iter()
+                .map(|ambiguous| AmbiguousEntry {
+                    name: ambiguous.error.get_description(),
+                    path: ambiguous.error.path().map(Path::to_path_buf),
+                    included,
+                    candidates: ambiguous.candidates.clone(),
+                })
+                .collect(),
+            orders,
+            materials,
+            timings,
+        }
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(&mut writer, self)?;
+        writeln!(writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// The `key=value` lines of the config
+fn config_entries(configs: &Configs) -> BTreeMap<String, String> {
+    configs
+        .to_string()
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.to_owned(), value.to_owned()))
+        .collect()
+}
+
+fn sticker_counts(
+    stickers: &[Sticker],
+    code_to_stickers: &HashMap<String, Vec<Sticker>>,
+) -> StickerCounts {
+    let mut counts = StickerCounts {
+        total: stickers.len(),
+        ..StickerCounts::default()
+    };
+    for sticker in stickers {
+        match sticker.provenance {
+            Provenance::Parsed => counts.parsed += 1,
+            Provenance::Inferred(_) => counts.inferred += 1,
+            Provenance::Override { .. } => counts.overridden += 1,
+            // aliases are applied to the grouped stickers
+            Provenance::Alias { .. } => {}
+        }
+    }
+    counts.aliased += code_to_stickers
+        .values()
+        .flatten()
+        .filter(|sticker| matches!(sticker.provenance, Provenance::Alias { .. }))
+        .count();
+    counts
+}
+
+/// Grouped like the `All Errors Grouped by Type` section, each group sorted by name
+fn grouped_errors(errors: &[ParseStickerError]) -> BTreeMap<String, Vec<ErrorEntry>> {
+    let mut grouped: BTreeMap<String, Vec<ErrorEntry>> = BTreeMap::new();
+    for error in errors {
+        grouped
+            .entry(error.to_string())
+            .or_default()
+            .push(ErrorEntry {
+                name: error.get_description(),
+                path: error.path().map(Path::to_path_buf),
+                details: error.details(),
+                rejected: error.context().rejected.clone(),
+            });
+    }
+    for group in grouped.values_mut() {
+        group.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+    grouped
+}
+
+fn inferred_stickers(stickers: &[Sticker]) -> Vec<InferredSticker> {
+    stickers
+        .iter()
+        .filter_map(|sticker| match &sticker.provenance {
+            Provenance::Inferred(fields) => Some(InferredSticker {
+                code: sticker.code.clone(),
+                name: sticker.full_name.clone(),
+                dimensions: sticker.dimensions.to_string(),
+                material: sticker.material.to_string(),
+                confidence: sticker.provenance.confidence(),
+                fields: fields.clone(),
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+fn order_report(orders: &[Order], code_to_stickers: &HashMap<String, Vec<Sticker>>) -> OrderReport {
+    let mut report = OrderReport::default();
+    for order in orders {
+        let entry = |stickers: usize| OrderEntry {
+            code: order.code.clone(),
+            description: order.description.clone(),
+            variant: order.variant.to_string(),
+            amount: order.amount,
+            stickers,
+        };
+        match order.stickers_in(code_to_stickers).len() {
+            0 => report.missing.push(entry(0)),
+            stickers => report.matched.push(entry(stickers)),
+        }
+    }
+    report
+}
+
+/// Sorted by group, size and variant
+fn material_totals(counts: MaterialCounts) -> Vec<MaterialTotal> {
+    let mut totals: Vec<MaterialTotal> = counts
+        .counts
+        .into_iter()
+        .map(|((group, variant, dimensions), amount)| MaterialTotal {
+            group,
+            dimensions: dimensions.to_string(),
+            variant: variant.to_string(),
+            amount,
+        })
+        .collect();
+    totals.sort_by(|a, b| {
+        (&a.group, &a.dimensions, &a.variant).cmp(&(&b.group, &b.dimensions, &b.variant))
+    });
+    totals
+}
@@ -1,178 +1,414 @@
-#[cfg(feature = "error_handling")]
-use crate::{report, structs::parse_stcker_error::ParseStickerError};
-#[cfg(feature = "error_handling")]
+use crate::{
+    configs::{AmbiguousInferences, Configs, NonOkStickers},
+    excel::{self, OrderImport},
+    index,
+    inference::{self, AmbiguousInference, FieldSources},
+    order_summary, parser, report,
+    run_report::{RunOutcome, RunReport, Timings},
+    similarity::DescriptionIndex,
+    structs::{
+        code_aliases::CodeAliases, parse_stcker_error::ParseStickerError,
+        provenance::InferredField, sticker::Sticker, sticker_overrides::StickerOverrides,
+    },
+};
 use either::Either;
-#[cfg(all(feature = "error_handling", feature = "inferring"))]
 use rayon::prelude::*;
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+    sync::Mutex,
+    time::Instant,
+};
+
+/// The stickers of the archive and what could not be parsed
+#[derive(Debug, Default)]
+pub struct ParsedArchive {
+    /// Sorted by code and deduplicated
+    pub stickers: Vec<Sticker>,
+    /// The errors that could not be recovered
+    pub errors: Vec<ParseStickerError>,
+    /// Names without a code that inference matched to several codes
+    pub ambiguous: Vec<AmbiguousInference>,
+}
 
-#[cfg(all(feature = "error_handling", feature = "inferring"))]
-use std::sync::Mutex;
-
-#[cfg(all(feature = "error_handling", not(feature = "inferring")))]
-use itertools::Itertools;
-
-use crate::{configs::Configs, excel, parser, structs::sticker::Sticker};
-use std::collections::HashMap;
-
-#[cfg(all(feature = "error_handling", feature = "inferring"))]
-const INFERRED_MARKER: &str = " !!!INFERRED!!!";
+/// The steps of a processing run, toggled at runtime.
+///
+/// `Pipeline::default()` enables the steps of the cargo features the binary was built with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pipeline {
+    inference: bool,
+    error_report: bool,
+    grouped_errors: bool,
+    production_tables: bool,
+    material_report: bool,
+    rebuild_index: bool,
+}
 
-#[cfg(all(feature = "error_handling", feature = "inferring"))]
-pub fn run_inferring() {
-    let configs = Configs::load_from_file("configs.txt");
+impl Default for Pipeline {
+    fn default() -> Self {
+        Self {
+            inference: cfg!(feature = "inferring"),
+            error_report: cfg!(feature = "error_handling"),
+            grouped_errors: cfg!(feature = "full_error_handling"),
+            production_tables: true,
+            material_report: cfg!(feature = "material_report"),
+            rebuild_index: false,
+        }
+    }
+}
 
-    let file_names = parser::collect_cdr_prefixes(&configs.archive_path);
-    let parsing_results = parser::parse_names(&file_names);
+impl Pipeline {
+    /// Pipeline with every step disabled, only the archive is parsed
+    pub fn parse_only() -> Self {
+        Self {
+            inference: false,
+            error_report: false,
+            grouped_errors: false,
+            production_tables: false,
+            material_report: false,
+            rebuild_index: false,
+        }
+    }
 
-    let (stickers_nested, errors): (Vec<Vec<Sticker>>, Vec<ParseStickerError>) = parsing_results
-        .into_par_iter()
-        .partition_map(|res| match res {
-            Ok(sticker) => Either::Left(sticker),
-            Err(error) => Either::Right(error),
-        });
+    /// Try to recover file names without a code, size or material from other archive files
+    pub fn inference(mut self, enabled: bool) -> Self {
+        self.inference = enabled;
+        self
+    }
 
-    let mut stickers: Vec<Sticker> = stickers_nested.into_iter().flatten().collect();
+    /// Print the parse errors that are similar to an order
+    pub fn error_report(mut self, enabled: bool) -> Self {
+        self.error_report = enabled;
+        self
+    }
 
-    let inferred_stickers_mutex = Mutex::new(Vec::new());
-    let unrecoverable_errors_mutex = Mutex::new(Vec::new());
+    /// Print all parse errors grouped by type
+    pub fn grouped_errors(mut self, enabled: bool) -> Self {
+        self.grouped_errors = enabled;
+        self
+    }
 
-    errors.par_iter().for_each(|error| match error {
-        ParseStickerError::MissingCode(_) => {
-            match parser::try_infering_code_by_description_similiarity_measure(
-                error,
-                &stickers,
-                configs.inferring_levenshtein_distance,
-            ) {
-                Ok(mut inferred) => {
-                    for sticker in &mut inferred {
-                        sticker.description.push_str(INFERRED_MARKER);
-                    }
-                    inferred_stickers_mutex.lock().unwrap().extend(inferred);
-                }
-                Err(e) => {
-                    unrecoverable_errors_mutex.lock().unwrap().push(e);
-                }
-            }
-        }
-        other => {
-            unrecoverable_errors_mutex
-                .lock()
-                .unwrap()
-                .push(other.clone());
-        }
-    });
+    /// Write the `orders_dd_mm_yy.xlsx` workbook
+    pub fn production_tables(mut self, enabled: bool) -> Self {
+        self.production_tables = enabled;
+        self
+    }
 
-    stickers.extend(inferred_stickers_mutex.into_inner().unwrap());
-    let unrecoverable_errors = unrecoverable_errors_mutex.into_inner().unwrap();
+    /// Write the `poruchka_stickeri.txt` material report
+    pub fn material_report(mut self, enabled: bool) -> Self {
+        self.material_report = enabled;
+        self
+    }
 
-    stickers.sort_by(|a, b| a.code.cmp(&b.code));
-    stickers.dedup();
+    /// Parse every archive file again instead of reusing the archive index
+    pub fn rebuild_index(mut self, enabled: bool) -> Self {
+        self.rebuild_index = enabled;
+        self
+    }
 
-    let mut code_to_stickers_map: HashMap<String, Vec<Sticker>> = HashMap::new();
-    for sticker in &stickers {
-        code_to_stickers_map
-            .entry(sticker.code.clone())
-            .or_default()
-            .push(sticker.clone());
+    /// Parses the archive, applying the overrides and inference when enabled.
+    ///
+    /// Returns the sorted and deduplicated stickers and the errors that could not be recovered.
+    pub fn parse_archive(&self, configs: &Configs) -> (Vec<Sticker>, Vec<ParseStickerError>) {
+        let parsed = self.parse(configs);
+        (parsed.stickers, parsed.errors)
     }
 
-    if !unrecoverable_errors.is_empty() {
-        report::print_relevant_errors(&unrecoverable_errors, &configs);
+    /// `parse_archive` that also returns the ambiguous inferences
+    pub fn parse(&self, configs: &Configs) -> ParsedArchive {
+        let applied =
+            load_overrides(configs).apply(parser::collect_cdr_files(&configs.archive_path));
+        report::print_stale_overrides(&applied.stale);
+        let files = applied.remaining;
+        let parsing_results = match &configs.archive_index_path {
+            Some(index_path) => {
+                let (results, stats) =
+                    index::parse_files_indexed(&files, index_path, self.rebuild_index);
+                println!("Archive index: {stats}");
+                results
+            }
+            None => parser::parse_files(&files),
+        };
+
+        let (stickers_nested, errors): (Vec<Vec<Sticker>>, Vec<ParseStickerError>) =
+            parsing_results
+                .into_par_iter()
+                .partition_map(|res| match res {
+                    Ok(sticker) => Either::Left(sticker),
+                    Err(error) => Either::Right(error),
+                });
+
+        let mut stickers: Vec<Sticker> = stickers_nested.into_iter().flatten().collect();
+        stickers.extend(applied.stickers);
+
+        let mut ambiguous = Vec::new();
+        let errors = if self.inference {
+            let inferred = infer_missing(&errors, &stickers, configs);
+            report::print_inferred_fields(&inferred.fields);
+            report::print_ambiguous_inferences(&inferred.ambiguous, configs.ambiguous_inferences);
+            stickers.extend(inferred.stickers);
+            ambiguous = inferred.ambiguous;
+            inferred.errors
+        } else {
+            errors
+        };
+
+        dedup_stickers(&mut stickers);
+
+        ParsedArchive {
+            stickers,
+            errors,
+            ambiguous,
+        }
     }
 
-    if let Err(e) = excel::write_tables(&configs, &code_to_stickers_map) {
-        eprintln!("Failed to write tables: {e:?}");
+    /// Runs the enabled steps and returns the parsed stickers grouped by code.
+    ///
+    /// The stickers of renamed codes are also listed under the codes that replaced them.
+    pub fn run(&self, configs: &Configs) -> HashMap<String, Vec<Sticker>> {
+        self.run_reporting(configs, None).code_to_stickers
     }
 
-    // output all errors to see what kind of problems there are in the archive (also debugging)
-    #[cfg(feature = "full_error_handling")]
-    report::print_errors_grouped_by_type(&unrecoverable_errors);
+    /// `run` that also writes the JSON run report to `report_path`.
+    ///
+    /// The orders are read once, every output of the run is made from the same orders.
+    pub fn run_reporting(&self, configs: &Configs, report_path: Option<&Path>) -> RunOutput {
+        let started = Instant::now();
+        let ParsedArchive {
+            stickers,
+            errors,
+            ambiguous,
+        } = self.parse(configs);
+        let parsed = started.elapsed();
+        let mut code_to_stickers_map = group_by_code(&stickers);
+
+        let aliases = load_code_aliases(configs);
+        report::print_alias_problems(&aliases.problems(&code_to_stickers_map));
+        aliases.apply(&mut code_to_stickers_map);
+        // every output is made from the same stickers, excluded ones are not ordered anywhere
+        if configs.non_ok_stickers == NonOkStickers::Exclude {
+            retain_approved(&mut code_to_stickers_map);
+        }
 
-    #[cfg(feature = "material_report")]
-    if let Err(e) =
-        crate::order_summary::generate_material_report_for_orders(&configs, &code_to_stickers_map)
-    {
-        eprintln!("Failed to write materials: {e:?}");
-    }
-}
+        let import = excel::import_orders(configs).map_err(|e| e.to_string());
+        if let Err(e) = &import {
+            eprintln!("Failed to read orders: {e}");
+        }
 
-#[cfg(all(feature = "error_handling", not(feature = "inferring")))]
-pub fn run_no_inferring() {
-    let configs = Configs::load_from_file("configs.txt");
+        if let Ok(import) = &import {
+            if self.error_report && !errors.is_empty() {
+                report::print_relevant_errors(&errors, &import.orders, configs);
+            }
 
-    let file_names = parser::collect_cdr_prefixes(&configs.archive_path);
-    let parsing_results = parser::parse_names(&file_names);
+            if self.production_tables {
+                if let Err(e) =
+                    excel::write_tables(configs, import, &code_to_stickers_map, &ambiguous)
+                {
+                    eprintln!("Failed to write tables: {e:?}");
+                }
+            }
+        }
 
-    let (stickers_nested, errors): (Vec<Vec<Sticker>>, Vec<ParseStickerError>) =
-        parsing_results.into_iter().partition_map(|res| match res {
-            Ok(sticker) => Either::Left(sticker),
-            Err(error) => Either::Right(error),
-        });
+        // output all errors to see what kind of problems there are in the archive (also debugging)
+        if self.grouped_errors {
+            report::print_errors_grouped_by_type(&errors);
+        }
 
-    let mut stickers: Vec<Sticker> = stickers_nested.into_iter().flatten().collect();
+        if let (true, Ok(import)) = (self.material_report, &import) {
+            if let Err(e) = order_summary::generate_material_report_for_orders(
+                &import.orders,
+                &code_to_stickers_map,
+            ) {
+                eprintln!("Failed to write materials: {e:?}");
+            }
+        }
 
-    stickers.sort_by(|a, b| a.code.cmp(&b.code));
-    stickers.dedup();
+        if let Some(path) = report_path {
+            let outcome = RunOutcome {
+                stickers: &stickers,
+                errors: &errors,
+                ambiguous: &ambiguous,
+                code_to_stickers: &code_to_stickers_map,
+                orders: match &import {
+                    Ok(import) => Ok(&import.orders),
+                    Err(e) => Err(e.clone()),
+                },
+            };
+            let timings = Timings::new(parsed, started.elapsed());
+            match RunReport::new(configs, &outcome, timings).write(path) {
+                Ok(()) => println!("Run report written to {path:?}"),
+                Err(e) => eprintln!("Failed to write run report {path:?}: {e}"),
+            }
+        }
 
-    let mut code_to_stickers_map: HashMap<String, Vec<Sticker>> = HashMap::new();
-    for sticker in &stickers {
-        code_to_stickers_map
-            .entry(sticker.code.clone())
-            .or_default()
-            .push(sticker.clone());
+        RunOutput {
+            code_to_stickers: code_to_stickers_map,
+            orders: import,
+        }
     }
+}
 
-    if !errors.is_empty() {
-        report::print_relevant_errors(&errors, &configs);
-    }
+/// Keeps the stickers marked `OK`, codes without any are left out
+fn retain_approved(code_to_stickers: &mut HashMap<String, Vec<Sticker>>) {
+    code_to_stickers.retain(|_, stickers| {
+        stickers.retain(|sticker| sticker.status.is_ok());
+        !stickers.is_empty()
+    });
+}
 
-    if let Err(e) = excel::write_tables(&configs, &code_to_stickers_map) {
-        eprintln!("Failed to write tables: {e:?}");
-    }
+/// What a processing run made its outputs from
+#[derive(Debug)]
+pub struct RunOutput {
+    /// The stickers grouped by code, renamed codes also under the codes that replaced them
+    pub code_to_stickers: HashMap<String, Vec<Sticker>>,
+    /// The orders of all outputs, or why they could not be read
+    pub orders: Result<OrderImport, String>,
+}
 
-    // output all errors to see what kind of problems there are in the archive (also debugging)
-    #[cfg(feature = "full_error_handling")]
-    report::print_errors_grouped_by_type(&errors);
+/// Sorts the stickers by code and keeps the first of equal ones.
+///
+/// Inference pushes its stickers in thread order, so they are sorted by provenance and file
+/// name too, a parsed sticker is kept over a recovered one equal to it.
+pub fn dedup_stickers(stickers: &mut Vec<Sticker>) {
+    stickers.sort_by(|a, b| {
+        (&a.code, !a.provenance.is_parsed(), &a.full_name).cmp(&(
+            &b.code,
+            !b.provenance.is_parsed(),
+            &b.full_name,
+        ))
+    });
+    let mut seen: HashSet<Sticker> = HashSet::new();
+    stickers.retain(|sticker| seen.insert(sticker.clone()));
+}
 
-    #[cfg(feature = "material_report")]
-    if let Err(e) =
-        crate::order_summary::generate_material_report_for_orders(&configs, &code_to_stickers_map)
-    {
-        eprintln!("Failed to write materials: {e:?}");
-    }
+/// What inference recovered from the parse errors
+#[derive(Debug, Default)]
+struct Inferred {
+    stickers: Vec<Sticker>,
+    /// The sizes and materials that were filled in
+    fields: Vec<InferredField>,
+    ambiguous: Vec<AmbiguousInference>,
+    /// The errors that could not be recovered
+    errors: Vec<ParseStickerError>,
 }
 
-#[cfg(not(feature = "error_handling"))]
-pub fn run_optimized() {
-    let configs = Configs::load_from_file("configs.txt");
+/// Recovers names without a code from similar descriptions and names without a size or
+/// material from other files of the same code or a similar description.
+///
+/// A name matched to several codes is only used with `ambiguous_inferences=include`, else it
+/// stays an error.
+fn infer_missing(
+    errors: &[ParseStickerError],
+    stickers: &[Sticker],
+    configs: &Configs,
+) -> Inferred {
+    let inferred_mutex = Mutex::new(Inferred::default());
+    let index = DescriptionIndex::new(stickers, configs.inferring_similarity);
+    let threshold = configs.inferring_threshold();
+    let sources = FieldSources::new(stickers, &index, threshold);
+
+    errors.par_iter().for_each(|error| match error {
+        ParseStickerError::MissingCode(_) => {
+            let result = parser::infer_stickers(error, &index, threshold);
+            let mut inferred = inferred_mutex.lock().unwrap();
+            match result {
+                Ok(stickers) => match inference::ambiguity(error, &stickers) {
+                    Some(ambiguous) => {
+                        inferred.ambiguous.push(ambiguous);
+                        match configs.ambiguous_inferences {
+                            AmbiguousInferences::Include => inferred.stickers.extend(stickers),
+                            AmbiguousInferences::Exclude => inferred.errors.push(error.clone()),
+                        }
+                    }
+                    None => inferred.stickers.extend(stickers),
+                },
+                Err(e) => inferred.errors.push(e),
+            }
+        }
+        ParseStickerError::MissingDimensions(..)
+        | ParseStickerError::MissingMaterial(_)
+        | ParseStickerError::UnknownMaterial(_) => {
+            let result = inference::infer_fields(error, &sources);
+            let mut inferred = inferred_mutex.lock().unwrap();
+            match result {
+                Ok((stickers, fields)) => {
+                    inferred.stickers.extend(stickers);
+                    inferred.fields.extend(fields);
+                }
+                Err(e) => inferred.errors.push(e),
+            }
+        }
+        other => inferred_mutex.lock().unwrap().errors.push(other.clone()),
+    });
 
-    let file_names = parser::collect_cdr_prefixes(&configs.archive_path);
-    let parsing_results = parser::parse_names(&file_names);
+    let mut inferred = inferred_mutex.into_inner().unwrap();
+    inferred.fields.sort_by(|a, b| a.name.cmp(&b.name));
+    inferred
+        .ambiguous
+        .sort_by_key(|ambiguous| ambiguous.error.get_description());
+    inferred
+}
 
-    let mut stickers: Vec<Sticker> = parsing_results
-        .into_iter()
-        .flat_map(|res| res.unwrap_or_default())
-        .collect();
+/// The configured sticker overrides, none when there is no overrides file or it cannot be read.
+///
+/// The lines that cannot be used are listed, the others are still used.
+pub fn load_overrides(configs: &Configs) -> StickerOverrides {
+    let Some(path) = &configs.overrides_path else {
+        return StickerOverrides::default();
+    };
+    match StickerOverrides::load(path) {
+        Ok(overrides) => {
+            report::print_override_problems(path, overrides.malformed());
+            overrides
+        }
+        Err(e) => {
+            println!("!!!WARNING: OVERRIDES NOT USED, {e}!!!");
+            StickerOverrides::default()
+        }
+    }
+}
 
-    stickers.sort_by(|a, b| a.code.cmp(&b.code));
-    stickers.dedup();
+/// The configured code aliases, none when there is no alias file or it cannot be read.
+///
+/// Read on every run, so `watch` picks up changes to the file.
+pub fn load_code_aliases(configs: &Configs) -> CodeAliases {
+    let Some(path) = &configs.code_aliases_path else {
+        return CodeAliases::default();
+    };
+    CodeAliases::load(path).unwrap_or_else(|e| {
+        println!("!!!WARNING: CODE ALIASES NOT USED, {e}!!!");
+        CodeAliases::default()
+    })
+}
 
+pub fn group_by_code(stickers: &[Sticker]) -> HashMap<String, Vec<Sticker>> {
     let mut code_to_stickers_map: HashMap<String, Vec<Sticker>> = HashMap::new();
-    for sticker in &stickers {
+    for sticker in stickers {
         code_to_stickers_map
             .entry(sticker.code.clone())
             .or_default()
             .push(sticker.clone());
     }
+    code_to_stickers_map
+}
 
-    if let Err(e) = excel::write_tables(&configs, &code_to_stickers_map) {
-        eprintln!("Failed to write tables: {e:?}");
-    }
+/// Lists the stickers parsed from the archive without reading the orders
+pub fn scan(pipeline: &Pipeline, configs: &Configs) {
+    let (stickers, errors) = pipeline.parse_archive(configs);
 
-    #[cfg(feature = "material_report")]
-    if let Err(e) =
-        crate::order_summary::generate_material_report_for_orders(&configs, &code_to_stickers_map)
-    {
-        eprintln!("Failed to write materials: {e:?}");
+    for sticker in &stickers {
+        println!("{sticker}");
     }
+
+    println!(
+        "\n{} stickers parsed, {} file names could not be parsed",
+        stickers.len(),
+        errors.len()
+    );
+}
+
+/// Prints only the archive parse errors grouped by type
+pub fn validate(pipeline: &Pipeline, configs: &Configs) {
+    let (_, errors) = pipeline.parse_archive(configs);
+    report::print_errors_grouped_by_type(&errors);
 }
@@ -0,0 +1,255 @@
+use crate::{configs::choice_name, structs::sticker::Sticker};
+use clap::ValueEnum;
+use rayon::prelude::*;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt;
+use strsim::{jaro_winkler, normalized_levenshtein};
+
+/// How similar two sticker descriptions are, from 0 to 1
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Similarity {
+    /// Edit distance of the whole strings, a typo or two
+    #[default]
+    Levenshtein,
+    /// Edit distance of the sorted shared and remaining words, ignores the word order
+    #[value(name = "token_set")]
+    TokenSet,
+    /// Favours strings with the same start, for descriptions cut off at the end
+    #[value(name = "jaro_winkler")]
+    JaroWinkler,
+    /// Shared character trigrams of the words, ignores the word order and uses an index
+    Trigram,
+}
+
+impl Similarity {
+    pub fn score(self, a: &str, b: &str) -> f64 {
+        match self {
+            Similarity::Levenshtein => normalized_levenshtein(a, b),
+            Similarity::TokenSet => token_set_ratio(a, b),
+            Similarity::JaroWinkler => jaro_winkler(a, b),
+            Similarity::Trigram => trigram_similarity(a, b),
+        }
+    }
+}
+
+impl fmt::Display for Similarity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&choice_name(self))
+    }
+}
+
+/// The uppercased words, split at anything that is not a letter or digit
+fn tokens(text: &str) -> BTreeSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|token| !token.is_empty())
+        .map(str::to_uppercase)
+        .collect()
+}
+
+/// Like fuzzywuzzy's token set ratio.
+///
+/// The shared words are compared with the shared words followed by the remaining ones of each
+/// side, so the order of the words does not matter and extra words only cost their length.
+pub fn token_set_ratio(a: &str, b: &str) -> f64 {
+    let (a, b) = (tokens(a), tokens(b));
+    let join = |words: Vec<&String>| {
+        words
+            .into_iter()
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    let shared = join(a.intersection(&b).collect());
+    let with_rest = |rest: Vec<&String>| {
+        let rest = join(rest);
+        format!("{shared} {rest}").trim().to_owned()
+    };
+    let a_rest = with_rest(a.difference(&b).collect());
+    let b_rest = with_rest(b.difference(&a).collect());
+
+    [
+        normalized_levenshtein(&shared, &a_rest),
+        normalized_levenshtein(&shared, &b_rest),
+        normalized_levenshtein(&a_rest, &b_rest),
+    ]
+    .into_iter()
+    .fold(0.0, f64::max)
+}
+
+type Trigram = [char; 3];
+
+/// The trigrams of every word padded with spaces, `  G`, ` GE`, `GEL` and `EL ` for `GEL`
+fn trigrams(text: &str) -> HashSet<Trigram> {
+    let mut trigrams = HashSet::new();
+    for token in tokens(text) {
+        let padded: Vec<char> = format!("  {token} ").chars().collect();
+        trigrams.extend(
+            padded
+                .windows(3)
+                .map(|window| [window[0], window[1], window[2]]),
+        );
+    }
+    trigrams
+}
+
+/// Dice coefficient of the word trigrams
+pub fn trigram_similarity(a: &str, b: &str) -> f64 {
+    let (a, b) = (trigrams(a), trigrams(b));
+    dice(a.intersection(&b).count(), a.len(), b.len())
+}
+
+fn dice(shared: usize, a: usize, b: usize) -> f64 {
+    if a + b == 0 {
+        return 1.0;
+    }
+    2.0 * shared as f64 / (a + b) as f64
+}
+
+/// A code with a description similar to a searched one
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SimilarCode<'a> {
+    pub code: &'a str,
+    /// The name of the first archive file of the code with the similar description
+    pub source: &'a str,
+    pub score: f64,
+}
+
+/// The descriptions of the parsed stickers and their codes, searched for similar descriptions.
+///
+/// Each description is kept once. For `Similarity::Trigram` an inverted index from trigram to
+/// descriptions is built, so a search only scores the descriptions sharing a trigram with it.
+#[derive(Debug, Clone)]
+pub struct DescriptionIndex {
+    similarity: Similarity,
+    descriptions: Vec<String>,
+    /// The codes of the stickers with each description, with the file of the first sticker
+    codes: Vec<BTreeMap<String, String>>,
+    /// Trigram count of each description
+    trigram_counts: Vec<usize>,
+    postings: HashMap<Trigram, Vec<u32>>,
+}
+
+impl DescriptionIndex {
+    pub fn new(stickers: &[Sticker], similarity: Similarity) -> Self {
+        let mut positions: HashMap<&str, usize> = HashMap::new();
+        let mut index = Self {
+            similarity,
+            descriptions: Vec::new(),
+            codes: Vec::new(),
+            trigram_counts: Vec::new(),
+            postings: HashMap::new(),
+        };
+
+        for sticker in stickers {
+            let position = *positions
+                .entry(sticker.description.as_str())
+                .or_insert_with(|| {
+                    index.descriptions.push(sticker.description.clone());
+                    index.codes.push(BTreeMap::new());
+                    index.descriptions.len() - 1
+                });
+            index.codes[position]
+                .entry(sticker.code.clone())
+                .or_insert_with(|| sticker.full_name.clone());
+        }
+
+        if similarity == Similarity::Trigram {
+            for (position, description) in index.descriptions.iter().enumerate() {
+                let trigrams = trigrams(description);
+                index.trigram_counts.push(trigrams.len());
+                for trigram in trigrams {
+                    index
+                        .postings
+                        .entry(trigram)
+                        .or_default()
+                        .push(position as u32);
+                }
+            }
+        }
+        index
+    }
+
+    pub fn similarity(&self) -> Similarity {
+        self.similarity
+    }
+
+    /// How many different descriptions there are
+    pub fn len(&self) -> usize {
+        self.descriptions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.descriptions.is_empty()
+    }
+
+    /// The descriptions at least `threshold` similar to `query`, most similar first
+    pub fn search(&self, query: &str, threshold: f64) -> Vec<(&str, f64)> {
+        self.matches(query, threshold)
+            .into_iter()
+            .map(|(position, score)| (self.descriptions[position].as_str(), score))
+            .collect()
+    }
+
+    /// The codes of the descriptions at least `threshold` similar to `query`, most similar first
+    pub fn similar_codes(&self, query: &str, threshold: f64) -> Vec<&str> {
+        self.similar(query, threshold)
+            .into_iter()
+            .map(|similar| similar.code)
+            .collect()
+    }
+
+    /// Each code of the descriptions at least `threshold` similar to `query` once, with its most
+    /// similar description, most similar first
+    pub fn similar(&self, query: &str, threshold: f64) -> Vec<SimilarCode<'_>> {
+        let mut found: Vec<SimilarCode> = Vec::new();
+        for (position, score) in self.matches(query, threshold) {
+            for (code, source) in &self.codes[position] {
+                if found.iter().all(|similar| similar.code != code) {
+                    found.push(SimilarCode {
+                        code,
+                        source,
+                        score,
+                    });
+                }
+            }
+        }
+        found
+    }
+
+    /// Positions and scores of the matching descriptions, most similar first
+    fn matches(&self, query: &str, threshold: f64) -> Vec<(usize, f64)> {
+        let mut found: Vec<(usize, f64)> = match self.similarity {
+            Similarity::Trigram => self.search_trigrams(query, threshold),
+            similarity => self
+                .descriptions
+                .par_iter()
+                .enumerate()
+                .map(|(position, description)| (position, similarity.score(query, description)))
+                .filter(|(_, score)| *score >= threshold)
+                .collect(),
+        };
+        found.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        found
+    }
+
+    fn search_trigrams(&self, query: &str, threshold: f64) -> Vec<(usize, f64)> {
+        let query = trigrams(query);
+        let mut shared: HashMap<u32, usize> = HashMap::new();
+        for trigram in &query {
+            for position in self.postings.get(trigram).into_iter().flatten() {
+                *shared.entry(*position).or_default() += 1;
+            }
+        }
+        shared
+            .into_iter()
+            .map(|(position, count)| {
+                let position = position as usize;
+                (
+                    position,
+                    dice(count, query.len(), self.trigram_counts[position]),
+                )
+            })
+            .filter(|(_, score)| *score >= threshold)
+            .collect()
+    }
+}
@@ -0,0 +1,20 @@
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+/// A `.cdr` file found in the archive
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ArchiveFile {
+    pub path: PathBuf,
+    /// The uppercased file stem the sticker is parsed from
+    pub name: String,
+    pub size: u64,
+    /// `None` where the platform does not report modification times
+    pub modified: Option<SystemTime>,
+}
+
+impl ArchiveFile {
+    /// The name stickers are parsed from for a file stem, uppercased without spaces before `_`
+    pub fn name_from_stem(stem: &str) -> String {
+        stem.to_uppercase().replace(" _", "_")
+    }
+}
@@ -0,0 +1,249 @@
+use super::{code_normalizer::code_normalizer, provenance::Provenance, sticker::Sticker};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::{fmt, fs, io};
+
+/// Old product codes and the codes that replaced them.
+///
+/// One alias per line, `OLD -> NEW` or `OLD1, OLD2 -> NEW`, several old codes may point at the
+/// same new one. The stickers of an old code are used for the orders of the codes that replaced
+/// it, unless those have stickers of their own.
+#[derive(Debug, Clone, Default)]
+pub struct CodeAliases {
+    /// Old code to new code, both normalized
+    targets: BTreeMap<String, String>,
+}
+
+#[derive(Debug)]
+pub enum CodeAliasesError {
+    Io { path: PathBuf, source: io::Error },
+    MalformedLines(Vec<(usize, String)>),
+}
+
+impl fmt::Display for CodeAliasesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CodeAliasesError::Io { path, source } => {
+                write!(f, "Failed to read code aliases file {:?}: {}", path, source)
+            }
+            CodeAliasesError::MalformedLines(lines) => {
+                write!(f, "{} malformed lines in code aliases file:", lines.len())?;
+                for (line, reason) in lines {
+                    write!(f, "\n\tline {line}: {reason}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for CodeAliasesError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CodeAliasesError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// An alias that cannot be used
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AliasProblem {
+    /// The aliases lead from the first code back to it, none of them is used
+    Cycle(Vec<String>),
+    /// The old code has no stickers in the archive
+    UnknownCode { old: String, new: String },
+}
+
+impl fmt::Display for AliasProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AliasProblem::Cycle(codes) => {
+                write!(f, "cycle {} -> {}", codes.join(" -> "), codes[0])
+            }
+            AliasProblem::UnknownCode { old, new } => {
+                write!(f, "{old} -> {new}: {old} has no stickers in the archive")
+            }
+        }
+    }
+}
+
+impl CodeAliases {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, CodeAliasesError> {
+        let text = fs::read_to_string(&path).map_err(|source| CodeAliasesError::Io {
+            path: path.as_ref().to_path_buf(),
+            source,
+        })?;
+        Self::parse(&text)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, CodeAliasesError> {
+        let mut aliases = CodeAliases::default();
+        let mut malformed = Vec::new();
+
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Err(reason) = aliases.parse_line(line) {
+                malformed.push((index + 1, reason));
+            }
+        }
+
+        if malformed.is_empty() {
+            Ok(aliases)
+        } else {
+            Err(CodeAliasesError::MalformedLines(malformed))
+        }
+    }
+
+    fn parse_line(&mut self, line: &str) -> Result<(), String> {
+        let (old_codes, new) = line
+            .split_once("->")
+            .ok_or_else(|| format!("expected 'OLD -> NEW', got '{line}'"))?;
+        let normalizer = code_normalizer();
+        let new = normalizer.normalize(new);
+        if new.is_empty() {
+            return Err(format!("no new code in '{line}'"));
+        }
+
+        let old_codes: Vec<String> = old_codes
+            .split(',')
+            .map(|old| normalizer.normalize(old))
+            .filter(|old| !old.is_empty())
+            .collect();
+        if old_codes.is_empty() {
+            return Err(format!("no old code in '{line}'"));
+        }
+
+        for old in old_codes {
+            if old == new {
+                return Err(format!("'{old}' is an alias of itself"));
+            }
+            match self.targets.get(&old) {
+                Some(existing) if *existing != new => {
+                    return Err(format!("'{old}' is already an alias of '{existing}'"))
+                }
+                _ => {
+                    self.targets.insert(old, new.clone());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.targets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.targets.is_empty()
+    }
+
+    /// The code that directly replaced `old`
+    pub fn get(&self, old: &str) -> Option<&str> {
+        self.targets.get(old).map(String::as_str)
+    }
+
+    /// The codes that replaced `old`, nearest first, `None` for codes without an alias and
+    /// codes that lead into a cycle
+    pub fn successors(&self, old: &str) -> Option<Vec<&str>> {
+        let mut successors: Vec<&str> = Vec::new();
+        let mut code = old;
+        while let Some(new) = self.targets.get(code) {
+            if new == old || successors.contains(&new.as_str()) {
+                return None;
+            }
+            successors.push(new);
+            code = new;
+        }
+        (!successors.is_empty()).then_some(successors)
+    }
+
+    /// Every cycle once, starting at its smallest code
+    pub fn cycles(&self) -> Vec<Vec<String>> {
+        let mut cycles: Vec<Vec<String>> = Vec::new();
+        for start in self.targets.keys() {
+            let mut path: Vec<&str> = vec![start];
+            let mut code = start.as_str();
+            while let Some(new) = self.targets.get(code) {
+                if let Some(position) = path.iter().position(|seen| *seen == new) {
+                    let mut cycle: Vec<String> = path[position..]
+                        .iter()
+                        .map(|code| code.to_string())
+                        .collect();
+                    let smallest = (0..cycle.len()).min_by_key(|&i| &cycle[i]).unwrap_or(0);
+                    cycle.rotate_left(smallest);
+                    if !cycles.contains(&cycle) {
+                        cycles.push(cycle);
+                    }
+                    break;
+                }
+                path.push(new);
+                code = new;
+            }
+        }
+        cycles
+    }
+
+    /// Cycles and aliases whose old code has no stickers, neither its own nor through an alias
+    pub fn problems(&self, code_to_stickers: &HashMap<String, Vec<Sticker>>) -> Vec<AliasProblem> {
+        let cycles = self.cycles();
+        // codes in the middle of a chain get stickers from the start of it
+        let reached: HashSet<&str> = self
+            .targets
+            .keys()
+            .filter(|old| code_to_stickers.contains_key(*old))
+            .filter_map(|old| self.successors(old))
+            .flatten()
+            .collect();
+
+        let mut problems: Vec<AliasProblem> =
+            cycles.iter().cloned().map(AliasProblem::Cycle).collect();
+        problems.extend(
+            self.targets
+                .iter()
+                .filter(|(old, _)| {
+                    !code_to_stickers.contains_key(*old)
+                        && !reached.contains(old.as_str())
+                        && !cycles.iter().flatten().any(|code| code == *old)
+                })
+                .map(|(old, new)| AliasProblem::UnknownCode {
+                    old: old.clone(),
+                    new: new.clone(),
+                }),
+        );
+        problems
+    }
+
+    /// Adds the stickers of old codes under the codes that replaced them.
+    ///
+    /// The stickers keep their old code and get an alias provenance. Codes with stickers of
+    /// their own do not get the ones of their old codes.
+    pub fn apply(&self, code_to_stickers: &mut HashMap<String, Vec<Sticker>>) {
+        let mut aliased: HashMap<String, Vec<Sticker>> = HashMap::new();
+        for old in self.targets.keys() {
+            let (Some(stickers), Some(successors)) =
+                (code_to_stickers.get(old), self.successors(old))
+            else {
+                continue;
+            };
+            for new in successors {
+                if !code_to_stickers.contains_key(new) {
+                    aliased
+                        .entry(new.to_string())
+                        .or_default()
+                        .extend(stickers.iter().map(|sticker| Sticker {
+                            provenance: Provenance::Alias {
+                                old: old.clone(),
+                                new: new.to_string(),
+                            },
+                            ..sticker.clone()
+                        }));
+                }
+            }
+        }
+        code_to_stickers.extend(aliased);
+    }
+}
@@ -0,0 +1,118 @@
+use std::sync::{LazyLock, OnceLock};
+
+static DEFAULT: LazyLock<CodeNormalizer> = LazyLock::new(CodeNormalizer::default);
+static INSTALLED: OnceLock<CodeNormalizer> = OnceLock::new();
+
+// Cyrillic capitals that look like Latin ones, a code typed on a Bulgarian layout mixes them
+const LOOKALIKES: [(char, char); 15] = [
+    ('А', 'A'),
+    ('В', 'B'),
+    ('Е', 'E'),
+    ('К', 'K'),
+    ('М', 'M'),
+    ('Н', 'H'),
+    ('О', 'O'),
+    ('Р', 'P'),
+    ('С', 'C'),
+    ('Т', 'T'),
+    ('У', 'Y'),
+    ('Х', 'X'),
+    ('Ѕ', 'S'),
+    ('І', 'I'),
+    ('Ј', 'J'),
+];
+
+/// Brings the codes of order sheets and archive file names to one form.
+///
+/// Codes are trimmed and uppercased, Cyrillic lookalikes become Latin letters, a configured
+/// prefix is stripped and numeric codes lose their leading zeros and a `.0` from a number cell.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct CodeNormalizer {
+    /// Folded, longest first so `BG-` is tried before `BG`
+    prefixes: Vec<String>,
+}
+
+impl CodeNormalizer {
+    pub fn new<I, S>(prefixes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut prefixes: Vec<String> = prefixes
+            .into_iter()
+            .map(|prefix| fold(prefix.as_ref().trim()))
+            .filter(|prefix| !prefix.is_empty())
+            .collect();
+        prefixes.sort_by_key(|prefix| std::cmp::Reverse(prefix.chars().count()));
+        prefixes.dedup();
+        Self { prefixes }
+    }
+
+    pub fn prefixes(&self) -> &[String] {
+        &self.prefixes
+    }
+
+    pub fn normalize(&self, code: &str) -> String {
+        let folded = fold(code.trim());
+        let stripped = self.strip_prefix(&folded).trim();
+        canonical_number(stripped).unwrap_or_else(|| stripped.to_string())
+    }
+
+    /// `text` without a configured prefix at its start, a prefix is never the whole text
+    pub fn strip_prefix<'a>(&self, text: &'a str) -> &'a str {
+        self.prefixes
+            .iter()
+            .find_map(|prefix| {
+                let mut folded = String::new();
+                for (start, c) in text.char_indices() {
+                    if folded == *prefix {
+                        return Some(&text[start..]);
+                    }
+                    folded.push_str(&fold(c.encode_utf8(&mut [0; 4])));
+                    if !prefix.starts_with(&folded) {
+                        return None;
+                    }
+                }
+                None
+            })
+            .unwrap_or(text)
+    }
+}
+
+/// Uppercases and replaces Cyrillic lookalikes with their Latin letters
+fn fold(text: &str) -> String {
+    text.chars()
+        .flat_map(char::to_uppercase)
+        .map(|c| {
+            LOOKALIKES
+                .iter()
+                .find(|(cyrillic, _)| *cyrillic == c)
+                .map_or(c, |(_, latin)| *latin)
+        })
+        .collect()
+}
+
+/// `00123` and `123.0` as `123`, `None` for anything that is not a whole number
+fn canonical_number(code: &str) -> Option<String> {
+    let (digits, fraction) = code.split_once(['.', ',']).unwrap_or((code, ""));
+    if digits.is_empty()
+        || !digits.bytes().all(|b| b.is_ascii_digit())
+        || !fraction.bytes().all(|b| b == b'0')
+    {
+        return None;
+    }
+    let trimmed = digits.trim_start_matches('0');
+    Some(if trimmed.is_empty() { "0" } else { trimmed }.to_string())
+}
+
+/// Makes `normalizer` the one used for the codes for the rest of the run.
+///
+/// Can only be done once, the normalizer is handed back if one is already installed.
+pub fn install_code_normalizer(normalizer: CodeNormalizer) -> Result<(), CodeNormalizer> {
+    INSTALLED.set(normalizer)
+}
+
+/// The installed normalizer, or one without prefixes if none was installed
+pub fn code_normalizer() -> &'static CodeNormalizer {
+    INSTALLED.get().unwrap_or(&DEFAULT)
+}
@@ -1,23 +1,34 @@
-use super::parse_stcker_error::ParseStickerError;
+use super::parse_stcker_error::{ErrorContext, ParseStickerError};
+use super::vocabulary::vocabulary;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Eq, PartialEq, Default)]
-pub enum Color {
-    Red,
-    Green,
-    Blue,
-    #[default]
-    Black,
+/// A sticker text color, one of the `color` entries of the vocabulary
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct Color(String);
+
+impl Color {
+    pub fn new(name: &str) -> Self {
+        Color(name.to_owned())
+    }
+
+    pub fn name(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Default for Color {
+    /// The vocabulary's default color, used when the name has none
+    fn default() -> Self {
+        vocabulary()
+            .default_color()
+            .cloned()
+            .unwrap_or_else(|| Color::new("Black"))
+    }
 }
 
 impl std::fmt::Display for Color {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let color_str = match self {
-            Color::Red => "Red",
-            Color::Green => "Green",
-            Color::Blue => "Blue",
-            Color::Black => "Black",
-        };
-        write!(f, "{}", color_str)
+        write!(f, "{}", self.0)
     }
 }
 
@@ -25,12 +36,13 @@ impl std::str::FromStr for Color {
     type Err = ParseStickerError;
 
     fn from_str(color_string: &str) -> Result<Self, Self::Err> {
-        match color_string {
-            s if s.contains("RED") => Ok(Color::Red),
-            s if s.contains("GREEN") => Ok(Color::Green),
-            s if s.contains("BLUE") => Ok(Color::Blue),
-            s if s.contains("BLACK") || s.contains("BLK") => Ok(Color::Black),
-            _ => Err(ParseStickerError::UnknownColor(color_string.to_string())),
-        }
+        vocabulary()
+            .find_color(color_string)
+            .map(|(entry, _)| entry.color.clone())
+            .ok_or_else(|| {
+                ParseStickerError::UnknownColor(
+                    ErrorContext::for_name(color_string).rejecting([color_string.to_string()]),
+                )
+            })
     }
 }
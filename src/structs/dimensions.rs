@@ -1,33 +1,36 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::io::{self, BufRead};
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::sync::{LazyLock, OnceLock};
+use std::{fmt, fs};
 
-// import the genereted during build time official dimensions
+// import the genereted during build time official dimensions, used when no catalog is configured
 include!(concat!(env!("OUT_DIR"), "/generated_dimensions.rs"));
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+static COMPILED_CATALOG: LazyLock<DimensionsCatalog> =
+    LazyLock::new(|| DimensionsCatalog::new(OFFICIAL_DIMENSIONS.iter().cloned()));
+static INSTALLED_CATALOG: OnceLock<DimensionsCatalog> = OnceLock::new();
+
+pub const DEFAULT_TOLERANCE_MM: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Dimensions {
     pub width: u32,
     pub height: u32,
 }
 
 impl Dimensions {
-    fn rev(&self) -> Self {
+    pub fn rev(&self) -> Self {
         Dimensions {
             width: self.height,
             height: self.width,
         }
     }
-}
 
-impl std::fmt::Display for Dimensions {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}x{}", self.width, self.height)
-    }
-}
-
-impl FromStr for Dimensions {
-    type Err = String;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
+    /// Parses `WxH` without checking the official catalog
+    pub fn parse_unchecked(s: &str) -> Result<Self, String> {
         if let Some((w, h)) = s
             .trim()
             .to_owned()
@@ -40,17 +43,7 @@ impl FromStr for Dimensions {
                 .parse::<u32>()
                 .map_err(|e| format!("Invalid height {e}"))?;
             if width > 0 && height > 0 {
-                let dims = Dimensions { width, height };
-                if OFFICIAL_DIMENSIONS.contains(&dims) {
-                    Ok(dims)
-                } else {
-                    let rev = dims.rev();
-                    if OFFICIAL_DIMENSIONS.contains(&rev) {
-                        Ok(rev)
-                    } else {
-                        Err(format!("{} is not in the official dimensions", dims))
-                    }
-                }
+                Ok(Dimensions { width, height })
             } else {
                 Err(format!(
                     "Width and height must be greater than 0: {width}x{height}"
@@ -61,3 +54,268 @@ impl FromStr for Dimensions {
         }
     }
 }
+
+impl std::fmt::Display for Dimensions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}x{}", self.width, self.height)
+    }
+}
+
+impl FromStr for Dimensions {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let dims = Dimensions::parse_unchecked(s)?;
+        catalog()
+            .official(&dims)
+            .ok_or_else(|| format!("{} is not in the official dimensions", dims))
+    }
+}
+
+// how many of the closest official sizes are suggested for a rejected one
+const MAX_SUGGESTIONS: usize = 3;
+
+/// The official sticker sizes a dimension in a file name is checked against
+#[derive(Debug, Clone, Default)]
+pub struct DimensionsCatalog {
+    dimensions: Vec<Dimensions>,
+    lookup: HashSet<Dimensions>,
+    tolerance_mm: u32,
+}
+
+/// An official size close to a size that is not in the catalog
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DimensionsSuggestion {
+    pub rejected: Dimensions,
+    pub official: Dimensions,
+    pub rotated: bool,
+    /// Sum of the width and height differences in mm
+    pub distance: u32,
+}
+
+impl fmt::Display for DimensionsSuggestion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} → {}", self.rejected, self.official)?;
+        if self.rotated {
+            write!(f, " (rotated)")?;
+        }
+        Ok(())
+    }
+}
+
+/// An entry of the catalog file that repeats an earlier one, possibly rotated
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateDimensions {
+    pub line: usize,
+    pub first_line: usize,
+    pub dimensions: Dimensions,
+    pub rotated: bool,
+}
+
+impl fmt::Display for DuplicateDimensions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.rotated {
+            write!(
+                f,
+                "line {}: {} is line {} rotated",
+                self.line, self.dimensions, self.first_line
+            )
+        } else {
+            write!(
+                f,
+                "line {}: {} is already on line {}",
+                self.line, self.dimensions, self.first_line
+            )
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum CatalogError {
+    Io { path: PathBuf, source: io::Error },
+    MalformedLines(Vec<(usize, String)>),
+}
+
+impl fmt::Display for CatalogError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CatalogError::Io { path, source } => {
+                write!(f, "Failed to read dimensions file {:?}: {}", path, source)
+            }
+            CatalogError::MalformedLines(lines) => {
+                write!(f, "{} malformed lines in dimensions file:", lines.len())?;
+                for (line, reason) in lines {
+                    write!(f, "\n\tline {line}: {reason}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for CatalogError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CatalogError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl DimensionsCatalog {
+    pub fn new(dimensions: impl IntoIterator<Item = Dimensions>) -> Self {
+        let mut catalog = Self {
+            tolerance_mm: DEFAULT_TOLERANCE_MM,
+            ..Default::default()
+        };
+        for dims in dimensions {
+            if catalog.lookup.insert(dims.clone()) {
+                catalog.dimensions.push(dims);
+            }
+        }
+        catalog
+    }
+
+    /// Loads a catalog with one `WxH` entry per line.
+    ///
+    /// Empty lines and lines starting with `#` are skipped. Duplicate and rotated duplicate
+    /// entries are kept and returned so they can be reported.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<(Self, Vec<DuplicateDimensions>), CatalogError> {
+        let io_error = |source| CatalogError::Io {
+            path: path.as_ref().to_path_buf(),
+            source,
+        };
+        let file = fs::File::open(&path).map_err(io_error)?;
+
+        let mut malformed = Vec::new();
+        let mut duplicates = Vec::new();
+        let mut first_lines: HashMap<Dimensions, usize> = HashMap::new();
+        let mut dimensions = Vec::new();
+
+        for (index, line_result) in io::BufReader::new(file).lines().enumerate() {
+            let line_number = index + 1;
+            let line = line_result.map_err(io_error)?;
+            let trimmed = line.trim();
+
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+
+            let dims = match Dimensions::parse_unchecked(trimmed) {
+                Ok(dims) => dims,
+                Err(reason) => {
+                    malformed.push((line_number, reason));
+                    continue;
+                }
+            };
+
+            if let Some(first_line) = first_lines.get(&dims) {
+                duplicates.push(DuplicateDimensions {
+                    line: line_number,
+                    first_line: *first_line,
+                    dimensions: dims,
+                    rotated: false,
+                });
+                continue;
+            }
+
+            if let Some(first_line) = first_lines.get(&dims.rev()) {
+                duplicates.push(DuplicateDimensions {
+                    line: line_number,
+                    first_line: *first_line,
+                    dimensions: dims.clone(),
+                    rotated: true,
+                });
+            }
+
+            first_lines.insert(dims.clone(), line_number);
+            dimensions.push(dims);
+        }
+
+        if malformed.is_empty() {
+            Ok((Self::new(dimensions), duplicates))
+        } else {
+            Err(CatalogError::MalformedLines(malformed))
+        }
+    }
+
+    /// The official orientation of the dimensions, if they are in the catalog at all
+    pub fn official(&self, dims: &Dimensions) -> Option<Dimensions> {
+        if self.lookup.contains(dims) {
+            Some(dims.clone())
+        } else {
+            let rev = dims.rev();
+            self.lookup.contains(&rev).then_some(rev)
+        }
+    }
+
+    /// The catalog compiled from `dimensions.txt`
+    pub fn compiled() -> Self {
+        COMPILED_CATALOG.clone()
+    }
+
+    /// How many mm a side may differ from an official size to still be suggested
+    pub fn with_tolerance(mut self, tolerance_mm: u32) -> Self {
+        self.tolerance_mm = tolerance_mm;
+        self
+    }
+
+    pub fn tolerance_mm(&self) -> u32 {
+        self.tolerance_mm
+    }
+
+    /// The closest official sizes within the tolerance, in either orientation
+    pub fn suggestions(&self, rejected: &Dimensions) -> Vec<DimensionsSuggestion> {
+        let mut suggestions: Vec<DimensionsSuggestion> = self
+            .dimensions
+            .iter()
+            .flat_map(|official| {
+                [(official.clone(), false), (official.rev(), true)]
+                    .into_iter()
+                    .map(move |(candidate, rotated)| (official, candidate, rotated))
+            })
+            .filter_map(|(official, candidate, rotated)| {
+                let width_diff = rejected.width.abs_diff(candidate.width);
+                let height_diff = rejected.height.abs_diff(candidate.height);
+                (width_diff <= self.tolerance_mm && height_diff <= self.tolerance_mm).then(|| {
+                    DimensionsSuggestion {
+                        rejected: rejected.clone(),
+                        official: official.clone(),
+                        rotated,
+                        distance: width_diff + height_diff,
+                    }
+                })
+            })
+            .collect();
+
+        suggestions.sort_by_key(|suggestion| (suggestion.distance, suggestion.rotated));
+        let mut seen = HashSet::new();
+        suggestions.retain(|suggestion| seen.insert(suggestion.official.clone()));
+        suggestions.truncate(MAX_SUGGESTIONS);
+        suggestions
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Dimensions> {
+        self.dimensions.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.dimensions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.dimensions.is_empty()
+    }
+}
+
+/// Makes `catalog` the one used by `Dimensions::from_str` for the rest of the run.
+///
+/// Can only be done once, the catalog is handed back if one is already installed.
+pub fn install_catalog(catalog: DimensionsCatalog) -> Result<(), DimensionsCatalog> {
+    INSTALLED_CATALOG.set(catalog)
+}
+
+/// The installed catalog, or the one compiled from `dimensions.txt` if none was installed
+pub fn catalog() -> &'static DimensionsCatalog {
+    INSTALLED_CATALOG.get().unwrap_or(&COMPILED_CATALOG)
+}
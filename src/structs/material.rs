@@ -1,26 +1,32 @@
-use super::parse_stcker_error::ParseStickerError;
-
-#[derive(Debug, Clone, Eq, PartialEq)]
-pub enum Material {
-    Paper,
-    PaperGR,
-    PVC,
-    PVCR,
-    PVCRSLV,
-    LEAFLET,
+use super::parse_stcker_error::{ErrorContext, ParseStickerError};
+use super::vocabulary::vocabulary;
+use serde::{Deserialize, Serialize};
+
+/// A sticker material, one of the `material` entries of the vocabulary
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct Material(String);
+
+impl Material {
+    pub fn new(name: &str) -> Self {
+        Material(name.to_owned())
+    }
+
+    pub fn name(&self) -> &str {
+        &self.0
+    }
+
+    /// The material group the vocabulary puts this material in, e.g. `PVC` for `PVC R`
+    pub fn group(&self) -> String {
+        vocabulary()
+            .material_entry(self)
+            .map(|entry| entry.group.clone())
+            .unwrap_or_else(|| self.0.to_uppercase())
+    }
 }
 
 impl std::fmt::Display for Material {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let material_str = match self {
-            Material::Paper => "PAPER",
-            Material::PaperGR => "PAPER GR",
-            Material::PVC => "PVC",
-            Material::PVCR => "PVC R",
-            Material::PVCRSLV => "PVC R SLV",
-            Material::LEAFLET => "LEAFLET",
-        };
-        write!(f, "{}", material_str)
+        write!(f, "{}", self.0)
     }
 }
 
@@ -30,17 +36,14 @@ impl FromStr for Material {
     type Err = ParseStickerError;
 
     fn from_str(material_string: &str) -> Result<Self, Self::Err> {
-        match material_string {
-            s if s.contains("GR") => Ok(Material::PaperGR),
-            s if s.contains("PAP") | s.contains("PP") => Ok(Material::Paper),
-            s if s.contains("LEAFLET") => Ok(Material::LEAFLET),
-            //-------------------------------------------------------------------------
-            s if s.contains("SLV") => Ok(Material::PVCRSLV),
-            s if s.contains("R") => Ok(Material::PVCR),
-            s if s.contains("PVC") => Ok(Material::PVC),
-            _ => Err(ParseStickerError::UnknownMaterial(
-                material_string.to_string(),
-            )),
-        }
+        vocabulary()
+            .find_material(material_string)
+            .map(|(entry, _)| entry.material.clone())
+            .ok_or_else(|| {
+                ParseStickerError::UnknownMaterial(
+                    ErrorContext::for_name(material_string)
+                        .rejecting([material_string.to_string()]),
+                )
+            })
     }
 }
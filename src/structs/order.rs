@@ -1,6 +1,123 @@
+use super::{sticker::Sticker, variant::Variant};
+use std::collections::HashMap;
+use std::fmt;
+
 #[derive(Debug, Clone)]
 pub struct Order {
     pub code: String,
     pub amount: u64,
     pub description: String,
+    /// From the variant column, or the end of the description
+    pub variant: Variant,
+    /// The code as written in the order sheet, when normalizing changed it
+    pub original_code: Option<String>,
+    /// The files and sheets the amount was ordered in
+    pub origins: Vec<OrderOrigin>,
+}
+
+/// How much of an order came from one file and sheet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrderOrigin {
+    /// File name of the order file
+    pub file: String,
+    pub sheet: Option<String>,
+    pub amount: u64,
+}
+
+impl OrderOrigin {
+    fn is_same_source(&self, other: &OrderOrigin) -> bool {
+        self.file == other.file && self.sheet == other.sheet
+    }
+}
+
+/// `file [sheet]`, used as column header for the amounts of a source
+impl fmt::Display for OrderOrigin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.sheet {
+            Some(sheet) => write!(f, "{} [{}]", self.file, sheet),
+            None => write!(f, "{}", self.file),
+        }
+    }
+}
+
+impl Order {
+    /// The stickers of the order's variant, a PROMO order is never made with regular stickers
+    pub fn matching_stickers<'a>(&self, stickers: &'a [Sticker]) -> Vec<&'a Sticker> {
+        stickers
+            .iter()
+            .filter(|sticker| sticker.variant == self.variant)
+            .collect()
+    }
+
+    /// The stickers of the order's code and variant, empty when the archive has none
+    pub fn stickers_in<'a>(
+        &self,
+        code_to_stickers: &'a HashMap<String, Vec<Sticker>>,
+    ) -> Vec<&'a Sticker> {
+        code_to_stickers
+            .get(&self.code)
+            .map(|stickers| self.matching_stickers(stickers))
+            .unwrap_or_default()
+    }
+
+    /// The amount ordered from `source`, a `file [sheet]` label
+    pub fn amount_from(&self, source: &str) -> Option<u64> {
+        self.origins
+            .iter()
+            .filter(|origin| origin.to_string() == source)
+            .map(|origin| origin.amount)
+            .reduce(|a, b| a + b)
+    }
+
+    /// Adds another order of the same code to this one, keeping where its amounts came from
+    pub fn merge(&mut self, other: Order) {
+        self.amount += other.amount;
+        if self.description.is_empty() {
+            self.description = other.description;
+        }
+        if self.original_code.is_none() {
+            self.original_code = other.original_code;
+        }
+        for origin in other.origins {
+            match self
+                .origins
+                .iter_mut()
+                .find(|existing| existing.is_same_source(&origin))
+            {
+                Some(existing) => existing.amount += origin.amount,
+                None => self.origins.push(origin),
+            }
+        }
+    }
+}
+
+/// Merges the orders of the same code and variant, in the order the codes first appear.
+///
+/// The variant is part of the key so a PROMO line of a code is not added to its regular one.
+pub fn merge_orders(orders: Vec<Order>) -> Vec<Order> {
+    let mut merged: Vec<Order> = Vec::with_capacity(orders.len());
+    let mut positions: HashMap<(String, Variant), usize> = HashMap::new();
+    for order in orders {
+        let key = (order.code.clone(), order.variant.clone());
+        match positions.get(&key) {
+            Some(&position) => merged[position].merge(order),
+            None => {
+                positions.insert(key, merged.len());
+                merged.push(order);
+            }
+        }
+    }
+    merged
+}
+
+/// The `file [sheet]` labels of all order sources, in the order they first appear
+pub fn order_sources(orders: &[Order]) -> Vec<String> {
+    let mut sources: Vec<String> = Vec::new();
+    for origin in orders.iter().flat_map(|order| &order.origins) {
+        let label = origin.to_string();
+        if !sources.contains(&label) {
+            sources.push(label);
+        }
+    }
+    sources
 }
@@ -1,27 +1,161 @@
-#[derive(Clone)]
+use super::dimensions::DimensionsSuggestion;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+/// The part of the sticker name parsing that failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SubParser {
+    Code,
+    Description,
+    Dimensions,
+    Material,
+    Color,
+}
+
+impl fmt::Display for SubParser {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sub_parser_str = match self {
+            SubParser::Code => "code",
+            SubParser::Description => "description",
+            SubParser::Dimensions => "dimensions",
+            SubParser::Material => "material",
+            SubParser::Color => "color",
+        };
+        write!(f, "{}", sub_parser_str)
+    }
+}
+
+/// Where in which file name a parse error happened
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErrorContext {
+    /// The normalized file stem that was parsed
+    pub name: String,
+    /// The archive file, `None` when a bare name was parsed
+    pub path: Option<PathBuf>,
+    /// Byte range in `name` the failing sub-parser looked at
+    pub span: Range<usize>,
+    /// Tokens that looked like a candidate but were rejected, e.g. a non-official size
+    pub rejected: Vec<String>,
+}
+
+impl ErrorContext {
+    pub fn new(name: &str, span: Range<usize>) -> Self {
+        Self {
+            name: name.to_owned(),
+            path: None,
+            span,
+            rejected: Vec::new(),
+        }
+    }
+
+    /// Context spanning the whole name
+    pub fn for_name(name: &str) -> Self {
+        Self::new(name, 0..name.len())
+    }
+
+    pub fn rejecting(mut self, rejected: impl IntoIterator<Item = String>) -> Self {
+        self.rejected.extend(rejected);
+        self
+    }
+
+    /// The name split into the text before, inside and after the span
+    pub fn split(&self) -> (&str, &str, &str) {
+        let start = self.span.start.min(self.name.len());
+        let end = self.span.end.clamp(start, self.name.len());
+        (
+            &self.name[..start],
+            &self.name[start..end],
+            &self.name[end..],
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ParseStickerError {
-    MissingCode(String),
-    MissingDescription(String),
-    MissingDimensions(String),
-    MissingMaterial(String),
-    UnknownColor(String),
-    UnknownMaterial(String),
+    MissingCode(ErrorContext),
+    MissingDescription(ErrorContext),
+    /// Also carries the closest official sizes to the rejected dimensions
+    MissingDimensions(ErrorContext, Vec<DimensionsSuggestion>),
+    MissingMaterial(ErrorContext),
+    UnknownColor(ErrorContext),
+    UnknownMaterial(ErrorContext),
 }
 
 impl ParseStickerError {
+    pub fn context(&self) -> &ErrorContext {
+        match self {
+            ParseStickerError::MissingCode(context)
+            | ParseStickerError::MissingDescription(context)
+            | ParseStickerError::MissingDimensions(context, _)
+            | ParseStickerError::MissingMaterial(context)
+            | ParseStickerError::UnknownColor(context)
+            | ParseStickerError::UnknownMaterial(context) => context,
+        }
+    }
+
+    fn context_mut(&mut self) -> &mut ErrorContext {
+        match self {
+            ParseStickerError::MissingCode(context)
+            | ParseStickerError::MissingDescription(context)
+            | ParseStickerError::MissingDimensions(context, _)
+            | ParseStickerError::MissingMaterial(context)
+            | ParseStickerError::UnknownColor(context)
+            | ParseStickerError::UnknownMaterial(context) => context,
+        }
+    }
+
     pub fn get_description(&self) -> String {
+        self.context().name.clone()
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.context().path.as_deref()
+    }
+
+    /// Attaches the archive file the failing name came from
+    pub fn with_path(mut self, path: &Path) -> Self {
+        self.context_mut().path = Some(path.to_path_buf());
+        self
+    }
+
+    pub fn sub_parser(&self) -> SubParser {
         match self {
-            ParseStickerError::MissingCode(desc)
-            | ParseStickerError::MissingDescription(desc)
-            | ParseStickerError::MissingDimensions(desc)
-            | ParseStickerError::MissingMaterial(desc)
-            | ParseStickerError::UnknownColor(desc)
-            | ParseStickerError::UnknownMaterial(desc) => desc.clone(),
+            ParseStickerError::MissingCode(_) => SubParser::Code,
+            ParseStickerError::MissingDescription(_) => SubParser::Description,
+            ParseStickerError::MissingDimensions(..) => SubParser::Dimensions,
+            ParseStickerError::MissingMaterial(_) | ParseStickerError::UnknownMaterial(_) => {
+                SubParser::Material
+            }
+            ParseStickerError::UnknownColor(_) => SubParser::Color,
         }
     }
-}
 
-use std::fmt;
+    /// Closest official sizes for dimensions that are not in the catalog
+    pub fn dimensions_suggestions(&self) -> &[DimensionsSuggestion] {
+        match self {
+            ParseStickerError::MissingDimensions(_, suggestions) => suggestions,
+            _ => &[],
+        }
+    }
+
+    /// The error type with the rejected tokens, e.g. `Missing dimensions (rejected: 60X41)`
+    pub fn details(&self) -> String {
+        let rejected = &self.context().rejected;
+        if rejected.is_empty() {
+            self.to_string()
+        } else {
+            format!("{self} (rejected: {})", rejected.join(", "))
+        }
+    }
+
+    /// The name with the failing part marked by `[` and `]`
+    pub fn marked_name(&self) -> String {
+        let (before, failed, after) = self.context().split();
+        format!("{before}[{failed}]{after}")
+    }
+}
 
 impl fmt::Display for ParseStickerError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -32,7 +166,7 @@ impl fmt::Display for ParseStickerError {
             ParseStickerError::MissingDescription(_) => {
                 write!(f, "Missing description")
             }
-            ParseStickerError::MissingDimensions(_) => {
+            ParseStickerError::MissingDimensions(..) => {
                 write!(f, "Missing dimensions")
             }
             ParseStickerError::MissingMaterial(_) => {
@@ -48,27 +182,4 @@ impl fmt::Display for ParseStickerError {
     }
 }
 
-impl fmt::Debug for ParseStickerError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            ParseStickerError::MissingCode(name) => {
-                write!(f, "Missing sticker code in: {}", name)
-            }
-            ParseStickerError::MissingDescription(name) => {
-                write!(f, "Missing description in: {}", name)
-            }
-            ParseStickerError::MissingDimensions(name) => {
-                write!(f, "Missing dimensions in: {}", name)
-            }
-            ParseStickerError::MissingMaterial(name) => {
-                write!(f, "Missing material in: {}", name)
-            }
-            ParseStickerError::UnknownColor(color_string) => {
-                write!(f, "Unknown color in: {}", color_string)
-            }
-            ParseStickerError::UnknownMaterial(material_string) => {
-                write!(f, "Unknown material in: {}", material_string)
-            }
-        }
-    }
-}
+impl std::error::Error for ParseStickerError {}
@@ -0,0 +1,70 @@
+use super::parse_stcker_error::SubParser;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A sticker field that was not in a file name and was taken from another archive file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InferredField {
+    /// The name of the file the field was missing from
+    pub name: String,
+    pub field: SubParser,
+    /// The field as it was put into the name, e.g. `234191`, `60X40` or `PVC R`
+    pub value: String,
+    /// The name of the archive file the field was taken from
+    pub source: String,
+    /// How similar the description of the source is, 1 for a file of the same code
+    pub score: f64,
+}
+
+impl fmt::Display for InferredField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} from {} ({:.2})",
+            self.field, self.value, self.source, self.score
+        )
+    }
+}
+
+/// Where a sticker came from, a file name that parsed or one of the ways a run recovers stickers
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub enum Provenance {
+    #[default]
+    Parsed,
+    /// The code, size or material was missing from the file name
+    Inferred(Vec<InferredField>),
+    /// Written out in the overrides file, on this 1-based line
+    Override { line: usize },
+    /// The sticker of an old code, used for the code that replaced it
+    Alias { old: String, new: String },
+}
+
+impl Provenance {
+    pub fn is_parsed(&self) -> bool {
+        matches!(self, Provenance::Parsed)
+    }
+
+    /// How sure the run is that the sticker is right, the lowest score of the inferred fields
+    pub fn confidence(&self) -> f64 {
+        match self {
+            Provenance::Inferred(fields) => {
+                fields.iter().map(|field| field.score).fold(1.0, f64::min)
+            }
+            _ => 1.0,
+        }
+    }
+}
+
+impl fmt::Display for Provenance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Provenance::Parsed => write!(f, "parsed"),
+            Provenance::Inferred(fields) => {
+                let fields: Vec<String> = fields.iter().map(|field| field.to_string()).collect();
+                write!(f, "inferred {}", fields.join(", "))
+            }
+            Provenance::Override { line } => write!(f, "override line {line}"),
+            Provenance::Alias { old, new } => write!(f, "alias {old} -> {new}"),
+        }
+    }
+}
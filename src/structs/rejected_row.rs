@@ -0,0 +1,52 @@
+use std::fmt;
+
+/// Why an order row was left out of the run
+#[derive(Debug, Clone, PartialEq)]
+pub enum RejectReason {
+    BlankCode,
+    MissingAmount,
+    /// The amount cell is not a number
+    InvalidAmount(String),
+    ZeroAmount,
+    NegativeAmount(f64),
+    FractionalAmount(f64),
+    /// The code was already ordered in the same sheet, at this 1-based row
+    DuplicateCode {
+        first_row: usize,
+    },
+}
+
+impl fmt::Display for RejectReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RejectReason::BlankCode => write!(f, "blank code"),
+            RejectReason::MissingAmount => write!(f, "missing amount"),
+            RejectReason::InvalidAmount(value) => write!(f, "amount '{value}' is not a number"),
+            RejectReason::ZeroAmount => write!(f, "zero amount"),
+            RejectReason::NegativeAmount(value) => write!(f, "negative amount {value}"),
+            RejectReason::FractionalAmount(value) => write!(f, "fractional amount {value}"),
+            RejectReason::DuplicateCode { first_row } => {
+                write!(f, "duplicate code, first ordered in row {first_row}")
+            }
+        }
+    }
+}
+
+/// An order row that could not be imported, with the cells as they were in the sheet
+#[derive(Debug, Clone, PartialEq)]
+pub struct RejectedRow {
+    /// `file [sheet]` of the row
+    pub source: String,
+    /// 1-based row number in the sheet
+    pub row: usize,
+    pub code: String,
+    pub description: String,
+    pub amount: String,
+    pub reason: RejectReason,
+}
+
+impl fmt::Display for RejectedRow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} row {}: {}", self.source, self.row, self.reason)
+    }
+}
@@ -0,0 +1,91 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A status suffix at the end of a sticker name
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StatusFlag {
+    /// `OK`, the design is approved
+    Approved,
+    /// `PF`, the print file is ready
+    PrintFile,
+    /// `DV`, `DV ST`, `DVOEN STIKER`, ... the product gets two stickers
+    DoubleSticker,
+    /// `TEMP_SIZE`, the size is not final yet
+    TempSize,
+}
+
+impl StatusFlag {
+    pub const ALL: [StatusFlag; 4] = [
+        StatusFlag::Approved,
+        StatusFlag::PrintFile,
+        StatusFlag::DoubleSticker,
+        StatusFlag::TempSize,
+    ];
+
+    fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+impl fmt::Display for StatusFlag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let flag_str = match self {
+            StatusFlag::Approved => "OK",
+            StatusFlag::PrintFile => "PF",
+            StatusFlag::DoubleSticker => "DV",
+            StatusFlag::TempSize => "TEMP_SIZE",
+        };
+        write!(f, "{}", flag_str)
+    }
+}
+
+/// The set of status flags parsed from a sticker name
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub struct StickerStatus(u8);
+
+impl StickerStatus {
+    pub fn insert(&mut self, flag: StatusFlag) {
+        self.0 |= flag.bit();
+    }
+
+    pub fn with(mut self, flag: StatusFlag) -> Self {
+        self.insert(flag);
+        self
+    }
+
+    pub fn contains(&self, flag: StatusFlag) -> bool {
+        self.0 & flag.bit() != 0
+    }
+
+    /// Approved for production
+    pub fn is_ok(&self) -> bool {
+        self.contains(StatusFlag::Approved)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = StatusFlag> + '_ {
+        StatusFlag::ALL
+            .into_iter()
+            .filter(|flag| self.contains(*flag))
+    }
+}
+
+impl FromIterator<StatusFlag> for StickerStatus {
+    fn from_iter<I: IntoIterator<Item = StatusFlag>>(flags: I) -> Self {
+        let mut status = StickerStatus::default();
+        for flag in flags {
+            status.insert(flag);
+        }
+        status
+    }
+}
+
+impl fmt::Display for StickerStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let flags: Vec<String> = self.iter().map(|flag| flag.to_string()).collect();
+        write!(f, "{}", flags.join(" "))
+    }
+}
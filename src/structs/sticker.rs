@@ -1,54 +1,64 @@
 use super::{
-    color::Color, dimensions::Dimensions, material::Material, parse_stcker_error::ParseStickerError,
+    archive_file::ArchiveFile,
+    code_normalizer::code_normalizer,
+    color::Color,
+    dimensions::Dimensions,
+    material::Material,
+    parse_stcker_error::{ErrorContext, ParseStickerError},
+    provenance::Provenance,
+    status::StickerStatus,
+    variant::Variant,
 };
 use crate::parser::{
-    extract_code, extract_color, extract_description, extract_dimensions, extract_material,
-    split_at_dimensions, DIMENSIONS_RE,
+    extract_code_token, extract_color, extract_description, extract_dimensions, extract_material,
+    extract_status, extract_variant, split_at_dimensions, DIMENSIONS_RE,
 };
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Sticker {
     pub code: String,
     pub description: String,
+    pub variant: Variant,
     pub dimensions: Dimensions,
     pub material: Material,
     pub text_color: Color,
+    pub status: StickerStatus,
     pub full_name: String,
+    /// Not part of the equality, a recovered sticker equal to a parsed one is a duplicate
+    #[serde(default)]
+    pub provenance: Provenance,
 }
 
 impl Sticker {
-    pub fn new(
-        code: String,
-        description: &str,
-        dimensions: Dimensions,
-        material: Material,
-        text_color: Color,
-        full_name: String,
-    ) -> Self {
-        Self {
-            code,
-            description: description.to_owned(),
-            dimensions,
-            material,
-            text_color,
-            full_name,
-        }
+    /// Parses the stickers of an archive file, errors point back at the file
+    pub fn parse_file(file: &ArchiveFile) -> Result<Vec<Self>, ParseStickerError> {
+        Self::parse_stickers(&file.name).map_err(|e| e.with_path(&file.path))
     }
 
     pub fn parse_stickers(name: &str) -> Result<Vec<Self>, ParseStickerError> {
-        let code = extract_code(name)?;
-        println!("{}", code);
+        let code_token = extract_code_token(name)?; // as written, the code is normalized
+        let code = code_normalizer().normalize(code_token);
         let name_parts = split_at_dimensions(name)?; // before and after first WxH
-        let description = extract_description(name_parts, code.as_str())?;
+        let description = extract_description(name_parts, code_token)?;
         let dimensions = extract_dimensions(name_parts.1);
         let materials: Vec<Material> = DIMENSIONS_RE
             .split(name_parts.1)
             .filter_map(|material_part| extract_material(material_part, name).ok())
             .collect();
         let color = extract_color(name_parts.1).unwrap_or_default();
+        let status = extract_status(name_parts.1);
+        let variant = extract_variant(name_parts.1, name);
 
         if materials.is_empty() {
-            return Err(ParseStickerError::MissingMaterial(name.to_owned()));
+            let rejected = DIMENSIONS_RE
+                .split(name_parts.1)
+                .map(|part| part.trim_matches(['_', ' ', '.', '&']))
+                .filter(|part| !part.is_empty())
+                .map(str::to_owned);
+            return Err(ParseStickerError::MissingMaterial(
+                ErrorContext::new(name, name_parts.0.len()..name.len()).rejecting(rejected),
+            ));
         }
 
         Ok(dimensions
@@ -60,14 +70,17 @@ impl Sticker {
                 std::iter::repeat_n(materials[0].clone(), dimensions.len()).collect()
             })
             .map(|(dimensions, material)| {
-                Self::new(
-                    code.clone(),
-                    &description,
+                Self {
+                    code: code.clone(),
+                    description: description.clone(),
+                    variant: variant.clone(),
                     dimensions,
                     material,
-                    color.clone(),
-                    name.to_string(), // Preserve original name
-                )
+                    text_color: color.clone(),
+                    status,
+                    full_name: name.to_string(), // Preserve original name
+                    provenance: Provenance::Parsed,
+                }
             })
             .collect())
     }
@@ -77,8 +90,14 @@ impl std::fmt::Display for Sticker {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "Code: {}, Description: {}, Dimensions: {:?}, Material: {}, Color: {}",
-            self.code, self.description, self.dimensions, self.material, self.text_color,
+            "Code: {}, Description: {}, Variant: {}, Dimensions: {:?}, Material: {}, Color: {}, Status: {}",
+            self.code,
+            self.description,
+            self.variant,
+            self.dimensions,
+            self.material,
+            self.text_color,
+            self.status,
         )
     }
 }
@@ -87,8 +106,24 @@ impl PartialEq for Sticker {
     fn eq(&self, other: &Self) -> bool {
         self.code == other.code
             && self.description == other.description
+            && self.variant == other.variant
             && self.dimensions == other.dimensions
             && self.material == other.material
             && self.text_color == other.text_color
+            && self.status == other.status
+    }
+}
+
+impl Eq for Sticker {}
+
+impl std::hash::Hash for Sticker {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.code.hash(state);
+        self.description.hash(state);
+        self.variant.hash(state);
+        self.dimensions.hash(state);
+        self.material.hash(state);
+        self.text_color.hash(state);
+        self.status.hash(state);
     }
 }
@@ -0,0 +1,338 @@
+use super::{
+    archive_file::ArchiveFile, code_normalizer::code_normalizer, color::Color,
+    dimensions::Dimensions, material::Material, provenance::Provenance, sticker::Sticker,
+};
+use crate::parser::{extract_status, extract_variant};
+use std::path::{Path, PathBuf};
+use std::{fmt, fs, io};
+
+/// Stickers written out by hand for archive files whose names do not parse.
+///
+/// One override per line, `<path or stem>; code=...; description=...; dimensions=WxH[, WxH];
+/// material=...; color=...`, the color is optional. The file is not parsed at all, its stickers
+/// are built from the override. Values with a `;` are quoted, `description="A; B"`, a quote in
+/// them is doubled.
+#[derive(Debug, Clone, Default)]
+pub struct StickerOverrides {
+    overrides: Vec<StickerOverride>,
+    /// The lines that could not be used and why, the other lines are used all the same
+    malformed: Vec<(usize, String)>,
+}
+
+/// Which archive file an override is for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OverrideTarget {
+    /// The end of the file path, relative to the archive or absolute
+    Path(PathBuf),
+    /// The file stem, case-insensitive, for a file anywhere in the archive
+    Stem(String),
+}
+
+impl OverrideTarget {
+    fn parse(target: &str) -> Self {
+        let is_path = target.contains(['/', '\\'])
+            || Path::new(target)
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("cdr"));
+        if is_path {
+            OverrideTarget::Path(PathBuf::from(target.replace('\\', "/")))
+        } else {
+            OverrideTarget::Stem(ArchiveFile::name_from_stem(target))
+        }
+    }
+
+    pub fn matches(&self, file: &ArchiveFile) -> bool {
+        match self {
+            OverrideTarget::Path(path) => file.path.ends_with(path),
+            OverrideTarget::Stem(stem) => file.name == *stem,
+        }
+    }
+}
+
+impl fmt::Display for OverrideTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OverrideTarget::Path(path) => write!(f, "{}", path.display()),
+            OverrideTarget::Stem(stem) => write!(f, "{stem}"),
+        }
+    }
+}
+
+/// The sticker fields of one archive file
+#[derive(Debug, Clone, PartialEq)]
+pub struct StickerOverride {
+    pub target: OverrideTarget,
+    /// 1-based line in the overrides file
+    pub line: usize,
+    pub code: String,
+    pub description: String,
+    /// One sticker per size
+    pub dimensions: Vec<Dimensions>,
+    pub material: Material,
+    pub color: Color,
+}
+
+#[derive(Debug)]
+pub enum OverridesError {
+    Io { path: PathBuf, source: io::Error },
+    MalformedLines(Vec<(usize, String)>),
+}
+
+impl fmt::Display for OverridesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OverridesError::Io { path, source } => {
+                write!(f, "Failed to read overrides file {:?}: {}", path, source)
+            }
+            OverridesError::MalformedLines(lines) => {
+                write!(f, "{} malformed lines in overrides file:", lines.len())?;
+                for (line, reason) in lines {
+                    write!(f, "\n\tline {line}: {reason}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for OverridesError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            OverridesError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// The archive files split into the ones an override is for and the ones left to parse
+#[derive(Debug, Default)]
+pub struct AppliedOverrides {
+    /// The stickers of the overridden files
+    pub stickers: Vec<Sticker>,
+    pub remaining: Vec<ArchiveFile>,
+    /// Overrides for files that are not in the archive anymore
+    pub stale: Vec<StickerOverride>,
+}
+
+impl StickerOverrides {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, OverridesError> {
+        let text = fs::read_to_string(&path).map_err(|source| OverridesError::Io {
+            path: path.as_ref().to_path_buf(),
+            source,
+        })?;
+        Ok(Self::parse(&text))
+    }
+
+    /// The overrides of the valid lines, the others are kept in `malformed`
+    pub fn parse(text: &str) -> Self {
+        let mut overrides = StickerOverrides::default();
+
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match parse_line(line, index + 1) {
+                // the first line of a target is used
+                Ok(entry) if overrides.overrides.iter().any(|o| o.target == entry.target) => {
+                    overrides
+                        .malformed
+                        .push((index + 1, format!("'{}' is overridden twice", entry.target)))
+                }
+                Ok(entry) => overrides.overrides.push(entry),
+                Err(reason) => overrides.malformed.push((index + 1, reason)),
+            }
+        }
+        overrides
+    }
+
+    /// The 1-based lines that could not be used, with the reason
+    pub fn malformed(&self) -> &[(usize, String)] {
+        &self.malformed
+    }
+
+    /// An error listing the malformed lines, if there are any
+    pub fn check(&self) -> Result<(), OverridesError> {
+        if self.malformed.is_empty() {
+            Ok(())
+        } else {
+            Err(OverridesError::MalformedLines(self.malformed.clone()))
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.overrides.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.overrides.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &StickerOverride> {
+        self.overrides.iter()
+    }
+
+    /// Builds the stickers of the overridden files and leaves the other files for parsing
+    pub fn apply(&self, files: Vec<ArchiveFile>) -> AppliedOverrides {
+        let mut applied = AppliedOverrides::default();
+        let mut used = vec![false; self.overrides.len()];
+
+        for file in files {
+            match self.overrides.iter().position(|o| o.target.matches(&file)) {
+                Some(position) => {
+                    used[position] = true;
+                    applied
+                        .stickers
+                        .extend(self.overrides[position].stickers(&file.name));
+                }
+                None => applied.remaining.push(file),
+            }
+        }
+
+        applied.stale = self
+            .overrides
+            .iter()
+            .zip(used)
+            .filter(|(_, used)| !used)
+            .map(|(entry, _)| entry.clone())
+            .collect();
+        applied
+    }
+}
+
+impl fmt::Display for StickerOverride {
+    /// The override as a line of the overrides file
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let dimensions: Vec<String> = self.dimensions.iter().map(|d| d.to_string()).collect();
+        write!(
+            f,
+            "{}; code={}; description={}; dimensions={}; material={}; color={}",
+            quoted(&self.target.to_string()),
+            self.code,
+            quoted(&self.description),
+            dimensions.join(", "),
+            self.material,
+            self.color
+        )
+    }
+}
+
+impl StickerOverride {
+    /// One sticker per size, the status and variant still come from the file name
+    pub fn stickers(&self, name: &str) -> Vec<Sticker> {
+        self.dimensions
+            .iter()
+            .map(|dimensions| Sticker {
+                code: self.code.clone(),
+                description: self.description.clone(),
+                variant: extract_variant(name, name),
+                dimensions: dimensions.clone(),
+                material: self.material.clone(),
+                text_color: self.color.clone(),
+                status: extract_status(name),
+                full_name: name.to_string(),
+                provenance: Provenance::Override { line: self.line },
+            })
+            .collect()
+    }
+}
+
+/// The value in quotes when it has a `;` or a quote, which would end or start a field
+fn quoted(value: &str) -> String {
+    if value.contains([';', '"']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// The value of a quoted field, other values as they are
+fn unquoted(value: &str) -> String {
+    match value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+    {
+        Some(inner) => inner.replace("\"\"", "\""),
+        None => value.to_string(),
+    }
+}
+
+/// The `;` separated fields of a line, a `;` in quotes is part of the field
+fn split_fields(line: &str) -> Vec<&str> {
+    let mut fields = Vec::new();
+    let mut in_quotes = false;
+    let mut start = 0;
+    for (index, c) in line.char_indices() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            ';' if !in_quotes => {
+                fields.push(line[start..index].trim());
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    fields.push(line[start..].trim());
+    fields
+}
+
+fn parse_line(line: &str, line_number: usize) -> Result<StickerOverride, String> {
+    let mut fields = split_fields(line).into_iter();
+    let target = unquoted(fields.next().unwrap_or_default());
+    if target.is_empty() {
+        return Err(format!("expected '<path or stem>; code=...', got '{line}'"));
+    }
+
+    let mut code = None;
+    let mut description = None;
+    let mut dimensions = Vec::new();
+    let mut material = None;
+    let mut color = None;
+    for field in fields.filter(|field| !field.is_empty()) {
+        match field.split_once('=').map(|(k, v)| (k.trim(), v.trim())) {
+            Some(("code", value)) => code = Some(code_normalizer().normalize(value)),
+            Some(("description", value)) => description = Some(unquoted(value).to_uppercase()),
+            Some(("dimensions", value)) => {
+                for size in value.split(',').filter(|size| !size.trim().is_empty()) {
+                    dimensions.push(Dimensions::parse_unchecked(size)?);
+                }
+            }
+            Some(("material", value)) => {
+                material = Some(
+                    value
+                        .parse::<Material>()
+                        .map_err(|_| format!("unknown material '{value}'"))?,
+                )
+            }
+            Some(("color", value)) => {
+                color = Some(
+                    value
+                        .parse::<Color>()
+                        .map_err(|_| format!("unknown color '{value}'"))?,
+                )
+            }
+            _ => return Err(format!("unknown setting '{field}'")),
+        }
+    }
+
+    let missing = |key: &str| format!("'{target}' has no {key}");
+    let code = code
+        .filter(|code| !code.is_empty())
+        .ok_or_else(|| missing("code"))?;
+    let description = description
+        .filter(|description| !description.is_empty())
+        .ok_or_else(|| missing("description"))?;
+    if dimensions.is_empty() {
+        return Err(missing("dimensions"));
+    }
+
+    Ok(StickerOverride {
+        target: OverrideTarget::parse(&target),
+        line: line_number,
+        code,
+        description,
+        dimensions,
+        material: material.ok_or_else(|| missing("material"))?,
+        color: color.unwrap_or_default(),
+    })
+}
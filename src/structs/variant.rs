@@ -0,0 +1,60 @@
+use super::vocabulary::vocabulary;
+use serde::{Deserialize, Serialize};
+
+// the variant of names without a variant entry of the vocabulary
+const REGULAR: &str = "REGULAR";
+
+/// The product variant a sticker is for, one of the `variant` entries of the vocabulary
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct Variant(String);
+
+impl Variant {
+    pub fn new(name: &str) -> Self {
+        Variant(name.to_uppercase())
+    }
+
+    pub fn regular() -> Self {
+        Variant(REGULAR.to_owned())
+    }
+
+    pub fn is_regular(&self) -> bool {
+        self.0 == REGULAR
+    }
+
+    pub fn name(&self) -> &str {
+        &self.0
+    }
+
+    /// The variant an order description ends with, regular if it ends with none.
+    ///
+    /// Only `anywhere` variants count elsewhere in it, a `TESTER KIT CREAM` is a regular order.
+    pub fn in_description(description: &str) -> Self {
+        vocabulary()
+            .find_variant_at_end(description)
+            .map(|entry| entry.variant.clone())
+            .unwrap_or_default()
+    }
+
+    /// The variant written in a variant column, its vocabulary name when it is an alias
+    pub fn from_cell(text: &str) -> Self {
+        if text.trim().is_empty() {
+            return Variant::regular();
+        }
+        vocabulary()
+            .find_variant(text)
+            .map(|(entry, _)| entry.variant.clone())
+            .unwrap_or_else(|| Variant::new(text.trim()))
+    }
+}
+
+impl Default for Variant {
+    fn default() -> Self {
+        Variant::regular()
+    }
+}
+
+impl std::fmt::Display for Variant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
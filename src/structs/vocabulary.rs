@@ -0,0 +1,350 @@
+use super::{color::Color, material::Material, variant::Variant};
+use regex::Regex;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, OnceLock};
+use std::{fmt, fs, io};
+
+// the vocabulary shipped with the binary, used when none is configured
+const BUILTIN_VOCABULARY: &str = include_str!("../../vocabulary.txt");
+
+static BUILTIN: LazyLock<Vocabulary> = LazyLock::new(|| {
+    Vocabulary::parse(BUILTIN_VOCABULARY).expect("The built-in vocabulary.txt is invalid")
+});
+static INSTALLED: OnceLock<Vocabulary> = OnceLock::new();
+
+// separators allowed after a material word, same as in the file names
+const SEPARATORS: &str = r"[_ ().&-]";
+
+#[derive(Debug, Clone)]
+pub struct MaterialEntry {
+    pub material: Material,
+    pub group: String,
+    pub excel_color: Option<u32>,
+    /// Searched in the whole name when nothing matches after the dimensions
+    anywhere: Option<Regex>,
+    pattern: Regex,
+}
+
+#[derive(Debug, Clone)]
+pub struct ColorEntry {
+    pub color: Color,
+    pub excel_color: Option<u32>,
+    pub default: bool,
+    pattern: Regex,
+}
+
+#[derive(Debug, Clone)]
+pub struct VariantEntry {
+    pub variant: Variant,
+    pub excel_color: Option<u32>,
+    /// Also matched in the description, not only after the dimensions
+    pub anywhere: bool,
+    pattern: Regex,
+}
+
+/// The materials, text colors and product variants a sticker name can contain
+#[derive(Debug, Clone, Default)]
+pub struct Vocabulary {
+    materials: Vec<MaterialEntry>,
+    colors: Vec<ColorEntry>,
+    variants: Vec<VariantEntry>,
+    fingerprint: u64,
+}
+
+#[derive(Debug)]
+pub enum VocabularyError {
+    Io { path: PathBuf, source: io::Error },
+    MalformedLines(Vec<(usize, String)>),
+}
+
+impl fmt::Display for VocabularyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VocabularyError::Io { path, source } => {
+                write!(f, "Failed to read vocabulary file {:?}: {}", path, source)
+            }
+            VocabularyError::MalformedLines(lines) => {
+                write!(f, "{} malformed lines in vocabulary file:", lines.len())?;
+                for (line, reason) in lines {
+                    write!(f, "\n\tline {line}: {reason}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for VocabularyError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            VocabularyError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// The key=value settings of one vocabulary line
+#[derive(Default)]
+struct EntrySettings {
+    group: Option<String>,
+    excel_color: Option<u32>,
+    patterns: Vec<String>,
+    anywhere: bool,
+    default: bool,
+}
+
+impl Vocabulary {
+    pub fn builtin() -> Self {
+        BUILTIN.clone()
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, VocabularyError> {
+        let text = fs::read_to_string(&path).map_err(|source| VocabularyError::Io {
+            path: path.as_ref().to_path_buf(),
+            source,
+        })?;
+        Self::parse(&text)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, VocabularyError> {
+        let mut vocabulary = Vocabulary::default();
+        let mut malformed = Vec::new();
+
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Err(reason) = vocabulary.parse_line(line) {
+                malformed.push((index + 1, reason));
+            }
+        }
+
+        if malformed.is_empty() {
+            let mut hasher = DefaultHasher::new();
+            text.hash(&mut hasher);
+            vocabulary.fingerprint = hasher.finish();
+            Ok(vocabulary)
+        } else {
+            Err(VocabularyError::MalformedLines(malformed))
+        }
+    }
+
+    fn parse_line(&mut self, line: &str) -> Result<(), String> {
+        let mut fields = line.split(';').map(str::trim);
+        let head = fields.next().unwrap_or_default();
+        let (kind, name) = head
+            .split_once(' ')
+            .map(|(kind, name)| (kind, name.trim()))
+            .filter(|(_, name)| !name.is_empty())
+            .ok_or_else(|| {
+                format!(
+                    "expected 'material <NAME>', 'color <NAME>' or 'variant <NAME>', got '{head}'"
+                )
+            })?;
+
+        let mut settings = EntrySettings::default();
+        for field in fields.filter(|field| !field.is_empty()) {
+            match field.split_once('=').map(|(k, v)| (k.trim(), v.trim())) {
+                Some(("group", group)) => settings.group = Some(group.to_uppercase()),
+                Some(("excel_color", hex)) => {
+                    let color = u32::from_str_radix(hex.trim_start_matches('#'), 16)
+                        .map_err(|_| format!("invalid excel_color '{hex}'"))?;
+                    settings.excel_color = Some(color);
+                }
+                Some(("pattern", pattern)) => settings.patterns.push(pattern.to_owned()),
+                Some(("alias", alias)) => settings.patterns.push(regex::escape(alias)),
+                None if field == "anywhere" => settings.anywhere = true,
+                None if field == "default" => settings.default = true,
+                _ => return Err(format!("unknown setting '{field}'")),
+            }
+        }
+
+        if settings.patterns.is_empty() {
+            return Err(format!("'{name}' has no pattern or alias"));
+        }
+        let alternatives = settings.patterns.join("|");
+        let pattern = match kind {
+            // a material must be followed by a separator or the end, e.g. PVC_R but not PVC_RED
+            "material" => format!("(?i)({alternatives})(?:{SEPARATORS}+|$)"),
+            // a variant is a whole token, so TESTERS or PROMOTION are not variants
+            "variant" => format!("(?i)(?:^|{SEPARATORS})({alternatives})(?:{SEPARATORS}|$)"),
+            _ => format!("(?i)({alternatives})"),
+        };
+        let pattern = Regex::new(&pattern).map_err(|e| format!("invalid pattern: {e}"))?;
+
+        match kind {
+            "material" => self.materials.push(MaterialEntry {
+                material: Material::new(name),
+                group: settings.group.unwrap_or_else(|| name.to_uppercase()),
+                excel_color: settings.excel_color,
+                anywhere: settings
+                    .anywhere
+                    .then(|| Regex::new(&format!("(?i){alternatives}")))
+                    .transpose()
+                    .map_err(|e| format!("invalid pattern: {e}"))?,
+                pattern,
+            }),
+            "color" => self.colors.push(ColorEntry {
+                color: Color::new(name),
+                excel_color: settings.excel_color,
+                default: settings.default,
+                pattern,
+            }),
+            "variant" if Variant::new(name).is_regular() => {
+                return Err(format!("'{name}' is the variant of names without one"))
+            }
+            "variant" => self.variants.push(VariantEntry {
+                variant: Variant::new(name),
+                excel_color: settings.excel_color,
+                anywhere: settings.anywhere,
+                pattern,
+            }),
+            other => return Err(format!("unknown entry kind '{other}'")),
+        }
+        Ok(())
+    }
+
+    /// The material with the longest match in `text` and where it matched
+    pub fn find_material(&self, text: &str) -> Option<(&MaterialEntry, Range<usize>)> {
+        let mut longest: Option<(&MaterialEntry, Range<usize>)> = None;
+        for entry in &self.materials {
+            for m in entry
+                .pattern
+                .captures_iter(text)
+                .filter_map(|caps| caps.get(1))
+            {
+                // ties keep the entry listed first
+                if longest
+                    .as_ref()
+                    .is_none_or(|(_, range)| m.len() > range.len())
+                {
+                    longest = Some((entry, m.range()));
+                }
+            }
+        }
+        longest
+    }
+
+    /// A material allowed to match anywhere in the name
+    pub fn find_material_anywhere(&self, name: &str) -> Option<&MaterialEntry> {
+        self.materials
+            .iter()
+            .find(|entry| entry.anywhere.as_ref().is_some_and(|re| re.is_match(name)))
+    }
+
+    /// The color matching first in `text` and where it matched
+    pub fn find_color(&self, text: &str) -> Option<(&ColorEntry, Range<usize>)> {
+        self.colors
+            .iter()
+            .filter_map(|entry| {
+                entry
+                    .pattern
+                    .captures(text)
+                    .and_then(|caps| caps.get(1))
+                    .map(|m| (entry, m.range()))
+            })
+            .min_by_key(|(_, range)| (range.start, usize::MAX - range.len()))
+    }
+
+    /// The variant matching first in `text` and where it matched
+    pub fn find_variant(&self, text: &str) -> Option<(&VariantEntry, Range<usize>)> {
+        self.variants
+            .iter()
+            .filter_map(|entry| {
+                entry
+                    .pattern
+                    .captures(text)
+                    .and_then(|caps| caps.get(1))
+                    .map(|m| (entry, m.range()))
+            })
+            .min_by_key(|(_, range)| range.start)
+    }
+
+    /// The variant ending `text`, or one allowed to match anywhere in it
+    pub fn find_variant_at_end(&self, text: &str) -> Option<&VariantEntry> {
+        let text = text.trim_end();
+        self.variants
+            .iter()
+            .find(|entry| {
+                entry
+                    .pattern
+                    .captures_iter(text)
+                    .filter_map(|caps| caps.get(1))
+                    .any(|m| m.end() == text.len())
+            })
+            .or_else(|| self.find_variant_anywhere(text))
+    }
+
+    /// A variant allowed to match anywhere in the name
+    pub fn find_variant_anywhere(&self, name: &str) -> Option<&VariantEntry> {
+        self.variants
+            .iter()
+            .find(|entry| entry.anywhere && entry.pattern.is_match(name))
+    }
+
+    pub fn variant_entry(&self, variant: &Variant) -> Option<&VariantEntry> {
+        self.variants.iter().find(|entry| &entry.variant == variant)
+    }
+
+    pub fn material_entry(&self, material: &Material) -> Option<&MaterialEntry> {
+        self.materials
+            .iter()
+            .find(|entry| &entry.material == material)
+    }
+
+    pub fn color_entry(&self, color: &Color) -> Option<&ColorEntry> {
+        self.colors.iter().find(|entry| &entry.color == color)
+    }
+
+    /// The color marked `default`, or the first one listed
+    pub fn default_color(&self) -> Option<&Color> {
+        self.colors
+            .iter()
+            .find(|entry| entry.default)
+            .or(self.colors.first())
+            .map(|entry| &entry.color)
+    }
+
+    /// Material groups in the order they first appear
+    pub fn groups(&self) -> Vec<&str> {
+        let mut groups: Vec<&str> = Vec::new();
+        for entry in &self.materials {
+            if !groups.contains(&entry.group.as_str()) {
+                groups.push(&entry.group);
+            }
+        }
+        groups
+    }
+
+    pub fn materials(&self) -> impl Iterator<Item = &MaterialEntry> {
+        self.materials.iter()
+    }
+
+    pub fn colors(&self) -> impl Iterator<Item = &ColorEntry> {
+        self.colors.iter()
+    }
+
+    /// Hash of the vocabulary text, changes whenever the file does
+    pub fn fingerprint(&self) -> u64 {
+        self.fingerprint
+    }
+
+    pub fn variants(&self) -> impl Iterator<Item = &VariantEntry> {
+        self.variants.iter()
+    }
+}
+
+/// Makes `vocabulary` the one used for parsing for the rest of the run.
+///
+/// Can only be done once, the vocabulary is handed back if one is already installed.
+pub fn install_vocabulary(vocabulary: Vocabulary) -> Result<(), Vocabulary> {
+    INSTALLED.set(vocabulary)
+}
+
+/// The installed vocabulary, or the built-in one if none was installed
+pub fn vocabulary() -> &'static Vocabulary {
+    INSTALLED.get().unwrap_or(&BUILTIN)
+}
@@ -0,0 +1,344 @@
+use crate::{
+    configs::{is_generated_file, Configs},
+    runs::Pipeline,
+    structs::{order::Order, sticker::Sticker},
+};
+use chrono::Local;
+use colored::*;
+use glob::Pattern;
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::Duration;
+use std::{fmt, fs};
+
+pub const DEFAULT_DEBOUNCE_MS: u64 = 1000;
+
+/// Which order codes have stickers in the archive
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OrderCoverage {
+    pub resolved: BTreeSet<String>,
+    pub missing: BTreeSet<String>,
+}
+
+/// The order codes whose coverage changed between two runs
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CoverageChanges {
+    pub newly_resolved: Vec<String>,
+    pub newly_missing: Vec<String>,
+}
+
+impl OrderCoverage {
+    pub fn new(orders: &[Order], code_to_stickers: &HashMap<String, Vec<Sticker>>) -> Self {
+        let mut coverage = Self::default();
+        for order in orders {
+            if order.stickers_in(code_to_stickers).is_empty() {
+                coverage.missing.insert(order.code.clone());
+            } else {
+                coverage.resolved.insert(order.code.clone());
+            }
+        }
+        // a code is resolved once the stickers of all its ordered variants are there
+        coverage
+            .resolved
+            .retain(|code| !coverage.missing.contains(code));
+        coverage
+    }
+
+    /// Codes resolved now but not before, and codes missing now but not before.
+    ///
+    /// A code that was not ordered before counts as newly missing if it has no stickers.
+    pub fn changes_since(&self, previous: &OrderCoverage) -> CoverageChanges {
+        CoverageChanges {
+            newly_resolved: self
+                .resolved
+                .difference(&previous.resolved)
+                .cloned()
+                .collect(),
+            newly_missing: self
+                .missing
+                .difference(&previous.missing)
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+impl CoverageChanges {
+    pub fn is_empty(&self) -> bool {
+        self.newly_resolved.is_empty() && self.newly_missing.is_empty()
+    }
+}
+
+impl fmt::Display for CoverageChanges {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "No order codes changed");
+        }
+        for code in &self.newly_resolved {
+            writeln!(f, "{} {}", "+".green().bold(), code.green())?;
+        }
+        for code in &self.newly_missing {
+            writeln!(f, "{} {}", "-".red().bold(), code.red())?;
+        }
+        write!(
+            f,
+            "{} newly resolved, {} newly missing",
+            self.newly_resolved.len(),
+            self.newly_missing.len()
+        )
+    }
+}
+
+#[derive(Debug)]
+pub enum WatchError {
+    Notify(notify::Error),
+    /// The watcher stopped sending events
+    Disconnected,
+    /// The vocabulary or dimensions catalog changed, it is only read when the process starts
+    InstalledChanged(PathBuf),
+}
+
+impl fmt::Display for WatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WatchError::Notify(e) => write!(f, "Failed to watch for changes: {e}"),
+            WatchError::Disconnected => write!(f, "The file watcher stopped unexpectedly"),
+            WatchError::InstalledChanged(path) => write!(
+                f,
+                "{path:?} changed, it is only read when watch starts, restart watch to use it"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for WatchError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            WatchError::Notify(e) => Some(e),
+            WatchError::Disconnected | WatchError::InstalledChanged(_) => None,
+        }
+    }
+}
+
+impl From<notify::Error> for WatchError {
+    fn from(e: notify::Error) -> Self {
+        WatchError::Notify(e)
+    }
+}
+
+/// The directory of an `order` entry and the pattern of its order files
+struct WatchedOrders {
+    dir: PathBuf,
+    mode: RecursiveMode,
+    pattern: Pattern,
+}
+
+/// The paths a watch run reacts to
+struct WatchedPaths {
+    archive: PathBuf,
+    orders: Vec<WatchedOrders>,
+    /// The code aliases and overrides, read again by every run
+    files: Vec<PathBuf>,
+    /// The vocabulary and dimensions catalog, only read when the process starts
+    installed: Vec<PathBuf>,
+}
+
+/// The absolute path of a file that may not exist yet, e.g. overrides before `resolve` saves one
+fn absolute_file(path: &Path) -> PathBuf {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let dir = fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf());
+    match path.file_name() {
+        Some(name) => dir.join(name),
+        None => dir,
+    }
+}
+
+impl WatchedPaths {
+    fn new(configs: &Configs) -> Self {
+        // events carry absolute paths, the configured ones may be relative
+        let absolute = |path: &Path| fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+
+        // the order workbooks are usually saved by replacing them, so their directories are
+        // watched, recursively when the glob has wildcards in a directory
+        let orders = configs
+            .orders
+            .iter()
+            .filter_map(|input| {
+                let (base, rest) = input.split_at_base_dir();
+                let dir = absolute(&base);
+                let pattern = PathBuf::from(Pattern::escape(&dir.to_string_lossy())).join(&rest);
+                let mode = if rest.components().count() > 1 {
+                    RecursiveMode::Recursive
+                } else {
+                    RecursiveMode::NonRecursive
+                };
+                Some(WatchedOrders {
+                    pattern: Pattern::new(&pattern.to_string_lossy()).ok()?,
+                    dir,
+                    mode,
+                })
+            })
+            .collect();
+
+        let files = [&configs.code_aliases_path, &configs.overrides_path]
+            .into_iter()
+            .flatten()
+            .map(|path| absolute_file(path))
+            .collect();
+        let installed = [&configs.vocabulary_path, &configs.dimensions_path]
+            .into_iter()
+            .flatten()
+            .map(|path| absolute_file(path))
+            .collect();
+
+        Self {
+            archive: absolute(&configs.archive_path),
+            orders,
+            files,
+            installed,
+        }
+    }
+
+    /// The directories of the single files, watched instead of the files so files saved by
+    /// replacing them or created later are seen
+    fn file_dirs(&self) -> BTreeSet<&Path> {
+        self.files
+            .iter()
+            .chain(&self.installed)
+            .filter_map(|file| file.parent())
+            .filter(|dir| dir.is_dir())
+            .collect()
+    }
+
+    /// The vocabulary or dimensions files the event changed
+    fn changed_installed<'a>(&'a self, event: &Event) -> Vec<&'a Path> {
+        self.installed
+            .iter()
+            .filter(|file| event.paths.contains(file))
+            .map(PathBuf::as_path)
+            .collect()
+    }
+
+    fn is_relevant(&self, event: &Event) -> bool {
+        if matches!(event.kind, EventKind::Access(_)) {
+            return false;
+        }
+        event.paths.iter().any(|path| {
+            let in_archive = path.starts_with(&self.archive)
+                && path
+                    .extension()
+                    // directories have no extension, renaming one moves all its files
+                    .is_none_or(|ext| ext.eq_ignore_ascii_case("cdr"));
+            // the workbooks written by a run are left out, a glob could match them
+            let is_order = !is_generated_file(path)
+                && self
+                    .orders
+                    .iter()
+                    .any(|orders| orders.pattern.matches_path(path));
+            let is_file = self
+                .files
+                .iter()
+                .chain(&self.installed)
+                .any(|file| file == path);
+            in_archive || is_order || is_file
+        })
+    }
+}
+
+/// Runs the pipeline, then again after every change to the archive, the order files, the code
+/// aliases or the overrides.
+///
+/// A changed vocabulary or dimensions catalog ends the watch with `InstalledChanged`, both are
+/// read once when the process starts and a run with the old ones would report wrong changes.
+///
+/// Changes are collected until none arrived for `debounce`, so copying many files at once
+/// triggers a single run. Only returns on an error.
+pub fn watch(pipeline: &Pipeline, configs: &Configs, debounce: Duration) -> Result<(), WatchError> {
+    let paths = WatchedPaths::new(configs);
+    let (sender, receiver) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender)?;
+    watcher.watch(&paths.archive, RecursiveMode::Recursive)?;
+    for orders in &paths.orders {
+        watcher.watch(&orders.dir, orders.mode)?;
+    }
+    for dir in paths.file_dirs() {
+        watcher.watch(dir, RecursiveMode::NonRecursive)?;
+    }
+
+    let mut coverage = run_once(pipeline, configs, None);
+
+    loop {
+        let orders: Vec<String> = configs
+            .orders
+            .iter()
+            .map(|input| input.to_string())
+            .collect();
+        println!(
+            "\n{} {:?} and {} for changes...",
+            "Watching".bold().blue(),
+            paths.archive,
+            orders.join(", ")
+        );
+
+        // wait for the first relevant change, then until the changes settle
+        let check_installed = |event: &Event| match paths.changed_installed(event).first() {
+            Some(path) => Err(WatchError::InstalledChanged(path.to_path_buf())),
+            None => Ok(()),
+        };
+        loop {
+            match receiver.recv() {
+                Ok(Ok(event)) if paths.is_relevant(&event) => {
+                    check_installed(&event)?;
+                    break;
+                }
+                Ok(Ok(_)) => continue,
+                Ok(Err(e)) => eprintln!("!!!WARNING: FILE WATCHER ERROR, {e}!!!"),
+                Err(_) => return Err(WatchError::Disconnected),
+            }
+        }
+        loop {
+            match receiver.recv_timeout(debounce) {
+                Ok(Ok(event)) => check_installed(&event)?,
+                Ok(Err(_)) => continue,
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => return Err(WatchError::Disconnected),
+            }
+        }
+
+        coverage = run_once(pipeline, configs, coverage.as_ref()).or(coverage);
+    }
+}
+
+/// Runs the pipeline and prints how the order coverage changed since `previous`
+fn run_once(
+    pipeline: &Pipeline,
+    configs: &Configs,
+    previous: Option<&OrderCoverage>,
+) -> Option<OrderCoverage> {
+    println!(
+        "\n{} {}",
+        "Run at".bold().blue(),
+        Local::now().format("%H:%M:%S")
+    );
+    let output = pipeline.run_reporting(configs, None);
+
+    // the workbook can be half written while a planner saves it, the next save triggers a run
+    let import = output.orders.ok()?;
+    let coverage = OrderCoverage::new(&import.orders, &output.code_to_stickers);
+
+    match previous {
+        Some(previous) => println!("{}", coverage.changes_since(previous)),
+        None => println!(
+            "{} codes resolved, {} codes missing",
+            coverage.resolved.len(),
+            coverage.missing.len()
+        ),
+    }
+
+    Some(coverage)
+}
@@ -0,0 +1,99 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::TestDir;
+    use order_processor::{
+        index::{ArchiveIndex, IndexStats},
+        parser,
+    };
+    use std::{fs, path::PathBuf};
+
+    fn archive(tmp: &TestDir, files: &[&str]) -> PathBuf {
+        for file in files {
+            tmp.write(&format!("archive/{file}"), "");
+        }
+        tmp.path().join("archive")
+    }
+
+    #[test]
+    fn test_reuses_unchanged_files() {
+        let tmp = TestDir::new("index_unchanged");
+        let dir = archive(
+            &tmp,
+            &[
+                "234191_AV CLEAN GEL TUBE 200ML_50X50_PVC_R_OK_PF.cdr",
+                "AV CLEAN GEL_50X50_PVC_R.cdr",
+            ],
+        );
+        let index_path = tmp.path().join("index.json");
+        let files = parser::collect_cdr_files(&dir);
+
+        let mut index = ArchiveIndex::default();
+        let (results, stats) = index.update(&files);
+        assert_eq!(results.iter().filter(|r| r.is_ok()).count(), 1);
+        assert_eq!(
+            stats,
+            IndexStats {
+                reused: 0,
+                parsed: 2,
+                removed: 0
+            }
+        );
+        index.save(&index_path).unwrap();
+
+        let mut index = ArchiveIndex::load(&index_path).unwrap();
+        let (reused_results, stats) = index.update(&files);
+        assert_eq!(stats.reused, 2);
+        assert_eq!(reused_results[0].as_ref().ok(), results[0].as_ref().ok());
+    }
+
+    #[test]
+    fn test_deleted_and_renamed_files() {
+        let tmp = TestDir::new("index_renamed");
+        let dir = archive(
+            &tmp,
+            &[
+                "234191_AV CLEAN GEL TUBE 200ML_50X50_PVC_R_OK_PF.cdr",
+                "238309_AV TOL LOT 200ML_40X60_PVC_OK_PF.cdr",
+            ],
+        );
+        let mut index = ArchiveIndex::default();
+        index.update(&parser::collect_cdr_files(&dir));
+
+        fs::remove_file(dir.join("238309_AV TOL LOT 200ML_40X60_PVC_OK_PF.cdr")).unwrap();
+        fs::rename(
+            dir.join("234191_AV CLEAN GEL TUBE 200ML_50X50_PVC_R_OK_PF.cdr"),
+            dir.join("234191_AV CLEAN GEL TUBE 200ML_60X40_PVC_R_OK_PF.cdr"),
+        )
+        .unwrap();
+
+        let (results, stats) = index.update(&parser::collect_cdr_files(&dir));
+        assert_eq!(
+            stats,
+            IndexStats {
+                reused: 0,
+                parsed: 1,
+                removed: 2
+            }
+        );
+        assert_eq!(
+            results[0].as_ref().unwrap()[0].dimensions.to_string(),
+            "60x40"
+        );
+        assert_eq!(index.len(), 1);
+    }
+
+    #[test]
+    fn test_corrupt_index() {
+        let tmp = TestDir::new("index_corrupt");
+        let dir = tmp.path();
+        let index_path = dir.join("index.json");
+        fs::write(&index_path, "not an index").unwrap();
+
+        assert!(ArchiveIndex::load(&index_path).is_err());
+        assert!(ArchiveIndex::load(dir.join("missing.json"))
+            .unwrap()
+            .is_empty());
+    }
+}
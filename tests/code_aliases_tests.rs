@@ -0,0 +1,86 @@
+#[cfg(test)]
+mod tests {
+    use order_processor::structs::code_aliases::{AliasProblem, CodeAliases, CodeAliasesError};
+    use order_processor::structs::provenance::Provenance;
+    use order_processor::structs::sticker::Sticker;
+    use std::collections::HashMap;
+
+    fn code_to_stickers(names: &[&str]) -> HashMap<String, Vec<Sticker>> {
+        let mut map: HashMap<String, Vec<Sticker>> = HashMap::new();
+        for name in names {
+            for sticker in Sticker::parse_stickers(name).unwrap() {
+                map.entry(sticker.code.clone()).or_default().push(sticker);
+            }
+        }
+        map
+    }
+
+    #[test]
+    fn test_parse() {
+        let aliases = CodeAliases::parse(
+            "# renamed in 2024\n\
+             0100 -> 200\n\
+             101, 102 -> 200\n\
+             200 -> 300\n",
+        )
+        .unwrap();
+        assert_eq!(aliases.len(), 4);
+        assert_eq!(aliases.successors("100"), Some(vec!["200", "300"]));
+        assert_eq!(aliases.successors("102"), Some(vec!["200", "300"]));
+        assert_eq!(aliases.successors("300"), None);
+
+        let Err(CodeAliasesError::MalformedLines(lines)) =
+            CodeAliases::parse("100 200\n100 -> 200\n100 -> 300\n400 -> 0400\n -> 500")
+        else {
+            panic!("expected malformed lines");
+        };
+        let numbers: Vec<usize> = lines.iter().map(|(line, _)| *line).collect();
+        assert_eq!(numbers, [1, 3, 4, 5]);
+    }
+
+    #[test]
+    fn test_cycles() {
+        let aliases = CodeAliases::parse("300 -> 100\n100 -> 200\n200 -> 300\n400 -> 100").unwrap();
+        assert_eq!(aliases.cycles(), [vec!["100", "200", "300"]]);
+        assert_eq!(aliases.successors("100"), None);
+        assert_eq!(aliases.successors("400"), None);
+
+        let problems = aliases.problems(&code_to_stickers(&["400_GEL_60X40_PVC_OK"]));
+        assert_eq!(
+            problems[0],
+            AliasProblem::Cycle(vec!["100".into(), "200".into(), "300".into()])
+        );
+        // 400 has stickers, the codes of the cycle are only reported as the cycle
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].to_string(), "cycle 100 -> 200 -> 300 -> 100");
+    }
+
+    #[test]
+    fn test_apply() {
+        let aliases = CodeAliases::parse("100 -> 200\n200 -> 300\n400 -> 500").unwrap();
+        let mut map = code_to_stickers(&["100_OLD GEL_60X40_PVC_OK", "300_NEW GEL_60X40_PVC_OK"]);
+
+        assert_eq!(
+            aliases.problems(&map),
+            [AliasProblem::UnknownCode {
+                old: "400".into(),
+                new: "500".into()
+            }]
+        );
+
+        aliases.apply(&mut map);
+        // 200 gets the stickers of 100, 300 keeps its own
+        assert_eq!(map["200"][0].code, "100");
+        assert_eq!(
+            map["200"][0].provenance,
+            Provenance::Alias {
+                old: "100".into(),
+                new: "200".into()
+            }
+        );
+        assert_eq!(map["300"].len(), 1);
+        assert_eq!(map["300"][0].code, "300");
+        assert!(map["300"][0].provenance.is_parsed());
+        assert!(!map.contains_key("500"));
+    }
+}
@@ -0,0 +1,45 @@
+#[cfg(test)]
+mod tests {
+    use order_processor::structs::code_normalizer::{code_normalizer, CodeNormalizer};
+    use order_processor::structs::sticker::Sticker;
+
+    #[test]
+    fn test_normalize() {
+        let normalizer = CodeNormalizer::default();
+        assert_eq!(normalizer.normalize(" 234191 "), "234191");
+        assert_eq!(normalizer.normalize("234191.0"), "234191");
+        assert_eq!(normalizer.normalize("0234191"), "234191");
+        assert_eq!(normalizer.normalize("000"), "0");
+        assert_eq!(normalizer.normalize("234191.5"), "234191.5");
+        // Cyrillic А, В and С typed for the Latin letters
+        assert_eq!(normalizer.normalize("АВС123"), "ABC123");
+        assert_eq!(normalizer.normalize("abc123"), "ABC123");
+    }
+
+    #[test]
+    fn test_prefixes() {
+        let normalizer = CodeNormalizer::new(["bg", "BG-", ""]);
+        assert_eq!(normalizer.prefixes(), ["BG-", "BG"]);
+        assert_eq!(normalizer.normalize("BG-0234191"), "234191");
+        assert_eq!(normalizer.normalize("ВG 234191"), "234191");
+        assert_eq!(normalizer.normalize("BG"), "BG");
+        assert_eq!(
+            normalizer.strip_prefix("BG-234191_CREAM_60X40_PVC"),
+            "234191_CREAM_60X40_PVC"
+        );
+        assert_eq!(normalizer.strip_prefix("BGN"), "N");
+    }
+
+    #[test]
+    fn test_sticker_code_matches_order_code() {
+        // no prefixes are installed in the tests
+        assert!(code_normalizer().prefixes().is_empty());
+
+        let sticker = Sticker::parse_stickers("0234191_AV CLEAN GEL_60X40_PVC_R_OK")
+            .unwrap()
+            .remove(0);
+        assert_eq!(sticker.code, "234191");
+        assert_eq!(sticker.description, "AV CLEAN GEL");
+        assert_eq!(code_normalizer().normalize("234191.0"), sticker.code);
+    }
+}
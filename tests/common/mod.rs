@@ -0,0 +1,53 @@
+//! Fixtures shared by the test files, each file uses only some of them
+#![allow(dead_code)]
+
+use order_processor::structs::sticker::Sticker;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The stickers of names that parse
+pub fn stickers(names: &[&str]) -> Vec<Sticker> {
+    names
+        .iter()
+        .flat_map(|name| Sticker::parse_stickers(name).unwrap())
+        .collect()
+}
+
+/// An empty directory of one test, removed when the test ends.
+///
+/// The names must differ between tests, they run in parallel. What an earlier run left behind
+/// is removed first.
+pub struct TestDir {
+    path: PathBuf,
+}
+
+impl TestDir {
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir()
+            .join("order_processor_tests")
+            .join(name);
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        Self { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Writes a file, `name` may have directories in it
+    pub fn write(&self, name: &str, contents: impl AsRef<[u8]>) -> PathBuf {
+        let path = self.path.join(name);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).unwrap();
+        }
+        fs::write(&path, contents).unwrap();
+        path
+    }
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
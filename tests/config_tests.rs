@@ -0,0 +1,265 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::TestDir;
+    use order_processor::configs::{
+        column_letter, suggest_key, AmbiguousInferences, ColumnRef, ConfigError, ConfigOverrides,
+        Configs, Location, NonOkStickers, OrderInput,
+    };
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_missing_file() {
+        let result = Configs::load_from_file("this_config_does_not_exist.txt");
+        assert!(matches!(result, Err(ConfigError::Io { .. })));
+    }
+
+    #[test]
+    fn test_valid_config() {
+        let tmp = TestDir::new("config_valid_config");
+        let order = tmp.write("order.xlsx", "");
+        let dir = order.parent().unwrap();
+        let path = tmp.write(
+            "valid.txt",
+            format!(
+                "# comment\narchive={}\norder={}\ninferring_levenshtein_distance=0.9\nerror_output_levenshtein_distance=0.5\n",
+                dir.display(),
+                order.display()
+            ),
+        );
+
+        let configs = Configs::load_from_file(path).unwrap();
+        assert_eq!(configs.inferring_levenshtein_distance, 0.9);
+        assert_eq!(configs.error_output_levenshtein_distance, 0.5);
+        assert_eq!(configs.sheet_name, None);
+    }
+
+    #[test]
+    fn test_all_problems_are_collected() {
+        let tmp = TestDir::new("config_all_problems_are_collected");
+        let path = tmp.write(
+            "broken.txt",
+            "archive=/definitely/not/a/dir\njust some text\ninferring_levenshtein_distance=abc\nerror_output_levenshtein_distance=1.5\n",
+        );
+
+        let Err(ConfigError::Multiple(errors)) = Configs::load_from_file(path) else {
+            panic!("expected multiple errors");
+        };
+        assert!(matches!(
+            errors[0],
+            ConfigError::MalformedLine { line: 2, .. }
+        ));
+        assert!(matches!(
+            errors[1],
+            ConfigError::InvalidFloat { line: 3, .. }
+        ));
+        assert!(matches!(
+            errors[2],
+            ConfigError::OutOfRange {
+                location: Location::Line(4),
+                ..
+            }
+        ));
+        assert!(matches!(
+            errors[3],
+            ConfigError::InvalidArchive {
+                location: Location::Line(1),
+                ..
+            }
+        ));
+        assert_eq!(errors.len(), 4);
+    }
+
+    #[test]
+    fn test_orders_checked_separately() {
+        let tmp = TestDir::new("config_orders_checked_separately");
+        let order = tmp.write("order_checked.csv", "");
+        let dir = order.parent().unwrap();
+        let path = tmp.write("no_orders.txt", format!("archive={}\n", dir.display()));
+        // the commands that do not read orders work without them
+        let configs = Configs::load_from_file(path).unwrap();
+        assert!(matches!(
+            configs.check_orders(),
+            Err(ConfigError::MissingKey("order"))
+        ));
+
+        let path = tmp.write(
+            "unmatched_orders.txt",
+            format!(
+                "archive={}\norder={}, {}\n",
+                dir.display(),
+                order.display(),
+                dir.join("nothing_*.xlsx").display()
+            ),
+        );
+        let configs = Configs::load_from_file(path).unwrap();
+        assert!(matches!(
+            configs.check_orders(),
+            Err(ConfigError::NoOrderFiles {
+                location: Location::Line(2),
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_threshold_order() {
+        let tmp = TestDir::new("config_threshold_order");
+        let order = tmp.write("order_thresholds.xls", "");
+        let dir = order.parent().unwrap();
+        let path = tmp.write(
+            "thresholds.txt",
+            format!(
+                "archive={}\norder={}\ninferring_levenshtein_distance=0.6\nerror_output_levenshtein_distance=0.8\n",
+                dir.display(),
+                order.display()
+            ),
+        );
+
+        assert!(matches!(
+            Configs::load_from_file(path),
+            Err(ConfigError::ThresholdOrder { .. })
+        ));
+    }
+
+    #[test]
+    fn test_overrides() {
+        let tmp = TestDir::new("config_overrides");
+        let order = tmp.write("order_overrides.xlsx", "");
+        let dir = order.parent().unwrap();
+        let path = tmp.write("overrides.txt", "archive=/definitely/not/a/dir\n");
+
+        let overrides = ConfigOverrides {
+            archive: Some(dir.to_path_buf()),
+            order: vec![order.display().to_string()],
+            sheet_name: Some("Orders".to_string()),
+            ..Default::default()
+        };
+
+        let configs = Configs::load(path, &overrides).unwrap();
+        assert_eq!(configs.archive_path, dir);
+        assert_eq!(
+            configs.orders,
+            vec![OrderInput::parse(&order.to_string_lossy())]
+        );
+        assert_eq!(configs.sheet_name.as_deref(), Some("Orders"));
+    }
+
+    #[test]
+    fn test_non_ok_stickers() {
+        let tmp = TestDir::new("config_non_ok_stickers");
+        let order = tmp.write("order_non_ok.xlsx", "");
+        let dir = order.parent().unwrap().display().to_string();
+        let contents = format!(
+            "archive={dir}\norder={}\nnon_ok_stickers=Highlight\n",
+            order.display()
+        );
+        let path = tmp.write("non_ok.txt", &contents);
+
+        let configs = Configs::load(&path, &ConfigOverrides::default()).unwrap();
+        assert_eq!(configs.non_ok_stickers, NonOkStickers::Highlight);
+
+        let overrides = ConfigOverrides {
+            non_ok_stickers: Some(NonOkStickers::Exclude),
+            ..Default::default()
+        };
+        let configs = Configs::load(&path, &overrides).unwrap();
+        assert_eq!(configs.non_ok_stickers, NonOkStickers::Exclude);
+
+        assert_eq!(configs.non_ok_stickers.to_string(), "exclude");
+
+        let path = tmp.write("non_ok_invalid.txt", contents.replace("Highlight", "hide"));
+        let Err(error @ ConfigError::InvalidChoice { line: 3, .. }) =
+            Configs::load(path, &ConfigOverrides::default())
+        else {
+            panic!("expected an invalid choice");
+        };
+        // the choices are the ones of the flag
+        assert!(error
+            .to_string()
+            .ends_with("expected one of include, exclude, highlight"));
+    }
+
+    #[test]
+    fn test_ambiguous_inferences() {
+        let tmp = TestDir::new("config_ambiguous_inferences");
+        let order = tmp.write("order_ambiguous.xlsx", "");
+        let dir = order.parent().unwrap().display().to_string();
+        let contents = format!("archive={dir}\norder={}\n", order.display());
+        let path = tmp.write("ambiguous.txt", &contents);
+
+        let configs = Configs::load(&path, &ConfigOverrides::default()).unwrap();
+        assert_eq!(configs.ambiguous_inferences, AmbiguousInferences::Exclude);
+
+        let path = tmp.write(
+            "ambiguous_include.txt",
+            format!("{contents}ambiguous_inferences=include\n"),
+        );
+        let configs = Configs::load(&path, &ConfigOverrides::default()).unwrap();
+        assert_eq!(configs.ambiguous_inferences, AmbiguousInferences::Include);
+
+        let path = tmp.write(
+            "ambiguous_invalid.txt",
+            format!("{contents}ambiguous_inferences=all\n"),
+        );
+        assert!(matches!(
+            Configs::load(path, &ConfigOverrides::default()),
+            Err(ConfigError::InvalidChoice { line: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn test_archive_index() {
+        let tmp = TestDir::new("config_archive_index");
+        let order = tmp.write("order_index.xlsx", "");
+        let dir = order.parent().unwrap();
+        let contents = format!("archive={}\norder={}\n", dir.display(), order.display());
+        let path = tmp.write("index.txt", &contents);
+
+        // the shared archive is not written to unless asked to
+        let configs = Configs::load(&path, &ConfigOverrides::default()).unwrap();
+        assert_eq!(
+            configs.archive_index_path,
+            Some(PathBuf::from("archive_index.json"))
+        );
+
+        let path = tmp.write(
+            "index_archive.txt",
+            format!("{contents}archive_index=<archive>/index.json\n"),
+        );
+        let configs = Configs::load(&path, &ConfigOverrides::default()).unwrap();
+        assert_eq!(configs.archive_index_path, Some(dir.join("index.json")));
+
+        let path = tmp.write("index_off.txt", format!("{contents}archive_index=OFF\n"));
+        let configs = Configs::load(&path, &ConfigOverrides::default()).unwrap();
+        assert_eq!(configs.archive_index_path, None);
+        assert!(configs.to_string().contains("archive_index=off"));
+    }
+
+    #[test]
+    fn test_key_suggestion() {
+        assert_eq!(suggest_key("achive"), Some("archive"));
+        assert_eq!(suggest_key("sheetname"), Some("sheet_name"));
+        assert_eq!(suggest_key("completely_unrelated"), None);
+    }
+
+    #[test]
+    fn test_column_ref() {
+        assert_eq!(ColumnRef::parse("A"), ColumnRef::Index(0));
+        assert_eq!(ColumnRef::parse(" L "), ColumnRef::Index(11));
+        assert_eq!(ColumnRef::parse("AA"), ColumnRef::Index(26));
+        assert_eq!(
+            ColumnRef::parse("Qty"),
+            ColumnRef::Header("Qty".to_string())
+        );
+        assert_eq!(
+            ColumnRef::parse("\"QTY\""),
+            ColumnRef::Header("QTY".to_string())
+        );
+        assert_eq!(column_letter(0), "A");
+        assert_eq!(column_letter(25), "Z");
+        assert_eq!(column_letter(27), "AB");
+        assert_eq!(ColumnRef::Header("QTY".to_string()).to_string(), "\"QTY\"");
+    }
+}
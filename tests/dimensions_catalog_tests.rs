@@ -0,0 +1,88 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::TestDir;
+    use order_processor::structs::dimensions::{CatalogError, Dimensions, DimensionsCatalog};
+
+    #[test]
+    fn test_load_catalog() {
+        let tmp = TestDir::new("dimensions_load_catalog");
+        let path = tmp.write("valid.txt", "# sizes\n60x40\n\n80Х55\n");
+        let (catalog, duplicates) = DimensionsCatalog::load(path).unwrap();
+
+        assert_eq!(catalog.len(), 2);
+        assert!(duplicates.is_empty());
+        assert_eq!(
+            catalog.official(&Dimensions::parse_unchecked("40x60").unwrap()),
+            Some(Dimensions {
+                width: 60,
+                height: 40
+            })
+        );
+        assert_eq!(
+            catalog.official(&Dimensions::parse_unchecked("61x40").unwrap()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_malformed_lines() {
+        let tmp = TestDir::new("dimensions_malformed_lines");
+        let path = tmp.write("malformed.txt", "60x40\n60-40\n0x10\n80x55\n");
+
+        let Err(CatalogError::MalformedLines(lines)) = DimensionsCatalog::load(path) else {
+            panic!("expected malformed lines");
+        };
+        assert_eq!(
+            lines.iter().map(|(line, _)| *line).collect::<Vec<_>>(),
+            vec![2, 3]
+        );
+    }
+
+    #[test]
+    fn test_duplicates() {
+        let tmp = TestDir::new("dimensions_duplicates");
+        let path = tmp.write("duplicates.txt", "60x40\n80x55\n60x40\n40x60\n");
+        let (catalog, duplicates) = DimensionsCatalog::load(path).unwrap();
+
+        assert_eq!(catalog.len(), 3);
+        assert_eq!(duplicates.len(), 2);
+        assert_eq!((duplicates[0].line, duplicates[0].first_line), (3, 1));
+        assert!(!duplicates[0].rotated);
+        assert_eq!((duplicates[1].line, duplicates[1].first_line), (4, 1));
+        assert!(duplicates[1].rotated);
+    }
+
+    #[test]
+    fn test_suggestions() {
+        let catalog = DimensionsCatalog::new([
+            Dimensions {
+                width: 60,
+                height: 40,
+            },
+            Dimensions {
+                width: 80,
+                height: 55,
+            },
+        ]);
+        let rejected = Dimensions::parse_unchecked("41x60").unwrap();
+
+        let suggestions = catalog.suggestions(&rejected);
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].official.to_string(), "60x40");
+        assert!(suggestions[0].rotated);
+        assert_eq!(suggestions[0].distance, 1);
+
+        assert!(catalog
+            .suggestions(&Dimensions::parse_unchecked("63x40").unwrap())
+            .is_empty());
+        assert_eq!(
+            catalog
+                .with_tolerance(3)
+                .suggestions(&Dimensions::parse_unchecked("63x40").unwrap())
+                .len(),
+            1
+        );
+    }
+}
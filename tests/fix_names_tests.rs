@@ -0,0 +1,118 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::TestDir;
+    use order_processor::fix_names::{
+        apply_plan, canonical_stem, plan, read_plan, undo_log_path, write_plan, NameFix,
+    };
+    use order_processor::parser::{collect_cdr_files, parse_files};
+    use order_processor::similarity::{DescriptionIndex, Similarity};
+    use order_processor::structs::sticker::Sticker;
+
+    #[test]
+    fn test_canonical_stem() {
+        let (name, fixes) = canonical_stem("234191 Cream 60 х 40 PVC-R_OK", None);
+        assert_eq!(name, "234191 Cream_60X40 PVC_R_OK");
+        assert_eq!(
+            fixes,
+            [
+                NameFix::Dimensions {
+                    from: "60 х 40".into(),
+                    to: "60X40".into()
+                },
+                NameFix::UnderscoreBeforeDimensions,
+                NameFix::Material {
+                    from: "PVC-R".into(),
+                    to: "PVC_R".into()
+                },
+            ]
+        );
+
+        let (name, fixes) = canonical_stem("234191_CREAM_60X40_PVC_R_OK", None);
+        assert_eq!(name, "234191_CREAM_60X40_PVC_R_OK");
+        assert!(fixes.is_empty());
+
+        // sizes that are not official are left alone
+        let (name, _) = canonical_stem("AV CREAM_61x41_PVC", Some("234191"));
+        assert_eq!(name, "234191_AV CREAM_61x41_PVC");
+    }
+
+    #[test]
+    fn test_plan_apply_and_undo() {
+        let tmp = TestDir::new("fix_names_plan_apply_and_undo");
+        let dir = tmp.path();
+        for name in [
+            "234191_AV CLEAN CREAM_60X40_PVC_OK",
+            "AV CLEAN CREAM_30X20_PVC_OK",
+            "234192_SOAP 60х40_PVC-R",
+            "234193_BROKEN",
+            "234194_GEL 60 x 40_PVC",
+        ] {
+            tmp.write(&format!("{name}.cdr"), "");
+        }
+
+        let files = collect_cdr_files(dir);
+        let (stickers, errors): (Vec<Vec<Sticker>>, Vec<_>) = parse_files(&files).into_iter().fold(
+            (Vec::new(), Vec::new()),
+            |(mut ok, mut err), result| {
+                match result {
+                    Ok(stickers) => ok.push(stickers),
+                    Err(error) => err.push(error),
+                }
+                (ok, err)
+            },
+        );
+        let stickers: Vec<Sticker> = stickers.into_iter().flatten().collect();
+
+        let mut renames = plan(
+            &files,
+            &DescriptionIndex::new(&stickers, Similarity::Levenshtein),
+            &errors,
+            0.93,
+        );
+        renames.sort_by(|a, b| a.to.cmp(&b.to));
+        let targets: Vec<String> = renames
+            .iter()
+            .map(|rename| {
+                rename
+                    .to
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect();
+        // the soap parses as it is, it is not renamed only for looks
+        assert_eq!(
+            targets,
+            [
+                "234191_AV CLEAN CREAM_30X20_PVC_OK.cdr",
+                "234194_GEL_60X40_PVC.cdr"
+            ]
+        );
+
+        let plan_path = dir.join("rename_plan.csv");
+        write_plan(&plan_path, &renames).unwrap();
+        assert_eq!(read_plan(&plan_path).unwrap(), renames);
+
+        assert!(apply_plan(&renames, &undo_log_path(&plan_path))
+            .unwrap()
+            .is_empty());
+        assert!(renames.iter().all(|r| r.to.is_file() && !r.from.exists()));
+        // applying again fails for every file, they were renamed already
+        assert_eq!(
+            apply_plan(&renames, &dir.join("again.csv")).unwrap().len(),
+            2
+        );
+
+        // the undo log of the first apply is kept
+        assert!(apply_plan(&renames, &undo_log_path(&plan_path)).is_err());
+
+        let undo = read_plan(&undo_log_path(&plan_path)).unwrap();
+        assert!(apply_plan(&undo, &dir.join("undo_undo.csv"))
+            .unwrap()
+            .is_empty());
+        assert!(renames.iter().all(|r| r.from.is_file() && !r.to.exists()));
+    }
+}
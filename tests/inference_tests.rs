@@ -0,0 +1,148 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::stickers;
+    use order_processor::inference::{ambiguity, infer_fields, FieldSources};
+    use order_processor::parser;
+    use order_processor::runs::dedup_stickers;
+    use order_processor::similarity::{DescriptionIndex, Similarity};
+    use order_processor::structs::{
+        dimensions::Dimensions,
+        material::Material,
+        parse_stcker_error::{ErrorContext, ParseStickerError, SubParser},
+        sticker::Sticker,
+    };
+
+    fn infer(existing: &[Sticker], name: &str) -> Result<(Vec<Sticker>, Vec<String>), ()> {
+        let index = DescriptionIndex::new(existing, Similarity::Levenshtein);
+        let sources = FieldSources::new(existing, &index, 0.9);
+        let error = Sticker::parse_stickers(name).unwrap_err();
+        infer_fields(&error, &sources)
+            .map(|(stickers, fields)| {
+                let fields = fields
+                    .iter()
+                    .map(|f| format!("{} {} {}", f.field, f.value, f.source))
+                    .collect();
+                (stickers, fields)
+            })
+            .map_err(|_| ())
+    }
+
+    #[test]
+    fn test_infer_dimensions_from_same_code() {
+        let existing = stickers(&["234191_AV CLEAN CREAM_60X40_PVC_R_OK"]);
+
+        let (inferred, fields) = infer(&existing, "234191_AV CLEAN CREAM_PVC_R_OK").unwrap();
+        assert_eq!(inferred.len(), 1);
+        assert_eq!(
+            inferred[0].dimensions,
+            "60x40".parse::<Dimensions>().unwrap()
+        );
+        assert_eq!(inferred[0].material, Material::new("PVC R"));
+        assert_eq!(inferred[0].full_name, "234191_AV CLEAN CREAM_PVC_R_OK");
+        assert_eq!(inferred[0].provenance.confidence(), 1.0);
+        // the provenance is not part of the equality, so the sticker is a duplicate of the source
+        assert_eq!(inferred[0], existing[0]);
+        assert_eq!(
+            fields,
+            vec!["dimensions 60X40 234191_AV CLEAN CREAM_60X40_PVC_R_OK"]
+        );
+
+        // a size that is not official is replaced
+        let (inferred, _) = infer(&existing, "234191_AV CLEAN CREAM_60X41_PVC_R_OK").unwrap();
+        assert_eq!(
+            inferred[0].dimensions,
+            "60x40".parse::<Dimensions>().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_infer_material_from_similar_description() {
+        let existing = stickers(&["234191_AV CLEAN CREAM_60X40_PAPER"]);
+
+        let (inferred, fields) = infer(&existing, "234192_AV CLEAN CREAMS_50X50_OK").unwrap();
+        assert_eq!(inferred[0].code, "234192");
+        assert_eq!(inferred[0].material, Material::new("PAPER"));
+        assert_eq!(
+            inferred[0].dimensions,
+            "50x50".parse::<Dimensions>().unwrap()
+        );
+        assert_eq!(
+            fields,
+            vec!["material PAPER 234191_AV CLEAN CREAM_60X40_PAPER"]
+        );
+    }
+
+    #[test]
+    fn test_infer_both_fields() {
+        let existing = stickers(&["234191_AV CLEAN CREAM_60X40_PVC"]);
+
+        let (inferred, fields) = infer(&existing, "234191_AV CLEAN CREAM").unwrap();
+        assert_eq!(inferred[0].material, Material::new("PVC"));
+        assert_eq!(fields.len(), 2);
+        assert!(fields[0].starts_with(&SubParser::Dimensions.to_string()));
+    }
+
+    #[test]
+    fn test_sources_that_disagree() {
+        let existing = stickers(&[
+            "234191_AV CLEAN CREAM_60X40_PVC",
+            "234191_AV CLEAN CREAM_50X50_PVC",
+        ]);
+        assert!(infer(&existing, "234191_AV CLEAN CREAM_PVC").is_err());
+        // both files agree on the material
+        assert!(infer(&existing, "234191_AV CLEAN CREAM_60X40").is_ok());
+        // no file of the code and no similar description
+        assert!(infer(&existing, "777777_LIQUID SOAP_PVC").is_err());
+    }
+
+    #[test]
+    fn test_ambiguity() {
+        let existing = stickers(&[
+            "234191_AV CLEAN CREAM_60X40_PVC",
+            "234192_AV CLEAN CREAM_50X50_PVC",
+            "234193_AV CLEAN CREAMY_60X40_PVC",
+            "777777_LIQUID SOAP_60X40_PVC",
+        ]);
+        let index = DescriptionIndex::new(&existing, Similarity::Levenshtein);
+        let error =
+            ParseStickerError::MissingCode(ErrorContext::for_name("AV CLEAN CREAM_60X40_PVC_OK"));
+
+        let inferred = parser::infer_stickers(&error, &index, 0.9).unwrap();
+        let ambiguous = ambiguity(&error, &inferred).unwrap();
+        let candidates: Vec<(&str, bool)> = ambiguous
+            .candidates
+            .iter()
+            .map(|candidate| (candidate.value.as_str(), candidate.score == 1.0))
+            .collect();
+        // the exact descriptions first
+        assert_eq!(
+            candidates,
+            [("234191", true), ("234192", true), ("234193", false)]
+        );
+
+        let error =
+            ParseStickerError::MissingCode(ErrorContext::for_name("LIQUID SOAP_60X40_PVC_OK"));
+        let inferred = parser::infer_stickers(&error, &index, 0.9).unwrap();
+        assert!(ambiguity(&error, &inferred).is_none());
+    }
+
+    #[test]
+    fn test_dedup_keeps_parsed_sticker() {
+        let existing = stickers(&["234191_AV CLEAN CREAM_60X40_PVC_R_OK"]);
+        let (inferred, _) = infer(&existing, "234191_AV CLEAN CREAM_PVC_R_OK").unwrap();
+        let other = stickers(&["234190_SOAP_60X40_PVC_R"]);
+
+        // in whatever order inference pushed them
+        for mut all in [
+            [inferred.clone(), other.clone(), existing.clone()].concat(),
+            [existing.clone(), other.clone(), inferred.clone()].concat(),
+        ] {
+            dedup_stickers(&mut all);
+            assert_eq!(all.len(), 2);
+            assert_eq!(all[1].full_name, "234191_AV CLEAN CREAM_60X40_PVC_R_OK");
+            assert!(all[1].provenance.is_parsed());
+        }
+    }
+}
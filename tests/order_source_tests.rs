@@ -0,0 +1,351 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::TestDir;
+    use order_processor::configs::{Configs, OrderInput};
+    use order_processor::excel::{import_orders, parse_orders};
+    use order_processor::order_source::{decode, detect_delimiter, SheetSelection};
+    use order_processor::structs::order::{merge_orders, Order, OrderOrigin};
+    use order_processor::structs::rejected_row::RejectReason;
+    use order_processor::structs::variant::Variant;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn test_detect_delimiter() {
+        assert_eq!(detect_delimiter("code;amount\n123;4\n"), b';');
+        assert_eq!(detect_delimiter("code,amount\n123,4\n"), b',');
+        assert_eq!(detect_delimiter("code\tamount\n123\t4\n"), b'\t');
+        // commas in a description do not beat a semicolon on every line
+        assert_eq!(
+            detect_delimiter("code;description\n123;Cream, 50 ml\n456;Soap\n"),
+            b';'
+        );
+        assert_eq!(
+            detect_delimiter("code,description\n123,\"Cream; 50 ml\"\n"),
+            b','
+        );
+        assert_eq!(detect_delimiter("single column\n"), b',');
+    }
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(decode("Поръчка".as_bytes()), "Поръчка");
+        assert_eq!(decode(b"\xEF\xBB\xBFcode"), "code");
+        // "Поръчка;Описание" in Windows-1251
+        let (bytes, _, _) = encoding_rs::WINDOWS_1251.encode("Поръчка;Описание");
+        assert_eq!(decode(&bytes), "Поръчка;Описание");
+    }
+
+    #[test]
+    fn test_parse_csv_orders() {
+        let tmp = TestDir::new("order_source_parse_csv_orders");
+        let (csv, _, _) = encoding_rs::WINDOWS_1251
+            .encode("Заявка от 01.02\n\nБГ СТИКЕР;Описание;Поръчка\n123456;Крем, 50 мл;12\n654321;Сапун;3\n");
+        let order = tmp.write("orders.csv", &csv);
+        let config = tmp.write(
+            "config.txt",
+            format!(
+                "archive={}\norder={}\n",
+                order.parent().unwrap().display(),
+                order.display()
+            ),
+        );
+
+        let configs = Configs::load_from_file(config).unwrap();
+        let orders = parse_orders(&configs).unwrap();
+        assert_eq!(orders.len(), 2);
+        assert_eq!(orders[0].code, "123456");
+        assert_eq!(orders[0].description, "Крем, 50 мл");
+        assert_eq!(orders[0].amount, 12);
+        assert_eq!(orders[1].code, "654321");
+        assert_eq!(orders[1].amount, 3);
+        assert_eq!(orders[1].original_code, None);
+    }
+
+    #[test]
+    fn test_normalized_order_codes() {
+        let tmp = TestDir::new("order_source_normalized_order_codes");
+        let order = tmp.write(
+            "normalized_order.csv",
+            "БГ СТИКЕР;Описание;Поръчка\n234191.0;Gel;2\n 0234191 ;Gel;3\nаbc12;Soap;1\n",
+        );
+        let configs = load_configs(&order, "");
+
+        let import = import_orders(&configs).unwrap();
+        assert_eq!(import.orders.len(), 2);
+        assert_eq!(import.orders[0].code, "234191");
+        assert_eq!(import.orders[0].original_code.as_deref(), Some("234191.0"));
+        assert_eq!(import.orders[1].code, "ABC12");
+        // the same code written two ways is a duplicate row
+        assert_eq!(import.rejected.len(), 1);
+        assert_eq!(import.rejected[0].code, "0234191");
+    }
+
+    #[test]
+    fn test_order_input() {
+        let input = OrderInput::parse(" orders/*.xlsx@Week 1 | Week 2 ");
+        assert_eq!(input.pattern, PathBuf::from("orders/*.xlsx"));
+        assert_eq!(
+            input.sheets,
+            Some(SheetSelection::Named(vec![
+                "Week 1".to_string(),
+                "Week 2".to_string()
+            ]))
+        );
+        assert!(input.is_glob());
+        assert_eq!(
+            input.split_at_base_dir(),
+            (PathBuf::from("orders"), PathBuf::from("*.xlsx"))
+        );
+
+        let input = OrderInput::parse("order.xlsx@*");
+        assert_eq!(input.sheets, Some(SheetSelection::All));
+        assert!(!input.is_glob());
+        assert_eq!(
+            input.split_at_base_dir(),
+            (PathBuf::from("."), PathBuf::from("order.xlsx"))
+        );
+    }
+
+    #[test]
+    fn test_merge_orders() {
+        let order = |code: &str, description: &str, file: &str, amount: u64| Order {
+            code: code.to_string(),
+            amount,
+            description: description.to_string(),
+            variant: Variant::in_description(description),
+            original_code: None,
+            origins: vec![OrderOrigin {
+                file: file.to_string(),
+                sheet: None,
+                amount,
+            }],
+        };
+
+        let merged = merge_orders(vec![
+            order("100", "Cream", "north.csv", 5),
+            order("200", "Soap", "north.csv", 1),
+            order("100", "Cream", "south.csv", 7),
+            order("100", "Cream PROMO", "south.csv", 2),
+            order("100", "Cream", "south.csv", 1),
+        ]);
+
+        assert_eq!(merged.len(), 3);
+        assert_eq!(merged[0].code, "100");
+        assert_eq!(merged[0].amount, 13);
+        assert_eq!(merged[0].origins.len(), 2);
+        assert_eq!(merged[0].amount_from("north.csv"), Some(5));
+        assert_eq!(merged[0].amount_from("south.csv"), Some(8));
+        assert_eq!(merged[1].code, "200");
+        assert_eq!(merged[1].amount_from("south.csv"), None);
+        assert_eq!(merged[2].amount, 2);
+    }
+
+    #[test]
+    fn test_parse_globbed_orders() {
+        let tmp = TestDir::new("order_source_globbed");
+        let dir = tmp.path().join("distributors");
+        tmp.write(
+            "distributors/north.csv",
+            "БГ СТИКЕР;Описание;Поръчка\n123456;Крем;12\n654321;Сапун;3\n",
+        );
+        tmp.write(
+            "distributors/south.csv",
+            "Fr Code,Description,Order\n123456,Cream,8\n",
+        );
+        // a workbook written by an earlier run is not an order file
+        tmp.write("distributors/orders_01_02_25.xlsx", "");
+
+        let config = tmp.write(
+            "glob_config.txt",
+            format!(
+                "archive={}\norder={}\n",
+                dir.display(),
+                dir.join("*.*").display()
+            ),
+        );
+
+        let configs = Configs::load_from_file(config).unwrap();
+        let orders = parse_orders(&configs).unwrap();
+        assert_eq!(orders.len(), 2);
+        assert_eq!(orders[0].code, "123456");
+        assert_eq!(orders[0].amount, 20);
+        assert_eq!(orders[0].description, "Крем");
+        assert_eq!(orders[0].amount_from("north.csv"), Some(12));
+        assert_eq!(orders[0].amount_from("south.csv"), Some(8));
+        assert_eq!(orders[1].amount, 3);
+    }
+
+    fn wide_order_csv(tmp: &TestDir) -> PathBuf {
+        // the amount is in column L, past the columns searched by default
+        tmp.write(
+            "wide_order.csv",
+            "Week 5\n\
+             No;Code;Name;d;e;f;g;h;i;j;k;Qty\n\
+             1;123456;Cream;;;;;;;;;12\n\
+             2;654321;Soap;;;;;;;;;3\n",
+        )
+    }
+
+    /// A config next to the order file, reading it
+    fn load_configs(order: &Path, extra: &str) -> Configs {
+        let config = order.with_file_name("config.txt");
+        fs::write(
+            &config,
+            format!(
+                "archive={}\norder={}\n{extra}",
+                order.parent().unwrap().display(),
+                order.display()
+            ),
+        )
+        .unwrap();
+        Configs::load_from_file(config).unwrap()
+    }
+
+    #[test]
+    fn test_column_mapping() {
+        let tmp = TestDir::new("order_source_column_mapping");
+        let order = wide_order_csv(&tmp);
+
+        let configs = load_configs(
+            &order,
+            "header_row=2\ncode_column=B\namount_column=L\ndescription_column=Name\n",
+        );
+        let orders = parse_orders(&configs).unwrap();
+        assert_eq!(orders.len(), 2);
+        assert_eq!(orders[0].code, "123456");
+        assert_eq!(orders[0].amount, 12);
+        assert_eq!(orders[0].description, "Cream");
+
+        // the header row is found from the code header text
+        let configs = load_configs(
+            &order,
+            "code_column=Code\namount_column=qty\ndescription_column=C\n",
+        );
+        let orders = parse_orders(&configs).unwrap();
+        assert_eq!(orders[1].code, "654321");
+        assert_eq!(orders[1].amount, 3);
+    }
+
+    #[test]
+    fn test_variant_column() {
+        let tmp = TestDir::new("order_source_variant_column");
+        let order = tmp.write(
+            "variant_order.csv",
+            "БГ СТИКЕР;Описание;Type;Поръчка\n\
+             123456;Tester kit cream;;12\n\
+             123456;Tester kit cream;promo;4\n\
+             654321;Soap TESTER;;3\n",
+        );
+
+        let configs = load_configs(&order, "variant_column=Type\n");
+        let orders = parse_orders(&configs).unwrap();
+        assert_eq!(orders.len(), 3);
+        assert!(orders[0].variant.is_regular());
+        assert_eq!(orders[1].variant, Variant::new("PROMO"));
+        // the column is used instead of the description
+        assert!(orders[2].variant.is_regular());
+
+        let configs = load_configs(&order, "");
+        let orders = parse_orders(&configs).unwrap();
+        // the same line twice without the column
+        assert_eq!(orders.len(), 2);
+        assert!(orders[0].variant.is_regular());
+        assert_eq!(orders[1].variant, Variant::new("TESTER"));
+    }
+
+    #[test]
+    fn test_layout_errors() {
+        let tmp = TestDir::new("order_source_layout_errors");
+        let order = wide_order_csv(&tmp);
+
+        let configs = load_configs(&order, "code_column=Code\n");
+        let error = parse_orders(&configs).unwrap_err().to_string();
+        assert!(error.contains("no amount column"), "{error}");
+        assert!(error.contains("B: 'Code'"), "{error}");
+        assert!(error.contains("L: 'Qty'"), "{error}");
+
+        let configs = load_configs(&order, "");
+        let error = parse_orders(&configs).unwrap_err().to_string();
+        assert!(error.contains("no code header"), "{error}");
+        assert!(error.contains("A2: 'No'"), "{error}");
+
+        let configs = load_configs(&order, "header_row=2\ncode_column=B\namount_column=M\n");
+        let error = parse_orders(&configs).unwrap_err().to_string();
+        assert!(
+            error.contains("amount_column=M is outside the sheet"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn test_rejected_rows() {
+        let tmp = TestDir::new("order_source_rejected_rows");
+        let order = tmp.write(
+            "rejected_order.csv",
+            "БГ СТИКЕР;Описание;Поръчка\n\
+             100;Cream;5\n\
+             ;Soap;2\n\
+             200;Gel;abc\n\
+             300;Tonic;0\n\
+             400;Mask;-2\n\
+             500;Scrub;1,5\n\
+             100;Cream;4\n\
+             600;Oil;\n\
+             100;Cream PROMO;3\n\
+             700;Soap;7\n\
+             ;;\n\
+             ;Deliver by Friday;\n\
+             800;Not an order;1\n",
+        );
+        let configs = load_configs(&order, "");
+
+        let import = import_orders(&configs).unwrap();
+        let codes: Vec<&str> = import.orders.iter().map(|o| o.code.as_str()).collect();
+        assert_eq!(codes, vec!["100", "100", "700"]);
+
+        let reasons: Vec<(usize, RejectReason)> = import
+            .rejected
+            .iter()
+            .map(|rejected| (rejected.row, rejected.reason.clone()))
+            .collect();
+        assert_eq!(
+            reasons,
+            vec![
+                (3, RejectReason::BlankCode),
+                (4, RejectReason::InvalidAmount("abc".to_string())),
+                (5, RejectReason::ZeroAmount),
+                (6, RejectReason::NegativeAmount(-2.0)),
+                (7, RejectReason::FractionalAmount(1.5)),
+                (8, RejectReason::DuplicateCode { first_row: 2 }),
+                (9, RejectReason::MissingAmount),
+            ]
+        );
+        assert_eq!(import.rejected[0].source, "rejected_order.csv");
+        assert_eq!(import.rejected[0].description, "Soap");
+    }
+
+    #[test]
+    fn test_totals_row_ends_table() {
+        let tmp = TestDir::new("order_source_totals_row_ends_table");
+        let order = tmp.write(
+            "totals_order.csv",
+            "БГ СТИКЕР;Описание;Поръчка\n\
+             ;;\n\
+             100;Cream;5\n\
+             200;Gel;7\n\
+             ;ОБЩО;12\n\
+             ;;\n\
+             Total;;12\n",
+        );
+        let configs = load_configs(&order, "");
+
+        let import = import_orders(&configs).unwrap();
+        let codes: Vec<&str> = import.orders.iter().map(|o| o.code.as_str()).collect();
+        assert_eq!(codes, vec!["100", "200"]);
+        // the sums under the orders are not rejected rows
+        assert!(import.rejected.is_empty());
+    }
+}
@@ -0,0 +1,150 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::TestDir;
+    use order_processor::resolve::{candidates, Decision, DecisionFiles, Resolver};
+    use order_processor::structs::code_aliases::CodeAliases;
+    use order_processor::structs::order::Order;
+    use order_processor::structs::parse_stcker_error::ParseStickerError;
+    use order_processor::structs::sticker::Sticker;
+    use order_processor::structs::sticker_overrides::{OverrideTarget, StickerOverrides};
+    use order_processor::structs::variant::Variant;
+    use std::collections::HashMap;
+    use std::path::Path;
+
+    fn order(code: &str, description: &str) -> Order {
+        Order {
+            code: code.to_string(),
+            amount: 1,
+            description: description.to_string(),
+            variant: Variant::in_description(description),
+            original_code: None,
+            origins: Vec::new(),
+        }
+    }
+
+    fn error(path: &str) -> ParseStickerError {
+        let path = Path::new(path);
+        let name = path.file_stem().unwrap().to_string_lossy();
+        Sticker::parse_stickers(&name).unwrap_err().with_path(path)
+    }
+
+    #[test]
+    fn test_candidates() {
+        let errors = [
+            error("/archive/BG SOAP LABEL_60X40_PVC.cdr"),
+            error("/archive/BG 23419_AV CLEAN CREAM_60X40_PVC.cdr"),
+            error("/archive/234191_NO SIZE_PVC.cdr"),
+        ];
+        let found = candidates(&order("234191", "AV CLEAN CREAM"), &errors, 0.5);
+        let names: Vec<String> = found.iter().map(|c| c.error.get_description()).collect();
+        // the file with the code is offered even though its name is not similar
+        assert_eq!(
+            names,
+            ["BG 23419_AV CLEAN CREAM_60X40_PVC", "234191_NO SIZE_PVC"]
+        );
+        assert!(found[0].similarity > found[1].similarity);
+    }
+
+    #[test]
+    fn test_resolve_saves_decisions() {
+        let tmp = TestDir::new("resolve_saves_decisions");
+        let files = DecisionFiles {
+            overrides: tmp.path().join("overrides.txt"),
+            code_aliases: tmp.write("code_aliases.txt", "# renamed codes"),
+        };
+
+        let errors = [
+            error("/archive/old/BG 23419_AV CLEAN CREAM_60X40_PVC_RED.cdr"),
+            error("/archive/234192_SOAP LABEL_PVC.cdr"),
+        ];
+        let missing = [
+            order("234191", "AV CLEAN CREAM"),
+            order("234192", "Soap label"),
+            order("555", "GEL"),
+            order("556", "GEL 2"),
+        ];
+        let mut code_to_stickers: HashMap<String, Vec<Sticker>> = HashMap::new();
+        code_to_stickers.insert(
+            "100".into(),
+            Sticker::parse_stickers("100_GEL_60X40_PVC_OK").unwrap(),
+        );
+
+        // the cream file names everything, the soap file has no size, 999 has no stickers
+        let input = "1\n1\n50x30\nc 999\nc 100\nq\n";
+        let mut output = Vec::new();
+        let decisions = Resolver::new(input.as_bytes(), &mut output, Path::new("/archive"), 0.5)
+            .resolve(
+                &missing,
+                &errors,
+                &code_to_stickers,
+                &CodeAliases::default(),
+                &files,
+            )
+            .unwrap();
+        assert_eq!(decisions.len(), 3);
+        assert!(String::from_utf8_lossy(&output).contains("999 has no stickers in the archive"));
+
+        let overrides = StickerOverrides::load(&files.overrides).unwrap();
+        let saved: Vec<_> = overrides.iter().collect();
+        assert_eq!(
+            saved[0].target,
+            OverrideTarget::Path("old/BG 23419_AV CLEAN CREAM_60X40_PVC_RED.cdr".into())
+        );
+        assert_eq!(saved[0].code, "234191");
+        assert_eq!(saved[0].color.name(), "Red");
+        assert_eq!(saved[1].description, "SOAP LABEL");
+        assert_eq!(saved[1].dimensions[0].to_string(), "50x30");
+
+        let aliases = CodeAliases::load(&files.code_aliases).unwrap();
+        assert_eq!(aliases.get("100"), Some("555"));
+        assert_eq!(
+            decisions[2],
+            Decision::Alias {
+                old: "100".into(),
+                new: "555".into()
+            }
+        );
+    }
+
+    #[test]
+    fn test_resolve_description_with_separator() {
+        let tmp = TestDir::new("resolve_description_with_separator");
+        let files = DecisionFiles {
+            overrides: tmp.write(
+                "overrides.txt",
+                "SOAP_60X40_PVC; code=1; description=SOAP; dimensions=60x40; material=PVC\n",
+            ),
+            code_aliases: tmp.path().join("code_aliases.txt"),
+        };
+
+        let errors = [error("/archive/234191_CREAM \"MINI\"_PVC.cdr")];
+        let missing = [order("234191", "Cream \"mini\"; 50ml")];
+        let mut output = Vec::new();
+        Resolver::new(
+            "1\n60x40\n".as_bytes(),
+            &mut output,
+            Path::new("/archive"),
+            0.5,
+        )
+        .resolve(
+            &missing,
+            &errors,
+            &HashMap::new(),
+            &CodeAliases::default(),
+            &files,
+        )
+        .unwrap();
+
+        // the earlier override is still read and the description keeps its separator
+        let overrides = StickerOverrides::load(&files.overrides).unwrap();
+        let saved: Vec<_> = overrides.iter().collect();
+        assert_eq!(saved.len(), 2);
+        assert_eq!(saved[1].description, "CREAM \"MINI\"; 50ML");
+        assert_eq!(
+            saved[1].target,
+            OverrideTarget::Path("234191_CREAM \"MINI\"_PVC.cdr".into())
+        );
+    }
+}
@@ -0,0 +1,112 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::TestDir;
+    use order_processor::configs::Configs;
+    use order_processor::run_report::SCHEMA_VERSION;
+    use order_processor::runs::Pipeline;
+    use serde_json::Value;
+    use std::fs;
+
+    #[test]
+    fn test_run_report() {
+        let tmp = TestDir::new("run_report");
+        let archive = tmp.path().join("archive");
+        for name in [
+            "234191_AV CLEAN CREAM_60X40_PVC_R_OK",
+            "234191_AV CLEAN CREAM_PVC_R_OK",
+            "AV CLEAN CREAM_60X40_PVC_R",
+            "234192_SOAP_PVC_R",
+        ] {
+            tmp.write(&format!("archive/{name}.cdr"), "");
+        }
+        let orders = tmp.write(
+            "orders.csv",
+            "БГ СТИКЕР;Описание;Поръчка\n234191;Cream;5\n999999;Shampoo;2\n",
+        );
+        let config = tmp.write(
+            "config.txt",
+            format!(
+                "archive={}\norder={}\n",
+                archive.display(),
+                orders.display()
+            ),
+        );
+        let report_path = tmp.path().join("report.json");
+
+        let mut configs = Configs::load_from_file(&config).unwrap();
+        configs.archive_index_path = None;
+        Pipeline::parse_only()
+            .inference(true)
+            .run_reporting(&configs, Some(&report_path));
+        let report: Value =
+            serde_json::from_str(&fs::read_to_string(&report_path).unwrap()).unwrap();
+
+        assert_eq!(report["schema_version"], SCHEMA_VERSION);
+        assert_eq!(report["config"]["archive"], archive.display().to_string());
+        assert_eq!(report["stickers"]["parsed"], 1);
+        // the file with its size inferred is a duplicate of the parsed one
+        assert_eq!(report["stickers"]["inferred"], 1);
+
+        // the soap has no size and no other file to take one from
+        let errors = report["errors"].as_object().unwrap();
+        assert_eq!(errors.len(), 1);
+        let failed = errors.values().next().unwrap().as_array().unwrap();
+        assert_eq!(failed[0]["name"], "234192_SOAP_PVC_R");
+        assert!(failed[0]["path"].as_str().unwrap().ends_with(".cdr"));
+
+        let inferred = report["inferred"].as_array().unwrap();
+        assert!(inferred
+            .iter()
+            .all(|sticker| sticker["code"] == "234191" && sticker["confidence"].is_number()));
+
+        assert_eq!(report["orders"]["matched"][0]["code"], "234191");
+        assert_eq!(report["orders"]["missing"][0]["code"], "999999");
+        assert_eq!(report["orders"]["error"], Value::Null);
+        let materials = report["materials"].as_array().unwrap();
+        assert_eq!(materials.len(), 1);
+        assert_eq!(materials[0]["dimensions"], "60x40");
+        // five of each of the two stickers of the code
+        assert_eq!(report["orders"]["matched"][0]["stickers"], 2);
+        assert_eq!(materials[0]["amount"], 10);
+        assert!(report["timings"]["total_ms"].is_u64());
+    }
+
+    #[test]
+    fn test_run_report_excludes_non_ok_stickers() {
+        let tmp = TestDir::new("run_report_excludes_non_ok_stickers");
+        let archive = tmp.path().join("archive");
+        for name in [
+            "234191_AV CLEAN CREAM_60X40_PVC_R_OK",
+            "234192_SOAP_50X50_PVC_R",
+        ] {
+            tmp.write(&format!("archive/{name}.cdr"), "");
+        }
+        let orders = tmp.write(
+            "orders.csv",
+            "БГ СТИКЕР;Описание;Поръчка\n234191;Cream;5\n234192;Soap;2\n",
+        );
+        let config = tmp.write(
+            "config.txt",
+            format!(
+                "archive={}\norder={}\nnon_ok_stickers=exclude\narchive_index=off\n",
+                archive.display(),
+                orders.display()
+            ),
+        );
+        let report_path = tmp.path().join("report.json");
+
+        let configs = Configs::load_from_file(&config).unwrap();
+        Pipeline::parse_only().run_reporting(&configs, Some(&report_path));
+        let report: Value =
+            serde_json::from_str(&fs::read_to_string(&report_path).unwrap()).unwrap();
+
+        // the soap is not marked OK, no material is counted for its order
+        assert_eq!(report["orders"]["missing"][0]["code"], "234192");
+        let materials = report["materials"].as_array().unwrap();
+        assert_eq!(materials.len(), 1);
+        assert_eq!(materials[0]["dimensions"], "60x40");
+        assert_eq!(materials[0]["amount"], 5);
+    }
+}
@@ -0,0 +1,94 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::stickers;
+    use clap::ValueEnum;
+    use order_processor::parser;
+    use order_processor::similarity::{
+        token_set_ratio, trigram_similarity, DescriptionIndex, Similarity,
+    };
+    use order_processor::structs::parse_stcker_error::{
+        ErrorContext, ParseStickerError, SubParser,
+    };
+    use order_processor::structs::provenance::Provenance;
+
+    #[test]
+    fn test_scores() {
+        assert_eq!(
+            token_set_ratio("AV CLEAN GEL TUBE", "GEL TUBE AV CLEAN"),
+            1.0
+        );
+        assert_eq!(
+            trigram_similarity("AV CLEAN GEL TUBE", "gel tube av clean"),
+            1.0
+        );
+        assert_eq!(trigram_similarity("GEL", "SOAP"), 0.0);
+        assert!(token_set_ratio("AV CLEAN GEL TUBE", "AV CLEAN GEL TUBE 200ML") > 0.9);
+        assert!(Similarity::Levenshtein.score("AV CLEAN GEL TUBE", "GEL TUBE AV CLEAN") < 0.5);
+        assert_eq!(
+            <Similarity as ValueEnum>::from_str("token_set", true),
+            Ok(Similarity::TokenSet)
+        );
+        assert_eq!(Similarity::JaroWinkler.to_string(), "jaro_winkler");
+        assert!(<Similarity as ValueEnum>::from_str("cosine", true).is_err());
+    }
+
+    #[test]
+    fn test_infer_reordered_words() {
+        let existing = stickers(&["234191_AV CLEAN GEL TUBE_50X50_PVC_R_OK"]);
+        let error = ParseStickerError::MissingCode(ErrorContext::for_name(
+            "GEL TUBE AV CLEAN_60X40_PVC_R_OK",
+        ));
+
+        for (similarity, threshold) in [(Similarity::TokenSet, 0.9), (Similarity::Trigram, 0.7)] {
+            let index = DescriptionIndex::new(&existing, similarity);
+            let inferred = parser::infer_stickers(&error, &index, threshold).unwrap();
+            assert_eq!(inferred[0].code, "234191", "{similarity}");
+            let Provenance::Inferred(fields) = &inferred[0].provenance else {
+                panic!("{similarity}: {:?}", inferred[0].provenance);
+            };
+            assert_eq!(fields[0].field, SubParser::Code);
+            assert_eq!(fields[0].source, "234191_AV CLEAN GEL TUBE_50X50_PVC_R_OK");
+            assert!(fields[0].score >= threshold);
+        }
+        let index = DescriptionIndex::new(&existing, Similarity::Levenshtein);
+        assert!(parser::infer_stickers(&error, &index, 0.93).is_err());
+    }
+
+    #[test]
+    fn test_index_matches_brute_force() {
+        let existing = stickers(&[
+            "234191_AV CLEAN GEL TUBE_50X50_PVC_R_OK",
+            "234192_AV CLEAN GEL TUBE_60X40_PVC_R_OK",
+            "235354_AV CLEAN CREAM_60X40_PVC_R_OK",
+            "237355_LIQUID SOAP LEMON_60X40_PVC_R_OK",
+            "238309_GEL TUBE_60X40_PVC_R_OK",
+        ]);
+        let query = "AV CLEAN GEL TUB";
+
+        for similarity in [
+            Similarity::Levenshtein,
+            Similarity::TokenSet,
+            Similarity::JaroWinkler,
+            Similarity::Trigram,
+        ] {
+            let index = DescriptionIndex::new(&existing, similarity);
+            // the shared description is kept once with both of its codes
+            assert_eq!(index.len(), 4);
+
+            let mut expected: Vec<(&str, f64)> = existing
+                .iter()
+                .map(|sticker| sticker.description.as_str())
+                .map(|description| (description, similarity.score(query, description)))
+                .filter(|(_, score)| *score >= 0.5)
+                .collect();
+            expected.sort_by(|a, b| b.1.total_cmp(&a.1));
+            expected.dedup();
+            assert_eq!(index.search(query, 0.5), expected, "{similarity}");
+        }
+
+        let index = DescriptionIndex::new(&existing, Similarity::Trigram);
+        assert_eq!(index.similar_codes(query, 0.8), vec!["234191", "234192"]);
+    }
+}
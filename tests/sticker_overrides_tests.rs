@@ -0,0 +1,100 @@
+#[cfg(test)]
+mod tests {
+    use order_processor::structs::archive_file::ArchiveFile;
+    use order_processor::structs::provenance::Provenance;
+    use order_processor::structs::sticker_overrides::{
+        OverrideTarget, OverridesError, StickerOverrides,
+    };
+    use std::path::PathBuf;
+
+    fn archive_file(path: &str) -> ArchiveFile {
+        let path = PathBuf::from(path);
+        ArchiveFile {
+            name: ArchiveFile::name_from_stem(&path.file_stem().unwrap().to_string_lossy()),
+            path,
+            size: 0,
+            modified: None,
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let overrides = StickerOverrides::parse(
+            "# old files without sizes\n\
+             old/cream.cdr; code=0234191; description=Cream; dimensions=60x40, 30x20; material=pvc\n\
+             gel label; code=100; description=GEL; dimensions=100X50; material=PAPER; color=red\n",
+        );
+        assert_eq!(overrides.len(), 2);
+        assert!(overrides.malformed().is_empty());
+
+        let cream = overrides.iter().next().unwrap();
+        assert_eq!(cream.target, OverrideTarget::Path("old/cream.cdr".into()));
+        assert_eq!(cream.code, "234191");
+        assert_eq!(cream.description, "CREAM");
+        assert_eq!(cream.dimensions.len(), 2);
+        assert_eq!(cream.material.name(), "PVC");
+        assert_eq!(cream.color.name(), "Black");
+
+        let gel = overrides.iter().nth(1).unwrap();
+        assert_eq!(gel.target, OverrideTarget::Stem("GEL LABEL".into()));
+        assert_eq!(gel.color.name(), "Red");
+
+        // a stem as it is on disk matches the file name it is parsed from
+        let spaced = StickerOverrides::parse(
+            "Gel label _ok; code=100; description=GEL; dimensions=100X50; material=PAPER\n",
+        );
+        let target = &spaced.iter().next().unwrap().target;
+        assert_eq!(*target, OverrideTarget::Stem("GEL LABEL_OK".into()));
+        assert!(target.matches(&archive_file("archive/Gel label _ok.cdr")));
+
+        // the bad lines are left out, the first line of a target and the valid ones are used
+        let overrides = StickerOverrides::parse(
+            "a; code=1; description=A; dimensions=60x40; material=PVC\n\
+             A; code=2; description=B; dimensions=60x40; material=PVC\n\
+             b; code=1; description=A; dimensions=60x40\n\
+             c; code=1; description=A; dimensions=60x40; material=WOOD\n\
+             d; code=1; size=60x40\n\
+             e; code=5; description=E; dimensions=60x40; material=PVC",
+        );
+        let numbers: Vec<usize> = overrides
+            .malformed()
+            .iter()
+            .map(|(line, _)| *line)
+            .collect();
+        assert_eq!(numbers, [2, 3, 4, 5]);
+        let codes: Vec<&str> = overrides.iter().map(|o| o.code.as_str()).collect();
+        assert_eq!(codes, ["1", "5"]);
+        assert!(matches!(
+            overrides.check(),
+            Err(OverridesError::MalformedLines(lines)) if lines.len() == 4
+        ));
+    }
+
+    #[test]
+    fn test_apply() {
+        let overrides = StickerOverrides::parse(
+            "old/CREAM.cdr; code=1; description=CREAM; dimensions=60x40, 30x20; material=PVC\n\
+             gel_ok; code=2; description=GEL; dimensions=100x50; material=PVC\n\
+             deleted; code=3; description=GONE; dimensions=100x50; material=PVC\n",
+        );
+        let applied = overrides.apply(vec![
+            archive_file("archive/old/CREAM.cdr"),
+            archive_file("archive/new/CREAM.cdr"),
+            archive_file("archive/GEL_OK.cdr"),
+        ]);
+
+        assert_eq!(applied.stickers.len(), 3);
+        assert!(applied.stickers[..2]
+            .iter()
+            .all(|sticker| sticker.code == "1"));
+        // the status still comes from the file name
+        assert!(applied.stickers[2].status.is_ok());
+        assert_eq!(
+            applied.stickers[2].provenance,
+            Provenance::Override { line: 2 }
+        );
+        assert_eq!(applied.remaining, [archive_file("archive/new/CREAM.cdr")]);
+        assert_eq!(applied.stale.len(), 1);
+        assert_eq!(applied.stale[0].target.to_string(), "DELETED");
+    }
+}
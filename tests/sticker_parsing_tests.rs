@@ -3,8 +3,14 @@ mod tests {
     use order_processor::{
         parser,
         structs::{
-            color::Color, dimensions::Dimensions, material::Material,
-            parse_stcker_error::ParseStickerError, sticker::Sticker,
+            color::Color,
+            dimensions::Dimensions,
+            material::Material,
+            order::Order,
+            parse_stcker_error::{ErrorContext, ParseStickerError, SubParser},
+            status::{StatusFlag, StickerStatus},
+            sticker::Sticker,
+            variant::Variant,
         },
     };
 
@@ -14,8 +20,8 @@ mod tests {
             .unwrap();
         assert_eq!(s[0].code, "7099");
         assert_eq!(s[0].dimensions, "60x40".parse::<Dimensions>().unwrap());
-        assert_eq!(s[0].material, Material::PVCR);
-        assert_eq!(s[0].text_color, Color::Black);
+        assert_eq!(s[0].material, Material::new("PVC R"));
+        assert_eq!(s[0].text_color, Color::new("Black"));
     }
 
     #[test]
@@ -23,8 +29,8 @@ mod tests {
         let s = Sticker::parse_stickers("7129_LRS_НЕЖЕН ИЗМИВАЩ ГЕЛ БЕБЕ 80X55_PVC_R").unwrap();
         assert_eq!(s[0].code, "7129");
         assert_eq!(s[0].dimensions, "80x55".parse::<Dimensions>().unwrap());
-        assert_eq!(s[0].material, Material::PVCR);
-        assert_eq!(s[0].text_color, Color::Black);
+        assert_eq!(s[0].material, Material::new("PVC R"));
+        assert_eq!(s[0].text_color, Color::new("Black"));
     }
 
     #[test]
@@ -32,8 +38,8 @@ mod tests {
         let s = Sticker::parse_stickers("7136_LRS_ХИДРАТИРАЩ КРЕМ БЕБЕ_58X43_PVC_R").unwrap();
         assert_eq!(s[0].code, "7136");
         assert_eq!(s[0].dimensions, "58x43".parse::<Dimensions>().unwrap());
-        assert_eq!(s[0].material, Material::PVCR);
-        assert_eq!(s[0].text_color, Color::Black);
+        assert_eq!(s[0].material, Material::new("PVC R"));
+        assert_eq!(s[0].text_color, Color::new("Black"));
     }
 
     #[test]
@@ -41,8 +47,8 @@ mod tests {
         let s = Sticker::parse_stickers("7143_LRS_ПОЧИСТВАЩО МЛЯКО БЕБЕ_60X110_PVC_R").unwrap();
         assert_eq!(s[0].code, "7143");
         assert_eq!(s[0].dimensions, "60x110".parse::<Dimensions>().unwrap());
-        assert_eq!(s[0].material, Material::PVCR);
-        assert_eq!(s[0].text_color, Color::Black);
+        assert_eq!(s[0].material, Material::new("PVC R"));
+        assert_eq!(s[0].text_color, Color::new("Black"));
     }
 
     #[test]
@@ -52,8 +58,13 @@ mod tests {
                 .unwrap();
         assert_eq!(s[0].dimensions, "50x30".parse::<Dimensions>().unwrap());
         assert_eq!(s[0].code, "7303");
-        assert_eq!(s[0].material, Material::PVCR);
-        assert_eq!(s[0].text_color, Color::Black);
+        assert_eq!(s[0].material, Material::new("PVC R"));
+        assert_eq!(s[0].text_color, Color::new("Black"));
+        assert_eq!(
+            s[0].status,
+            StickerStatus::default().with(StatusFlag::TempSize)
+        );
+        assert!(!s[0].status.is_ok());
     }
 
     #[test]
@@ -62,8 +73,8 @@ mod tests {
             .unwrap();
         assert_eq!(s[0].dimensions, "80x55".parse::<Dimensions>().unwrap());
         assert_eq!(s[0].code, "7396");
-        assert_eq!(s[0].material, Material::PVCR);
-        assert_eq!(s[0].text_color, Color::Black);
+        assert_eq!(s[0].material, Material::new("PVC R"));
+        assert_eq!(s[0].text_color, Color::new("Black"));
     }
 
     #[test]
@@ -71,8 +82,8 @@ mod tests {
         let s = Sticker::parse_stickers("7624_LRS_ПОЧИСТВАЩА ВОДА БЕБЕ_60X110_PVC_R").unwrap();
         assert_eq!(s[0].dimensions, "60x110".parse::<Dimensions>().unwrap());
         assert_eq!(s[0].code, "7624");
-        assert_eq!(s[0].material, Material::PVCR);
-        assert_eq!(s[0].text_color, Color::Black);
+        assert_eq!(s[0].material, Material::new("PVC R"));
+        assert_eq!(s[0].text_color, Color::new("Black"));
     }
 
     #[test]
@@ -80,8 +91,8 @@ mod tests {
         let s = Sticker::parse_stickers("7631_LRS_БЕБЕШКА ПАСТА ЗА ЗЪБИ_50X30_PVC_R").unwrap();
         assert_eq!(s[0].dimensions, "50x30".parse::<Dimensions>().unwrap());
         assert_eq!(s[0].code, "7631");
-        assert_eq!(s[0].material, Material::PVCR);
-        assert_eq!(s[0].text_color, Color::Black);
+        assert_eq!(s[0].material, Material::new("PVC R"));
+        assert_eq!(s[0].text_color, Color::new("Black"));
     }
 
     #[test]
@@ -89,8 +100,8 @@ mod tests {
         let s = Sticker::parse_stickers("7860_LR_BOX BEBE_80X55_PVC_R").unwrap();
         assert_eq!(s[0].dimensions, "80x55".parse::<Dimensions>().unwrap());
         assert_eq!(s[0].code, "7860");
-        assert_eq!(s[0].material, Material::PVCR);
-        assert_eq!(s[0].text_color, Color::Black);
+        assert_eq!(s[0].material, Material::new("PVC R"));
+        assert_eq!(s[0].text_color, Color::new("Black"));
     }
 
     #[test]
@@ -102,8 +113,12 @@ mod tests {
         assert_eq!(s[0].code, "205475");
         assert_eq!(s[0].dimensions, "58x75".parse::<Dimensions>().unwrap(),);
         assert_eq!(s[1].dimensions, "36x73".parse::<Dimensions>().unwrap(),);
-        assert_eq!(s[0].material, Material::Paper);
-        assert_eq!(s[0].text_color, Color::Green);
+        assert_eq!(s[0].material, Material::new("PAPER"));
+        assert_eq!(s[0].text_color, Color::new("Green"));
+        assert_eq!(
+            s[1].status,
+            StickerStatus::from_iter([StatusFlag::DoubleSticker, StatusFlag::Approved])
+        );
     }
 
     #[test]
@@ -112,8 +127,8 @@ mod tests {
             Sticker::parse_stickers("234191_AV CLEAN GEL TUBE 200ML_50X50_PVC_R_OK_PF").unwrap();
         assert_eq!(s[0].code, "234191");
         assert_eq!(s[0].dimensions, "50x50".parse::<Dimensions>().unwrap());
-        assert_eq!(s[0].material, Material::PVCR);
-        assert_eq!(s[0].text_color, Color::Black);
+        assert_eq!(s[0].material, Material::new("PVC R"));
+        assert_eq!(s[0].text_color, Color::new("Black"));
     }
 
     #[test]
@@ -122,8 +137,8 @@ mod tests {
             .unwrap();
         assert_eq!(s[0].code, "235354");
         assert_eq!(s[0].dimensions, "45x101".parse::<Dimensions>().unwrap());
-        assert_eq!(s[0].material, Material::PVCRSLV);
-        assert_eq!(s[0].text_color, Color::Black);
+        assert_eq!(s[0].material, Material::new("PVC R SLV"));
+        assert_eq!(s[0].text_color, Color::new("Black"));
     }
 
     #[test]
@@ -132,8 +147,8 @@ mod tests {
             .unwrap();
         assert_eq!(s[0].code, "237355");
         assert_eq!(s[0].dimensions, "100x40".parse::<Dimensions>().unwrap());
-        assert_eq!(s[0].material, Material::PVC);
-        assert_eq!(s[0].text_color, Color::Black);
+        assert_eq!(s[0].material, Material::new("PVC"));
+        assert_eq!(s[0].text_color, Color::new("Black"));
     }
 
     #[test]
@@ -141,8 +156,8 @@ mod tests {
         let s = Sticker::parse_stickers("238309_AV TOL LOT 200ML_40X60_PVC_OK_PF").unwrap();
         assert_eq!(s[0].code, "238309");
         assert_eq!(s[0].dimensions, "40x60".parse::<Dimensions>().unwrap());
-        assert_eq!(s[0].material, Material::PVC);
-        assert_eq!(s[0].text_color, Color::Black);
+        assert_eq!(s[0].material, Material::new("PVC"));
+        assert_eq!(s[0].text_color, Color::new("Black"));
     }
 
     #[test]
@@ -152,8 +167,8 @@ mod tests {
                 .unwrap();
         assert_eq!(s[0].dimensions, "45x101".parse::<Dimensions>().unwrap());
         assert_eq!(s[0].code, "239680");
-        assert_eq!(s[0].material, Material::PVC);
-        assert_eq!(s[0].text_color, Color::Black);
+        assert_eq!(s[0].material, Material::new("PVC"));
+        assert_eq!(s[0].text_color, Color::new("Black"));
     }
 
     #[test]
@@ -163,8 +178,8 @@ mod tests {
                 .unwrap();
         assert_eq!(s[0].dimensions, "60x40".parse::<Dimensions>().unwrap());
         assert_eq!(s[0].code, "240198");
-        assert_eq!(s[0].material, Material::PVCR);
-        assert_eq!(s[0].text_color, Color::Black);
+        assert_eq!(s[0].material, Material::new("PVC R"));
+        assert_eq!(s[0].text_color, Color::new("Black"));
     }
 
     #[test]
@@ -173,8 +188,8 @@ mod tests {
             .unwrap();
         assert_eq!(s[0].code, "241438");
         assert_eq!(s[0].dimensions, "50x100".parse::<Dimensions>().unwrap());
-        assert_eq!(s[0].material, Material::Paper);
-        assert_eq!(s[0].text_color, Color::Blue);
+        assert_eq!(s[0].material, Material::new("PAPER"));
+        assert_eq!(s[0].text_color, Color::new("Blue"));
     }
 
     #[test]
@@ -182,8 +197,8 @@ mod tests {
         let s = Sticker::parse_stickers("247109_KL SHP GALANGA 200ML_60X40_PVC_R_OK_PF").unwrap();
         assert_eq!(s[0].dimensions, "60x40".parse::<Dimensions>().unwrap());
         assert_eq!(s[0].code, "247109");
-        assert_eq!(s[0].material, Material::PVCR);
-        assert_eq!(s[0].text_color, Color::Black);
+        assert_eq!(s[0].material, Material::new("PVC R"));
+        assert_eq!(s[0].text_color, Color::new("Black"));
     }
 
     #[test]
@@ -194,8 +209,8 @@ mod tests {
         .unwrap();
         assert_eq!(s[0].dimensions, "40x90".parse::<Dimensions>().unwrap());
         assert_eq!(s[0].code, "253831");
-        assert_eq!(s[0].material, Material::PaperGR);
-        assert_eq!(s[0].text_color, Color::Black);
+        assert_eq!(s[0].material, Material::new("PAPER GR"));
+        assert_eq!(s[0].text_color, Color::new("Black"));
     }
 
     #[test]
@@ -203,8 +218,8 @@ mod tests {
         let s = Sticker::parse_stickers("259064_KL SHP PIVOINE 200ML_60X40_PVC_R_OK").unwrap();
         assert_eq!(s[0].code, "259064");
         assert_eq!(s[0].dimensions, "60x40".parse::<Dimensions>().unwrap());
-        assert_eq!(s[0].material, Material::PVCR);
-        assert_eq!(s[0].text_color, Color::Black);
+        assert_eq!(s[0].material, Material::new("PVC R"));
+        assert_eq!(s[0].text_color, Color::new("Black"));
     }
 
     #[test]
@@ -213,8 +228,8 @@ mod tests {
             .unwrap();
         assert_eq!(s[0].code, "259839");
         assert_eq!(s[0].dimensions, "40x100".parse::<Dimensions>().unwrap());
-        assert_eq!(s[0].material, Material::Paper);
-        assert_eq!(s[0].text_color, Color::Black);
+        assert_eq!(s[0].material, Material::new("PAPER"));
+        assert_eq!(s[0].text_color, Color::new("Black"));
     }
 
     #[test]
@@ -223,8 +238,8 @@ mod tests {
             .unwrap();
         assert_eq!(s[0].code, "261776");
         assert_eq!(s[0].dimensions, "50x22".parse::<Dimensions>().unwrap());
-        assert_eq!(s[0].material, Material::PVC);
-        assert_eq!(s[0].text_color, Color::Black);
+        assert_eq!(s[0].material, Material::new("PVC"));
+        assert_eq!(s[0].text_color, Color::new("Black"));
     }
 
     #[test]
@@ -233,8 +248,8 @@ mod tests {
             .unwrap();
         assert_eq!(s[0].code, "261783");
         assert_eq!(s[0].dimensions, "40x45".parse::<Dimensions>().unwrap());
-        assert_eq!(s[0].material, Material::PVCR);
-        assert_eq!(s[0].text_color, Color::Black);
+        assert_eq!(s[0].material, Material::new("PVC R"));
+        assert_eq!(s[0].text_color, Color::new("Black"));
     }
 
     #[test]
@@ -242,8 +257,8 @@ mod tests {
         let s = Sticker::parse_stickers("261788_AV VITAMIN ACTIV CG SERUM_27X40_PVC_PF").unwrap();
         assert_eq!(s[0].code, "261788");
         assert_eq!(s[0].dimensions, "27x40".parse::<Dimensions>().unwrap());
-        assert_eq!(s[0].material, Material::PVC);
-        assert_eq!(s[0].text_color, Color::Black);
+        assert_eq!(s[0].material, Material::new("PVC"));
+        assert_eq!(s[0].text_color, Color::new("Black"));
     }
 
     #[test]
@@ -253,8 +268,8 @@ mod tests {
                 .unwrap();
         assert_eq!(s[0].code, "263673");
         assert_eq!(s[0].dimensions, "40x20".parse::<Dimensions>().unwrap());
-        assert_eq!(s[0].material, Material::PVCR);
-        assert_eq!(s[0].text_color, Color::Black);
+        assert_eq!(s[0].material, Material::new("PVC R"));
+        assert_eq!(s[0].text_color, Color::new("Black"));
     }
 
     #[test]
@@ -265,8 +280,8 @@ mod tests {
         .unwrap();
         assert_eq!(s[0].dimensions, "121x27".parse::<Dimensions>().unwrap());
         assert_eq!(s[0].code, "263673");
-        assert_eq!(s[0].material, Material::Paper);
-        assert_eq!(s[0].text_color, Color::Blue);
+        assert_eq!(s[0].material, Material::new("PAPER"));
+        assert_eq!(s[0].text_color, Color::new("Blue"));
     }
 
     #[test]
@@ -274,8 +289,8 @@ mod tests {
         let s = Sticker::parse_stickers("267995_AV SOL SPRAY 50 200ML_50X50_PVC_R_OK").unwrap();
         assert_eq!(s[0].code, "267995");
         assert_eq!(s[0].dimensions, "50x50".parse::<Dimensions>().unwrap());
-        assert_eq!(s[0].material, Material::PVCR);
-        assert_eq!(s[0].text_color, Color::Black);
+        assert_eq!(s[0].material, Material::new("PVC R"));
+        assert_eq!(s[0].text_color, Color::new("Black"));
     }
 
     #[test]
@@ -283,8 +298,8 @@ mod tests {
         let s = Sticker::parse_stickers("270402_RF VOLUMEA SHP 200ML_100X40_PAPER GREEN").unwrap();
         assert_eq!(s[0].code, "270402");
         assert_eq!(s[0].dimensions, "100x40".parse::<Dimensions>().unwrap());
-        assert_eq!(s[0].material, Material::Paper);
-        assert_eq!(s[0].text_color, Color::Green);
+        assert_eq!(s[0].material, Material::new("PAPER"));
+        assert_eq!(s[0].text_color, Color::new("Green"));
     }
 
     #[test]
@@ -292,8 +307,8 @@ mod tests {
         let s = Sticker::parse_stickers("270983_KL SHP MENTHE 200ML_60X40_PVC_R").unwrap();
         assert_eq!(s[0].code, "270983");
         assert_eq!(s[0].dimensions, "60x40".parse::<Dimensions>().unwrap());
-        assert_eq!(s[0].material, Material::PVCR);
-        assert_eq!(s[0].text_color, Color::Black);
+        assert_eq!(s[0].material, Material::new("PVC R"));
+        assert_eq!(s[0].text_color, Color::new("Black"));
     }
 
     #[test]
@@ -301,8 +316,8 @@ mod tests {
         let s = Sticker::parse_stickers("273656_DU MELAS FL INVISIBLE 30ML_40X60_PVC").unwrap();
         assert_eq!(s[0].code, "273656");
         assert_eq!(s[0].dimensions, "40x60".parse::<Dimensions>().unwrap());
-        assert_eq!(s[0].material, Material::PVC);
-        assert_eq!(s[0].text_color, Color::Black);
+        assert_eq!(s[0].material, Material::new("PVC"));
+        assert_eq!(s[0].text_color, Color::new("Black"));
     }
 
     #[test]
@@ -311,8 +326,8 @@ mod tests {
             .unwrap();
         assert_eq!(s[0].code, "269226");
         assert_eq!(s[0].dimensions, "80x25".parse::<Dimensions>().unwrap());
-        assert_eq!(s[0].material, Material::PVC);
-        assert_eq!(s[0].text_color, Color::Black);
+        assert_eq!(s[0].material, Material::new("PVC"));
+        assert_eq!(s[0].text_color, Color::new("Black"));
     }
 
     #[test]
@@ -321,8 +336,8 @@ mod tests {
             .unwrap();
         assert_eq!(s[0].code, "268175");
         assert_eq!(s[0].dimensions, "50x30".parse::<Dimensions>().unwrap());
-        assert_eq!(s[0].material, Material::PVCR);
-        assert_eq!(s[0].text_color, Color::Black);
+        assert_eq!(s[0].material, Material::new("PVC R"));
+        assert_eq!(s[0].text_color, Color::new("Black"));
     }
 
     #[test]
@@ -347,8 +362,8 @@ mod tests {
                 _ => unreachable!(),
             }
 
-            assert_eq!(s.material, Material::Paper);
-            assert_eq!(s.text_color, Color::Green);
+            assert_eq!(s.material, Material::new("PAPER"));
+            assert_eq!(s.text_color, Color::new("Green"));
         }
     }
 
@@ -358,9 +373,9 @@ mod tests {
             "234191_AV CLEAN GEL TUBE 200ML + RANDOM LENGHT_50X50_PVC_R_OK_PF",
         )
         .unwrap();
-        let error = ParseStickerError::MissingCode(
-            "AV CLEAN GEL TUBE 200ML + RANDOM LENGTH_60X60_PVC_R_OK_PF".into(),
-        );
+        let error = ParseStickerError::MissingCode(ErrorContext::for_name(
+            "AV CLEAN GEL TUBE 200ML + RANDOM LENGTH_60X60_PVC_R_OK_PF",
+        ));
 
         let result =
             parser::try_infering_code_by_description_similiarity_measure(&error, &existing, 0.93);
@@ -374,9 +389,9 @@ mod tests {
             "234191_AV CLEAN GEL TUBE 200ML + RANDOM LENGTH_50X50_PVC_R_OK_PF",
         )
         .unwrap();
-        let error = ParseStickerError::MissingCode(
-            "AV CLEAN GEL TUBE 200ML + RANDOM LENGT_70X40_PVC_R_OK_PF".into(),
-        );
+        let error = ParseStickerError::MissingCode(ErrorContext::for_name(
+            "AV CLEAN GEL TUBE 200ML + RANDOM LENGT_70X40_PVC_R_OK_PF",
+        ));
 
         let result =
             parser::try_infering_code_by_description_similiarity_measure(&error, &existing, 0.93);
@@ -390,9 +405,9 @@ mod tests {
             "234191_AV CLEAN GEL TUBE 200ML + RANDOM LENGTH_50X50_PVC_R_OK_PF",
         )
         .unwrap();
-        let error = ParseStickerError::MissingCode(
-            "AV CLEAN GEL TUBE 200ML + RANDOM LENTGH_50X50_PVC_R_OK_PF".into(),
-        );
+        let error = ParseStickerError::MissingCode(ErrorContext::for_name(
+            "AV CLEAN GEL TUBE 200ML + RANDOM LENTGH_50X50_PVC_R_OK_PF",
+        ));
 
         let result =
             parser::try_infering_code_by_description_similiarity_measure(&error, &existing, 0.93);
@@ -406,9 +421,9 @@ mod tests {
             "234191_AV CLEAN GEL TUBE 200ML + RANDOM LENGTH_50X50_PVC_R_OK_PF",
         )
         .unwrap();
-        let error = ParseStickerError::MissingCode(
-            "AV CLEAN GEL TUBE 200ML + RANDO LENTG_50X50_PVC_R_OK_PF".into(),
-        );
+        let error = ParseStickerError::MissingCode(ErrorContext::for_name(
+            "AV CLEAN GEL TUBE 200ML + RANDO LENTG_50X50_PVC_R_OK_PF",
+        ));
 
         let result =
             parser::try_infering_code_by_description_similiarity_measure(&error, &existing, 0.93);
@@ -421,85 +436,186 @@ mod tests {
             "234191_AV CLEAN GEL TUBE 200ML + RANDOM LENGTH_50X50_PVC_R_OK_PF",
         )
         .unwrap();
-        let error =
-            ParseStickerError::MissingCode("FACE WASH FOAM FOR MEN_50X50_PVC_R_OK_PF".into());
+        let error = ParseStickerError::MissingCode(ErrorContext::for_name(
+            "FACE WASH FOAM FOR MEN_50X50_PVC_R_OK_PF",
+        ));
 
         let result =
             parser::try_infering_code_by_description_similiarity_measure(&error, &existing, 0.93);
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_error_span_for_unofficial_dimensions() {
+        let name = "7099_LRS_НЕЖЕН САПУН_60X41_PVC_R";
+        let error = Sticker::parse_stickers(name).unwrap_err();
+
+        assert_eq!(error.sub_parser(), SubParser::Dimensions);
+        assert_eq!(error.context().split().1, "60X41");
+        assert_eq!(error.context().rejected, vec!["60X41".to_string()]);
+        assert_eq!(error.marked_name(), "7099_LRS_НЕЖЕН САПУН_[60X41]_PVC_R");
+    }
+
+    #[test]
+    fn test_error_span_for_missing_code() {
+        let error = Sticker::parse_stickers("AV_CLEAN GEL_50X50_PVC_R").unwrap_err();
+
+        assert_eq!(error.sub_parser(), SubParser::Code);
+        assert_eq!(error.context().split().1, "AV");
+        assert!(error.path().is_none());
+    }
+
+    #[test]
+    fn test_variants() {
+        let promo = Sticker::parse_stickers("234191_AV CLEAN GEL TUBE 200ML_50X50_PVC_R_PROMO_OK")
+            .unwrap()
+            .remove(0);
+        assert_eq!(promo.variant, Variant::new("PROMO"));
+        assert_eq!(promo.description, "AV CLEAN GEL TUBE 200ML");
+
+        // a product named TESTER is not a tester sticker
+        let regular = Sticker::parse_stickers("234192_SKIN TESTER KIT_50X50_PVC_R_OK")
+            .unwrap()
+            .remove(0);
+        assert!(regular.variant.is_regular());
+
+        let refill =
+            Sticker::parse_stickers("261776_AV HYALURON ACTIVE B3 REFILL 50ML_50X22_PVC_OK_PF")
+                .unwrap()
+                .remove(0);
+        assert_eq!(refill.variant, Variant::new("REFILL"));
+
+        let regular = Sticker::parse_stickers("234191_AV CLEAN GEL TUBE 200ML_60X40_PVC_R_OK")
+            .unwrap()
+            .remove(0);
+        let stickers = vec![regular.clone(), promo.clone()];
+        let order = |description: &str| Order {
+            code: "234191".to_string(),
+            amount: 10,
+            description: description.to_string(),
+            variant: Variant::in_description(description),
+            original_code: None,
+            origins: Vec::new(),
+        };
+        assert_eq!(
+            order("AV CLEAN GEL PROMO").matching_stickers(&stickers),
+            vec![&promo]
+        );
+        assert_eq!(
+            order("AV CLEAN GEL").matching_stickers(&stickers),
+            vec![&regular]
+        );
+        // no tester artwork, the order is not made with the other stickers of the code
+        assert!(order("AV CLEAN GEL TESTER")
+            .matching_stickers(&stickers)
+            .is_empty());
+
+        // a product named TESTER is not a tester order either
+        assert!(order("TESTER KIT CREAM").variant.is_regular());
+        assert_eq!(order("AV CLEAN GEL TESTER").variant, Variant::new("TESTER"));
+        assert_eq!(Variant::from_cell("promo"), Variant::new("PROMO"));
+        assert!(Variant::from_cell(" ").is_regular());
+    }
+
+    #[test]
+    fn test_status_flags() {
+        let status = |name: &str| Sticker::parse_stickers(name).unwrap()[0].status;
+
+        assert_eq!(
+            status("234191_AV CLEAN GEL TUBE 200ML_50X50_PVC_R_OK_PF").to_string(),
+            "OK PF"
+        );
+        assert_eq!(
+            status("207873_BOX SPRAY ETA COLLECT_3X50ML_40X100_40X27_DV.ST.PVC_R_OK_PF")
+                .to_string(),
+            "OK PF DV"
+        );
+        assert_eq!(
+            status("538752_AD DERMALIBUR CR BAR 100ML_40X100&40X45_DVA STIKERA_PVC_OK").to_string(),
+            "OK DV"
+        );
+        // the 1ST and 2ST sticker numbers are not statuses
+        assert_eq!(
+            status("254285_DU ANACAPS EXPERT 30CAPS_1ST 68X40_2ST 68X40_PAPER_BLUE_PF").to_string(),
+            "PF"
+        );
+        assert!(status("7099_LRS_НЕЖЕН САПУН ОБОГАТЕН С МАСЛА_60X40_PVC_R (2)").is_empty());
+        // a lone ST is an abbreviation, not a double sticker
+        assert!(status("234191_CREAM ST IVAN_60X40_PVC").is_empty());
+        assert!(parser::extract_status("CREAM ST IVAN_60X40_PVC_ST ANNA").is_empty());
+    }
+
     #[test]
     fn test_double_sticker_dimensions_and_materials_colors() {
-        use Color::*;
-        use Material::*;
+        let m = Material::new;
+        let c = Color::new;
 
         let test_cases = vec![
             (
                 "209989_DU DENSIAGE DOBAVKA 30TABL_30X101_45X59_PVC_R_SLV",
                 vec!["30x101", "45x59"],
-                vec![PVCRSLV, PVCRSLV],
-                vec![Black, Black],
+                vec![m("PVC R SLV"), m("PVC R SLV")],
+                vec![c("Black"), c("Black")],
             ),
             (
                 "207073 _RF VITALFAN SOL 30K_36X73_ 58X75_PAPER GREEN_DV_ST",
                 vec!["36x73", "58x75"],
-                vec![Paper, Paper],
-                vec![Green, Green],
+                vec![m("PAPER"), m("PAPER")],
+                vec![c("Green"), c("Green")],
             ),
             (
                 "254285_DU ANACAPS EXPERT 30CAPS_1ST 68X40_2ST 68X40_PAPER_BLUE_OK_PF",
                 vec!["68x40", "68x40"],
-                vec![Paper, Paper],
-                vec![Blue, Blue],
+                vec![m("PAPER"), m("PAPER")],
+                vec![c("Blue"), c("Blue")],
             ),
             (
                 "211949_EL SD GELULE MINCEUR TABLETES_45X102_45X40_PVC",
                 vec!["45x40"],
-                vec![PVC],
-                vec![Black],
+                vec![m("PVC")],
+                vec![c("Black")],
             ),
             (
                 "207687_AD HYDRALBA UV RICH CR 40ML_70X40_PVC&40X45_PVC R_OK",
                 vec!["70x40", "40x45"],
-                vec![PVC, PVCR],
-                vec![Black, Black],
+                vec![m("PVC"), m("PVC R")],
+                vec![c("Black"), c("Black")],
             ),
             (
                 "254310_DU ANACAPS REACTIV GEL 30U_40X68_42X50_PAPER BLUE_DB ST_PF",
                 vec!["40x68", "42x50"],
-                vec![Paper, Paper],
-                vec![Blue, Blue],
+                vec![m("PAPER"), m("PAPER")],
+                vec![c("Blue"), c("Blue")],
             ),
             (
                 "538752_AD DERMALIBUR CR BAR 100ML_40X100&40X45_DVA STIKERA_PVC_OK",
                 vec!["40x100", "40x45"],
-                vec![PVC, PVC],
-                vec![Black, Black],
+                vec![m("PVC"), m("PVC")],
+                vec![c("Black"), c("Black")],
             ),
             (
                 "515134_AD EXOMEGA DEFI 200ML_40X100_40X45_ДВОЕН СТИКЕР_PVC_OK",
                 vec!["40x100", "40x45"],
-                vec![PVC, PVC],
-                vec![Black, Black],
+                vec![m("PVC"), m("PVC")],
+                vec![c("Black"), c("Black")],
             ),
             (
                 "207686_AD HYDRALBA UV LEGERE TUBE 40ML_40X70_PVC_&_40X45_PVC_R_OK",
                 vec!["40x70", "40x45"],
-                vec![PVC, PVCR],
-                vec![Black, Black],
+                vec![m("PVC"), m("PVC R")],
+                vec![c("Black"), c("Black")],
             ),
             (
                 "207873_BOX SPRAY ETA COLLECT_3X50ML_40X100_40X27_DV.ST.PVC_R_OK_PF",
                 vec!["40x100", "40x27"],
-                vec![PVCR, PVCR],
-                vec![Black, Black],
+                vec![m("PVC R"), m("PVC R")],
+                vec![c("Black"), c("Black")],
             ),
             (
                 "205475_RF VITALFAN PROGR SINGLE 30K_58X75_40X68_PAPER GREEN_DVOEN STIKER",
                 vec!["58x75", "40x68"],
-                vec![Paper, Paper],
-                vec![Green, Green],
+                vec![m("PAPER"), m("PAPER")],
+                vec![c("Green"), c("Green")],
             ),
         ];
 
@@ -0,0 +1,72 @@
+#[cfg(test)]
+mod tests {
+    use order_processor::structs::{
+        color::Color,
+        material::Material,
+        vocabulary::{Vocabulary, VocabularyError},
+    };
+
+    const VOCABULARY: &str = "\
+# test vocabulary
+material VINYL MATTE; group=VINYL; excel_color=00FF00; pattern=VINYL[_ ]+MATTE
+material VINYL; group=VINYL; alias=VINYL; alias=VNL
+material KRAFT; alias=KRAFT; anywhere
+
+color Gold; excel_color=FFD700; alias=GOLD
+color White; alias=WHITE; default
+";
+
+    #[test]
+    fn test_longest_material_match() {
+        let vocabulary = Vocabulary::parse(VOCABULARY).unwrap();
+
+        let (entry, span) = vocabulary.find_material("_VINYL_MATTE_OK").unwrap();
+        assert_eq!(entry.material, Material::new("VINYL MATTE"));
+        assert_eq!(entry.group, "VINYL");
+        assert_eq!(entry.excel_color, Some(0x00FF00));
+        assert_eq!(span, 1..12);
+
+        let (entry, _) = vocabulary.find_material("VNL").unwrap();
+        assert_eq!(entry.material, Material::new("VINYL"));
+        // a material has to be followed by a separator
+        assert!(vocabulary.find_material("VINYLS").is_none());
+        assert_eq!(
+            vocabulary
+                .find_material_anywhere("123_KRAFTBAG_40X40")
+                .map(|entry| entry.material.clone()),
+            Some(Material::new("KRAFT"))
+        );
+        assert_eq!(vocabulary.groups(), vec!["VINYL", "KRAFT"]);
+    }
+
+    #[test]
+    fn test_colors() {
+        let vocabulary = Vocabulary::parse(VOCABULARY).unwrap();
+
+        let (entry, _) = vocabulary.find_color("_VINYL_GOLD").unwrap();
+        assert_eq!(entry.color, Color::new("Gold"));
+        assert_eq!(vocabulary.default_color(), Some(&Color::new("White")));
+    }
+
+    #[test]
+    fn test_malformed_lines() {
+        let text = "material PVC; alias=PVC\nmaterial; alias=X\ncolor Red; excel_color=XYZ; alias=RED\nsize A4; alias=A4\nmaterial PAPER\n";
+
+        let Err(VocabularyError::MalformedLines(lines)) = Vocabulary::parse(text) else {
+            panic!("expected malformed lines");
+        };
+        assert_eq!(
+            lines.iter().map(|(line, _)| *line).collect::<Vec<_>>(),
+            vec![2, 3, 4, 5]
+        );
+    }
+
+    #[test]
+    fn test_builtin_vocabulary() {
+        let vocabulary = Vocabulary::builtin();
+
+        assert_eq!(vocabulary.groups(), vec!["PVC", "PAPER"]);
+        assert_eq!("PVC_R_SLV".parse::<Material>().unwrap().group(), "PVC");
+        assert_eq!(Color::default(), Color::new("Black"));
+    }
+}
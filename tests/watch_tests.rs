@@ -0,0 +1,49 @@
+#[cfg(test)]
+mod tests {
+    use order_processor::{
+        structs::{order::Order, sticker::Sticker, variant::Variant},
+        watch::OrderCoverage,
+    };
+    use std::collections::HashMap;
+
+    fn order(code: &str) -> Order {
+        Order {
+            code: code.to_string(),
+            amount: 1,
+            description: String::new(),
+            variant: Variant::regular(),
+            original_code: None,
+            origins: Vec::new(),
+        }
+    }
+
+    fn stickers(code: &str) -> HashMap<String, Vec<Sticker>> {
+        let stickers = Sticker::parse_stickers(&format!("{code}_SOAP_60X40_PVC_R")).unwrap();
+        HashMap::from([(code.to_string(), stickers)])
+    }
+
+    #[test]
+    fn test_coverage_changes() {
+        let orders = [order("100"), order("200"), order("300")];
+        let before = OrderCoverage::new(&orders, &stickers("100"));
+
+        let orders = [order("100"), order("200"), order("400")];
+        let after = OrderCoverage::new(&orders, &stickers("200"));
+
+        let changes = after.changes_since(&before);
+        assert_eq!(changes.newly_resolved, vec!["200".to_string()]);
+        assert_eq!(
+            changes.newly_missing,
+            vec!["100".to_string(), "400".to_string()]
+        );
+        assert!(after.changes_since(&after).is_empty());
+
+        // the regular stickers of a code do not resolve its PROMO order
+        let promo = Order {
+            variant: Variant::new("PROMO"),
+            ..order("200")
+        };
+        let coverage = OrderCoverage::new(&[order("200"), promo], &stickers("200"));
+        assert!(coverage.resolved.is_empty());
+    }
+}